@@ -0,0 +1,91 @@
+//! Format Amlang source and .env files.
+//!
+//! Print formatted file:             `cargo run --example amlang_fmt -- file.aml`.
+//! Format files in place:            `cargo run --example amlang_fmt -- -w envs/*.env`.
+//! Fail if files aren't formatted:   `cargo run --example amlang_fmt -- --check f.aml`.
+//!
+//! Files ending in .env are formatted a form per line, as they're read;
+//! use --env to format other files that way.
+
+use clap::{App, Arg};
+use std::path::Path;
+
+use amlang::formatter::{format, FormatMode, FormatOptions};
+
+
+fn main() -> Result<(), String> {
+    let matches = App::new("Amlang Formatter")
+        .version("0.1")
+        .about("Format Amlang source and .env files")
+        .arg(
+            Arg::with_name("write")
+                .short('w')
+                .long("write")
+                .help("Write formatted output back to files"),
+        )
+        .arg(
+            Arg::with_name("check")
+                .long("check")
+                .conflicts_with("write")
+                .help("Only report files which aren't formatted"),
+        )
+        .arg(
+            Arg::with_name("env")
+                .long("env")
+                .help("Format all files as .env files"),
+        )
+        .arg(
+            Arg::with_name("width")
+                .long("width")
+                .takes_value(true)
+                .help("Max line width"),
+        )
+        .arg(Arg::with_name("files").required(true).multiple_values(true))
+        .get_matches();
+
+    let mut options = FormatOptions::default();
+    if let Some(width) = matches.value_of("width") {
+        options.max_width = width.parse().map_err(|_| "Invalid width".to_string())?;
+    }
+
+    let mut failed = false;
+    for file in matches.values_of("files").unwrap() {
+        let path = Path::new(file);
+        options.mode =
+            if matches.is_present("env") || path.extension().is_some_and(|ext| ext == "env") {
+                FormatMode::Env
+            } else {
+                FormatMode::Source
+            };
+
+        let input = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", file, err))?;
+        let output = match format(&input, &options) {
+            Ok(output) => output,
+            Err(diagnostics) => {
+                for diagnostic in diagnostics {
+                    eprintln!("{}:{}", file, diagnostic);
+                }
+                failed = true;
+                continue;
+            }
+        };
+
+        if matches.is_present("check") {
+            if output != input {
+                println!("{}", file);
+                failed = true;
+            }
+        } else if matches.is_present("write") {
+            if output != input {
+                std::fs::write(path, output).map_err(|err| format!("{}: {}", file, err))?;
+            }
+        } else {
+            print!("{}", output);
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -1,15 +1,25 @@
 //! Basic REPL in Amlang; no special impls, single-threaded w/SimplePolicy.
 //!
-//! Run with saved state as:       `cargo run --example simple_repl`.
-//! Reset saved state and run as:  `cargo run --example simple_repl -- -r`.
+//! Run with saved state as:       `cargo run --example amlang_repl`.
+//! Reset saved state and run as:  `cargo run --example amlang_repl -- -r`.
+//! Run in admin mode as:          `cargo run --example amlang_repl -- -a`.
 //!
 //!
-//! Uses envs in the .gitignore'd examples/envs/ directory and {de,}serializes,
-//! so state is maintained between executions. Lacking a meta.env, the meta.env
-//! used for integration tests will copied over.
+//! Unlike a default Runtime, which keeps envs in memory, uses envs in the
+//! .gitignore'd examples/envs/ directory and {de,}serializes, so state is
+//! maintained between executions. Lacking a meta.env, the copy embedded in
+//! the crate is bootstrapped from.
 //!
-//! The lang env is actually the one in the top-level envs/ directory, so this
-//! can be used to make changes to the env as part of a commit.
+//! The lang & error envs are always the copies embedded in the crate, and
+//! aren't saved; changes to them go through the top-level envs/ directory.
+//!
+//! Besides Amlang input, accepts colon-prefixed commands such as :save and
+//! :describe <symbol>; see ReplCommand. Admin mode additionally accepts raw
+//! node references like ^17 or ^4^t2, as printed for undesignated nodes.
+//!
+//! Each input is recorded as an entry of history.env along with its result
+//! and session (see HistorySession), and line history is seeded from the
+//! most recent of these.
 
 use clap::{App, Arg};
 use env_logger::{Builder, Env};
@@ -17,21 +27,19 @@ use log::LevelFilter;
 use std::convert::TryFrom;
 use std::path::Path;
 
-use amlang::agent::env_policy::SimplePolicy;
-use amlang::agent::{
-    Agent, AmlangContext, AmlangInterpreter, Context, EnvManager, NullInterpreter,
-    TransformExecutor, VmInterpreter,
-};
-use amlang::env::LocalNode;
+use amlang::agent::{Agent, HistoryEntry, HistorySession, NullInterpreter};
 use amlang::error::Error;
 use amlang::parser::Parser;
 use amlang::primitive::{Node, Primitive};
 use amlang::pull_transform;
+use amlang::runtime::Runtime;
 use amlang::sexp::Sexp;
-use amlang::token::cli_stream::CliStream;
+use amlang::token::cli_stream::{Bypass, CliInput, CliStream, ReplCommands};
+use amlang::token::Token;
 
 
-const SERIALIZATION_PATH: &str = ".";
+// As many lines as the line editor keeps by default.
+const HISTORY_LINES: usize = 100;
 
 fn main() -> Result<(), String> {
     // Setup logging.
@@ -49,6 +57,12 @@ fn main() -> Result<(), String> {
                 .long("reset")
                 .help("Reset all serialized state"),
         )
+        .arg(
+            Arg::with_name("admin")
+                .short('a')
+                .long("admin")
+                .help("Accept raw node references (e.g. ^17, ^4^t2) as input"),
+        )
         .get_matches();
 
     // Use examples/envs/.
@@ -56,71 +70,68 @@ fn main() -> Result<(), String> {
         .canonicalize()
         .unwrap();
     let examples_dir = Path::new(file!()).parent().unwrap();
-    let init_options = amlang::InitOptions::IsolatedRun(
-        base_dir.join(examples_dir).join("envs"),
-        matches.is_present("reset"),
-    );
-    amlang::init(init_options).unwrap();
-
-    // Bootstrap/deserialize.
-    let mut manager = match EnvManager::<SimplePolicy>::bootstrap(SERIALIZATION_PATH) {
-        Ok(val) => val,
-        Err(err) => return Err(format!("{}", err)),
+    let env_dir = base_dir.join(examples_dir).join("envs");
+    let mut runtime = match Runtime::builder()
+        .env_dir(&env_dir)
+        .reset(matches.is_present("reset"))
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(err) => return Err(err.to_string()),
     };
+    let (agent, manager) = runtime.split_mut();
+    let source_map = agent.source_map().cloned().unwrap();
+    let source = source_map.borrow_mut().add_source("<repl>");
 
-    // Prep agent.
-    let mut pre_agent = manager.agent_mut();
-    let lang_env = pre_agent.find_env("lang.env").unwrap();
-    let amlang_context =
-        AmlangContext::load(Node::new(lang_env, LocalNode::default()), &mut pre_agent).unwrap();
-    let history_env = pre_agent.find_env("history.env").unwrap();
-    let impl_env = pre_agent.find_env("impl.env").unwrap();
-    let mut agent = pre_agent.fork(VmInterpreter::new(
-        history_env,
-        impl_env,
-        amlang_context.clone(),
-    ));
-    agent
-        .set_eval(move |frame| {
-            let mut interpreter = AmlangInterpreter::new(impl_env, amlang_context.clone());
-            if let Some(frame) = frame {
-                interpreter.eval_state.push(frame);
-            }
-            Ok(Box::new(interpreter))
-        })
-        .unwrap();
-
-    let working_env = agent.find_env("working.env").unwrap();
-    let pos = agent.jump_env(working_env);
-    agent.designation_chain_mut().push_back(pos);
-
-    // TODO(func) Rm once we sort out the deal with CliHelper holding a
-    // potentially-stale Agent copy.
-    agent
-        .designation_chain_mut()
-        .push_front(Node::new(lang_env, LocalNode::default()));
+    // Record inputs to history.env, recalling recent inputs of previous
+    // sessions.
+    let previous = match HistoryEntry::last(agent, HISTORY_LINES) {
+        Ok(entries) => match entries
+            .iter()
+            .map(|entry| manager.serialize_structure(&entry.input))
+            .collect::<Result<Vec<_>, _>>()
+        {
+            Ok(lines) => lines,
+            Err(err) => return Err(err.to_string()),
+        },
+        Err(err) => return Err(err.to_string()),
+    };
+    let session = match HistorySession::start(agent) {
+        Ok(session) => session,
+        Err(err) => return Err(err.to_string()),
+    };
 
     // Run agent.
-    let tokens = CliStream::with_helper(agent.fork(NullInterpreter::default()));
-    let sexps = pull_transform!(?unwrap
-                                tokens
-                                =>. Parser::new()
-                                =>. TransformExecutor::custom(
-                                    &mut agent,
-                                    agent_handler));
-    for _result in sexps {}
-
-    // Serialize.
-    if let Err(err) = manager.serialize_full(SERIALIZATION_PATH, ["lang.env"].into()) {
-        return Err(err.to_string());
+    let mut commands = ReplCommands::new(manager, &env_dir, ["lang.env", "error.env"].into())
+        .with_history(session);
+    let stream = CliStream::with_helper(agent.fork(NullInterpreter::default()))
+        .with_source(source_map.clone(), source)
+        .with_history(previous);
+    let tokens: Box<dyn Iterator<Item = Result<CliInput<Token>, Error>>> =
+        if matches.is_present("admin") {
+            agent.set_node_references(true);
+            Box::new(stream.admin())
+        } else {
+            Box::new(stream)
+        };
+    let inputs = pull_transform!(?unwrap
+                                 tokens
+                                 =>. Bypass::new(Parser::new().with_source(source_map, source)));
+    for input in inputs {
+        let result = match input {
+            Ok(CliInput::Amlang(sexp)) => commands.eval(agent, sexp),
+            Ok(CliInput::Command(command)) => commands.run(agent, command),
+            Err(err) => Err(err),
+        };
+        print_result(agent, &result, commands.trace());
     }
 
+    // Envs are saved as runtime is dropped.
     Ok(())
 }
 
-fn agent_handler(agent: &mut Agent, sexp: Sexp) -> Result<Sexp, Error> {
-    let result = agent.interpret(sexp);
-    match &result {
+fn print_result(agent: &mut Agent, result: &Result<Sexp, Error>, trace: bool) {
+    match result {
         Ok(val) => {
             print!("-> ");
             if let Ok(node) = <Node>::try_from(val) {
@@ -133,11 +144,12 @@ fn agent_handler(agent: &mut Agent, sexp: Sexp) -> Result<Sexp, Error> {
             println!("");
         }
         Err(err) => {
-            agent.print_sexp(&err.kind().reify());
+            agent.print_sexp(&err.kind().reify(agent));
             println!("");
-            agent.trace_error(err);
+            if trace {
+                agent.trace_error(err);
+            }
         }
     };
     println!("");
-    result
 }
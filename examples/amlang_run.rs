@@ -0,0 +1,124 @@
+//! Non-interactive Amlang runner, e.g. for env maintenance jobs in CI.
+//!
+//! Run a script:                  `cargo run --example amlang_run -- file.aml a b`.
+//! Run a script piped to stdin:   `cargo run --example amlang_run < file.aml`.
+//! Run without persisting envs:   `cargo run --example amlang_run -- -n file.aml`.
+//!
+//! Uses the same envs as amlang_repl (examples/envs/), or with --root the
+//! top-level envs/ directory. Arguments following the script are available
+//! to it as a list of strings named script-args.
+//!
+//! Exits with 1 if interpreting the script fails, in which case envs aren't
+//! saved, and with 2 if the runner itself couldn't be set up.
+
+use clap::{App, Arg};
+use env_logger::{Builder, Env};
+use std::path::Path;
+use std::process::exit;
+
+use amlang::agent::script;
+use amlang::runtime::Runtime;
+use amlang::stream::input::StdinReader;
+
+
+fn main() {
+    // Setup logging.
+    Builder::from_env(Env::default().default_filter_or("warn")).init();
+
+    // Parse args.
+    let matches = App::new("Amlang Runner")
+        .version("0.1")
+        .about("Run Amlang scripts non-interactively")
+        .arg(
+            Arg::with_name("no-save")
+                .short('n')
+                .long("no-save")
+                .help("Don't persist envs after running"),
+        )
+        .arg(
+            Arg::with_name("root")
+                .long("root")
+                .help("Use the top-level envs/ directory"),
+        )
+        .arg(Arg::with_name("script").help("Script to run; read from stdin if omitted or -"))
+        .arg(
+            Arg::with_name("args")
+                .multiple_values(true)
+                .help("Arguments passed to the script"),
+        )
+        .get_matches();
+
+    let script_path = matches.value_of("script").filter(|path| *path != "-");
+    if script_path.is_none() && StdinReader::is_tty() {
+        eprintln!("No script given, and stdin is a terminal");
+        exit(2);
+    }
+    let script_path = match script_path.map(|path| Path::new(path).canonicalize()) {
+        Some(Ok(path)) => Some(path),
+        Some(Err(err)) => {
+            eprintln!("{}: {}", matches.value_of("script").unwrap(), err);
+            exit(2);
+        }
+        None => None,
+    };
+
+    // Use examples/envs/, as amlang_repl does.
+    // Saving is explicit below, once args are unbound.
+    let mut builder = Runtime::builder().save_on_drop(false);
+    if matches.is_present("root") {
+        builder = builder.root_run();
+    } else {
+        let base_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .canonicalize()
+            .unwrap();
+        let examples_dir = Path::new(file!()).parent().unwrap();
+        builder = builder.env_dir(base_dir.join(examples_dir).join("envs"));
+    }
+    let mut runtime = match builder.build() {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            eprintln!("{}", err);
+            exit(2);
+        }
+    };
+    let agent = runtime.agent_mut();
+
+    let args = matches.values_of("args").into_iter().flatten();
+    if let Err(err) = script::bind_args(agent, args) {
+        eprintln!("{}", err);
+        exit(2);
+    }
+
+    // Run script.
+    let result = match &script_path {
+        Some(path) => script::run_file(agent, path),
+        None => script::run(agent, "<stdin>", StdinReader::new()),
+    };
+    if let Err(err) = result {
+        let mut stderr = std::io::stderr();
+        if agent
+            .write_sexp(&mut stderr, &err.kind().reify(agent))
+            .is_err()
+        {
+            eprint!("{}", err);
+        }
+        eprintln!();
+        // Keep the trace with the error it explains.
+        if let Err(err) = agent.write_trace(&mut stderr, &err) {
+            eprintln!("trace error: {}", err);
+        }
+        exit(1);
+    }
+
+    // Serialize, leaving out the args.
+    if !matches.is_present("no-save") {
+        if let Err(err) = script::unbind_args(runtime.agent_mut()) {
+            eprintln!("{}", err);
+            exit(2);
+        }
+        if let Err(err) = runtime.save() {
+            eprintln!("{}", err);
+            exit(2);
+        }
+    }
+}
@@ -5,7 +5,7 @@ use env_logger::{Builder, Env};
 use log::LevelFilter;
 
 use amlang::agent::env_policy::SimplePolicy;
-use amlang::agent::{AmlangContext, Context, EnvManager};
+use amlang::agent::{AmlangContext, Context, EnvManager, EnvSources};
 use amlang::env::LocalNode;
 use amlang::primitive::Node;
 
@@ -25,7 +25,8 @@ fn main() -> Result<(), String> {
 
     amlang::init(amlang::InitOptions::RootRun).unwrap();
 
-    let mut manager = match EnvManager::<SimplePolicy>::bootstrap(SERIALIZATION_PATH) {
+    // Read lang & error envs from the source tree, as they're saved back to it.
+    let mut manager = match EnvManager::<SimplePolicy>::bootstrap_from(&EnvSources::root_run()) {
         Ok(val) => val,
         Err(err) => return Err(format!("{}", err)),
     };
@@ -1,17 +1,22 @@
 use colored::*;
 use derivative::Derivative;
-use log::debug;
+use log::{debug, warn};
 use serde::{Deserialize, Serialize};
 
 use std::cell::RefCell;
 use std::collections::VecDeque;
 use std::convert::TryFrom;
-use std::io::{self, stdout, BufWriter};
+use std::io::{self, Write};
+use std::path::Path;
 use std::rc::Rc;
 
 use super::agent_frames::{EnvFrame, ExecFrame};
+use super::agent_io::AgentIo;
 use super::context::MetaEnvContext;
+use super::error_context::ErrorContext;
+use super::history_context::HistoryContext;
 use super::interpreter::{InterpreterState, NullInterpreter};
+use super::print_settings::PrintSettings;
 use super::{BaseDeserializer, BaseSerializer};
 use crate::agent::lang_error::LangError;
 use crate::continuation::Continuation;
@@ -21,8 +26,10 @@ use crate::env::LocalNode;
 use crate::env::{EnvObject, TripleSet};
 use crate::error::Error;
 use crate::primitive::prelude::*;
+use crate::primitive::symbol_policies::policy_env_serde;
 use crate::primitive::table::Table;
 use crate::sexp::{HeapSexp, Sexp};
+use crate::span::SharedSourceMap;
 
 
 #[derive(Derivative)]
@@ -32,13 +39,30 @@ pub struct Agent {
     exec_state: Continuation<ExecFrame>,
     interpreter_state: Continuation<Rc<RefCell<Box<dyn InterpreterState>>>>,
     designation_chain: VecDeque<Node>,
+    symbol_grammar: SymbolGrammar,
+    node_references: bool,
 
     meta: MetaEnv,
     pub(super) context_metaenv: MetaEnvContext,
+    pub(super) context_error: ErrorContext,
+    context_history: Option<HistoryContext>,
+    source_map: Option<SharedSourceMap>,
+    print_settings: PrintSettings,
+    #[derivative(Debug = "ignore")]
+    io: AgentIo,
 
     #[derivative(Debug = "ignore")]
     gen_eval_interpreter:
         Option<Box<dyn Fn(Option<SymNodeTable>) -> Result<Box<dyn InterpreterState>, Error>>>,
+    #[derivative(Debug = "ignore")]
+    env_factory: Option<Rc<EnvFactory>>,
+}
+
+/// Creates envs on behalf of Agents, through their EnvManager's policy.
+/// Shared between an Agent and its forks, which share created envs through
+/// their MetaEnv.
+pub(super) struct EnvFactory {
+    create: Box<dyn Fn(LocalNode) -> Box<EnvObject>>,
 }
 
 impl Agent {
@@ -53,22 +77,65 @@ impl Agent {
                 NullInterpreter::default(),
             )))),
             designation_chain: VecDeque::new(),
+            symbol_grammar: SymbolGrammar::standard().clone(),
+            node_references: false,
 
             meta,
             context_metaenv: context,
+            context_error: ErrorContext::placeholder(),
+            context_history: None,
+            source_map: None,
+            print_settings: PrintSettings::default(),
+            io: AgentIo::default(),
 
             gen_eval_interpreter: None,
+            env_factory: None,
         }
     }
 
     pub fn fork<I: InterpreterState + 'static>(&self, base_interpreter: I) -> Self {
         let mut res = Self::new(self.pos(), self.meta.clone(), self.context_metaenv.clone());
+        res.context_error = self.context_error.clone();
+        res.context_history = self.context_history.clone();
         res.interpreter_state =
             Continuation::new(Rc::new(RefCell::new(Box::new(base_interpreter))));
         res.designation_chain = self.designation_chain.clone();
+        res.symbol_grammar = self.symbol_grammar.clone();
+        res.node_references = self.node_references;
+        res.source_map = self.source_map.clone();
+        res.print_settings = self.print_settings.clone();
+        res.io = self.io.clone();
+        res.env_factory = self.env_factory.clone();
         res
     }
 
+    /// Track source spans of interpreted Nodes in map, so that errors can
+    /// point back at the source text. See Parser::with_source.
+    pub fn set_source_map(&mut self, map: SharedSourceMap) {
+        self.source_map = Some(map);
+    }
+
+    pub fn source_map(&self) -> Option<&SharedSourceMap> {
+        self.source_map.as_ref()
+    }
+
+    pub fn print_settings(&self) -> &PrintSettings {
+        &self.print_settings
+    }
+
+    pub fn print_settings_mut(&mut self) -> &mut PrintSettings {
+        &mut self.print_settings
+    }
+
+    /// Output & input of the Agent, shared with its forks.
+    pub fn io(&self) -> &AgentIo {
+        &self.io
+    }
+
+    pub fn set_io(&mut self, io: AgentIo) {
+        self.io = io;
+    }
+
     /// If the VmInterpreter or some other base Interpreter runs into
     /// something like (eval ...), it won't be able to evaluate it on
     /// its own. This provides such a "lower" Interpreter with the
@@ -109,6 +176,37 @@ impl Agent {
         }
     }
 
+    /// Grammar of identifiers read by or on behalf of the Agent, e.g. by
+    /// deserialization & REPLs.
+    pub fn symbol_grammar(&self) -> &SymbolGrammar {
+        &self.symbol_grammar
+    }
+
+    pub fn set_symbol_grammar(&mut self, grammar: SymbolGrammar) {
+        self.symbol_grammar = grammar;
+    }
+
+    /// Whether designate resolves raw node references, as in admin REPLs.
+    /// Off by default, so such symbols are otherwise resolved by name like
+    /// any other. See Agent::node_reference.
+    pub fn set_node_references(&mut self, enabled: bool) {
+        self.node_references = enabled;
+    }
+
+    pub fn error_context(&self) -> &ErrorContext {
+        &self.context_error
+    }
+
+    /// Context of the env in which history entries are recorded, if any.
+    /// See HistorySession.
+    pub fn history_context(&self) -> Option<&HistoryContext> {
+        self.context_history.as_ref()
+    }
+
+    pub fn set_history_context(&mut self, context: HistoryContext) {
+        self.context_history = Some(context);
+    }
+
     pub fn interpreter_state(&self) -> &Continuation<Rc<RefCell<Box<dyn InterpreterState>>>> {
         &self.interpreter_state
     }
@@ -359,10 +457,45 @@ impl Agent {
         err!(self, LangError::UnboundSymbol(name.clone()))
     }
 
+    /// Node referred to by symbol if it's a raw node or triple reference,
+    /// as written in serialized envs (e.g. ^17, ^t2, ^4^17, ^4^t2). Local
+    /// references are relative to the current env.
+    ///
+    /// The Node is not checked for existence.
+    pub fn node_reference(&self, symbol: &Symbol) -> Option<Node> {
+        // Spare identifiers the full admin policy.
+        if !symbol.as_str().starts_with('^') {
+            return None;
+        }
+        let node = match policy_env_serde(symbol.as_str(), &self.symbol_grammar).ok()? {
+            AdminSymbolInfo::Identifier => return None,
+            AdminSymbolInfo::LocalNode(node) => node.globalize(self),
+            AdminSymbolInfo::LocalTriple(idx) => {
+                self.env().triple_from_index(idx).node().globalize(self)
+            }
+            AdminSymbolInfo::GlobalNode(env, node) => Node::new(env, node),
+            AdminSymbolInfo::GlobalTriple(env, idx) => {
+                Node::new(env, self.access_env(env)?.triple_from_index(idx).node())
+            }
+        };
+        Some(node)
+    }
+
     pub fn designate(&self, designator: Primitive) -> Result<Sexp, Error> {
         match designator {
             // Symbol -> Node
-            Primitive::Symbol(symbol) => Ok(self.resolve_name(&symbol)?.into()),
+            //
+            // Raw node references only when enabled, regardless of the
+            // policy the symbol was made under.
+            Primitive::Symbol(symbol) => match self
+                .node_references
+                .then(|| self.node_reference(&symbol))
+                .flatten()
+            {
+                Some(node) if self.node_exists(node) => Ok(node.into()),
+                Some(_) => err!(self, LangError::UnboundSymbol(symbol)),
+                None => Ok(self.resolve_name(&symbol)?.into()),
+            },
             // Node -> Structure
             Primitive::Node(node) => {
                 if let Some(structure) = self
@@ -385,13 +518,24 @@ impl Agent {
             }
             // Reify reflectives.
             Primitive::Procedure(proc) => Ok(*self.reify(&proc)?),
-            Primitive::SymNodeTable(table) => Ok(*self.reify(&table)?),
-            Primitive::LocalNodeTable(table) => Ok(*self.reify(&table)?),
+            Primitive::SymNodeTable(table) => Ok(table.to_form()),
+            Primitive::SymSexpTable(table) => Ok(table.to_form()),
+            Primitive::LocalNodeTable(table) => Ok(table.to_form()),
             // Base case for self-designating.
             _ => Ok(designator.into()),
         }
     }
 
+    fn node_exists(&self, node: Node) -> bool {
+        match self.access_env(node.env()) {
+            Some(env) => {
+                env.node_as_triple(node.local()).is_some()
+                    || env.all_nodes().contains(&node.local())
+            }
+            None => false,
+        }
+    }
+
     pub fn define(&mut self, structure: Option<Sexp>) -> Result<Node, Error> {
         let local = self.env_mut().insert_node(structure);
         Ok(self.globalize(local))
@@ -456,7 +600,7 @@ impl Agent {
                     amlang_node!(tell, self.context()),
                 )?;
                 // Only allow insertion to continue if the handler returns true.
-                if res != amlang_node!(t, self.context()).into() {
+                if res != true.into() {
                     return err!(
                         self,
                         LangError::RejectedTriple(list!(subject, predicate, object), res)
@@ -471,10 +615,12 @@ impl Agent {
         self.jump(original_pos);
         // Ensuring this triple didn't already exist assures that we can call
         // .local() here without any checks.
-        let triple =
-            self.env_mut()
-                .insert_triple(subject.local(), predicate.local(), object.local());
-        Ok(triple.node().globalize(&self))
+        let triple = self.access_env_mut(env).unwrap().insert_triple(
+            subject.local(),
+            predicate.local(),
+            object.local(),
+        );
+        Ok(Node::new(env, triple.node()))
     }
 
     pub fn ask(
@@ -603,6 +749,14 @@ impl Agent {
     }
 
     pub fn find_env<S: AsRef<str>>(&self, s: S) -> Option<LocalNode> {
+        self.env_paths()
+            .into_iter()
+            .find(|(_, path)| path.as_std_path().ends_with(s.as_ref()))
+            .map(|(env, _)| env)
+    }
+
+    /// Envs listed in the meta env, along with their serialization paths.
+    pub fn env_paths(&self) -> Vec<(LocalNode, LangPath)> {
         let meta = self.meta.base();
         let triples = self
             .ask_from(
@@ -616,17 +770,111 @@ impl Agent {
             )
             .unwrap()
             .triples();
+        let mut res = vec![];
         for triple in triples {
             let object_node = meta.triple_object(triple);
             let entry = meta.entry(object_node);
             let object = entry.structure();
             if let Ok(path) = <&LangPath>::try_from(object) {
-                if path.as_std_path().ends_with(s.as_ref()) {
-                    return Some(meta.triple_subject(triple));
-                }
+                res.push((meta.triple_subject(triple), path.clone()));
             }
         }
-        None
+        res
+    }
+
+    /// Create an env to be serialized to path, listing it in the meta env.
+    ///
+    /// Only Agents of an EnvManager (or their forks) can create envs.
+    pub fn create_env<P: AsRef<Path>>(&mut self, path: P) -> Result<LocalNode, Error> {
+        let env_node = self.create_unlisted_env()?;
+        self.list_env(env_node, path);
+        Ok(env_node)
+    }
+
+    /// Create an env without a serialize path, which isn't saved unless
+    /// later listed through list_env.
+    pub fn create_unlisted_env(&mut self) -> Result<LocalNode, Error> {
+        let factory = match &self.env_factory {
+            Some(factory) => factory.clone(),
+            None => {
+                return err!(
+                    self,
+                    LangError::InvalidState {
+                        actual: "no env factory".into(),
+                        expected: "Agent of an EnvManager".into(),
+                    }
+                );
+            }
+        };
+
+        let env_node = self.meta.base_mut().insert_node(None);
+        let env = (factory.create)(env_node);
+        self.meta.insert_env(env_node, env);
+        Ok(env_node)
+    }
+
+    /// List an env created through create_unlisted_env, to be serialized to path.
+    pub fn list_env<P: AsRef<Path>>(&mut self, env_node: LocalNode, path: P) {
+        let serialize_path = *self.context_metaenv.serialize_path();
+        let path = LangPath::new(path.as_ref().to_path_buf());
+        let path_node = self.meta.base_mut().insert_node(Some(path.into()));
+        self.meta
+            .base_mut()
+            .insert_triple(env_node, serialize_path, path_node);
+    }
+
+    /// Env of the module evaluated from source, if it's been registered and
+    /// source hasn't been modified since.
+    pub fn module_env<P: AsRef<Path>>(&self, source: P) -> Option<LocalNode> {
+        let modified = modified_nanos(source.as_ref())?;
+        self.module_versions(source)
+            .into_iter()
+            .find(|(_, version)| *version == modified)
+            .map(|(env, _)| env)
+    }
+
+    /// Envs of all modules evaluated from source, including those of
+    /// versions since modified, oldest first.
+    pub fn module_envs<P: AsRef<Path>>(&self, source: P) -> Vec<LocalNode> {
+        self.module_versions(source)
+            .into_iter()
+            .map(|(env, _)| env)
+            .collect()
+    }
+
+    /// Record env as the module evaluated from source, as of its current
+    /// modification time, so that module_env can find it.
+    pub fn register_module<P: AsRef<Path>>(&mut self, env: LocalNode, source: P) {
+        let module_source = *self.context_metaenv.module_source();
+        let path = LangPath::new(source.as_ref().to_path_buf());
+        let modified = modified_nanos(source.as_ref()).unwrap_or_default();
+        let meta = self.meta.base_mut();
+        let source_node = meta.insert_node(Some(list!(path, Number::U64(modified))));
+        meta.insert_triple(env, module_source, source_node);
+    }
+
+    // Envs of modules evaluated from source, along with the modification
+    // time of source when they were.
+    fn module_versions<P: AsRef<Path>>(&self, source: P) -> Vec<(LocalNode, u64)> {
+        let meta = self.meta.base();
+        let module_source = *self.context_metaenv.module_source();
+        let mut versions = vec![];
+        for triple in meta.match_predicate(module_source).triples() {
+            let entry = meta.entry(meta.triple_object(triple));
+            let version = match break_sexp!(entry.structure().clone() => (LangPath, Number)) {
+                Ok((path, modified)) if path.as_std_path() == source.as_ref() => modified,
+                _ => continue,
+            };
+            if let Ok(modified) = u64::try_from(version) {
+                versions.push((meta.triple_subject(triple), modified));
+            }
+        }
+        versions.sort_by_key(|(env, _)| env.id());
+        versions
+    }
+
+    pub(super) fn set_env_factory(&mut self, factory: EnvFactory) {
+        self.env_factory = Some(Rc::new(factory));
     }
 
     fn get_or_create_import_table(&mut self, from_env: LocalNode) -> LocalNode {
@@ -696,87 +944,244 @@ impl Agent {
 }
 
 
+impl EnvFactory {
+    pub(super) fn new<F: Fn(LocalNode) -> Box<EnvObject> + 'static>(create: F) -> Self {
+        Self {
+            create: Box::new(create),
+        }
+    }
+}
+
+
 // Print functionality.
 impl Agent {
+    /// Write the trace of err's continuation to the Agent's output.
     pub fn trace_error(&mut self, err: &Error) {
-        if let Some(cont) = err.cont() {
-            let mut original_cont = std::mem::replace(&mut self.exec_state, cont.clone());
-            println!("");
-            println!("  --TRACE--");
+        let io = self.io.clone();
+        if let Err(err) = io.write(|mut w| self.write_trace(&mut w, err)) {
+            warn!("trace_error error: {:?}", err);
+        }
+    }
+
+    /// Write the trace of err's continuation, as trace_error would.
+    pub fn write_trace<W: std::io::Write>(
+        &mut self,
+        w: &mut W,
+        err: &Error,
+    ) -> std::io::Result<()> {
+        let cont = match err.cont() {
+            Some(cont) => cont,
+            None => return Ok(()),
+        };
+        let mut original_cont = std::mem::replace(&mut self.exec_state, cont.clone());
+        let res = (|| {
+            writeln!(w)?;
+            writeln!(w, "  --TRACE--")?;
             let end = cont.depth() - 1;
             for (i, frame) in cont.iter().enumerate() {
                 if i == end {
                     break;
                 }
                 self.exec_state_mut().pop();
-                print!("   {})  ", i);
-                self.print_sexp(&frame.context().into());
-                println!("");
+                write!(w, "   {})  ", i)?;
+                self.write_sexp(w, &frame.context().into())?;
+                writeln!(w)?;
+                if let Some(map) = &self.source_map {
+                    let map = map.borrow();
+                    if let Some(excerpt) = map
+                        .node_span(frame.context())
+                        .and_then(|span| map.render(&span))
+                    {
+                        writeln!(w, "{}", excerpt)?;
+                    }
+                }
             }
-            std::mem::swap(&mut self.exec_state, &mut original_cont);
-        }
+            Ok(())
+        })();
+        std::mem::swap(&mut self.exec_state, &mut original_cont);
+        res
     }
 
+    /// Write structure to the Agent's output.
     pub fn print_sexp(&self, structure: &Sexp) {
-        let mut writer = BufWriter::new(stdout());
-        if let Err(err) = self.write_sexp(&mut writer, structure, 0, true) {
-            println!("print_sexp error: {:?}", err);
+        if let Err(err) = self.io.write(|mut w| self.write_sexp(&mut w, structure)) {
+            warn!("print_sexp error: {:?}", err);
         }
     }
 
-    // TODO(func) Make show_redirects & paren_color configurable & introspectable.
-    fn write_sexp<W: std::io::Write>(
+    /// Write structure as print_sexp would, according to print_settings.
+    pub fn write_sexp<W: std::io::Write>(
+        &self,
+        w: &mut W,
+        structure: &Sexp,
+    ) -> std::io::Result<()> {
+        let show_redirects = self.print_settings.show_redirects;
+        self.write_sexp_at(w, structure, 0, Some(0), show_redirects)
+    }
+
+    // Write structure starting at col, breaking lists which don't fit
+    // within max_width across lines. A col of None writes everything on
+    // one line.
+    fn write_sexp_at<W: std::io::Write>(
         &self,
         w: &mut W,
         structure: &Sexp,
         depth: usize,
+        col: Option<usize>,
         show_redirects: bool,
     ) -> std::io::Result<()> {
-        fn paren_color(depth: usize) -> (u8, u8, u8) {
-            match depth % 6 {
-                0 => (0, 255, 204),
-                1 => (204, 51, 0),
-                2 => (153, 255, 102),
-                3 => (153, 102, 255),
-                4 => (255, 255, 102),
-                _ => (255, 179, 179),
+        let settings = &self.print_settings;
+        if let (Some(col), Some(max_width), Sexp::Cons(_)) = (col, settings.max_width, structure) {
+            let mut flat = vec![];
+            self.write_sexp_at(&mut flat, structure, depth, None, show_redirects)?;
+            if end_col(&flat, col) <= max_width {
+                return w.write_all(&flat);
             }
+            return self.write_broken_list(w, structure, depth, col, show_redirects);
         }
 
-        // Any list longer than this will simply be suffixed with "..." after these
-        // many elements.
-        const MAX_LENGTH: usize = 64;
-        const MAX_DEPTH: usize = 16;
-
         structure.write(
             w,
             depth,
             &mut |writer, primitive, depth| {
-                self.write_primitive(writer, primitive, depth, show_redirects)
-            },
-            &mut |writer, paren, depth| {
-                let (r, g, b) = paren_color(depth);
-                write!(writer, "{}", paren.truecolor(r, g, b))
+                self.write_primitive(writer, primitive, depth, col, show_redirects)
             },
+            &mut |writer, paren, depth| self.write_paren(writer, paren, depth),
             &mut |writer, _depth| write!(writer, " "),
-            Some(MAX_LENGTH),
-            Some(MAX_DEPTH),
+            settings.max_length,
+            settings.max_depth,
         )
     }
 
+    // Lists headed by a symbol keep their first arg on the first line and
+    // indent the rest; other lists align their elements.
+    fn write_broken_list<W: std::io::Write>(
+        &self,
+        w: &mut W,
+        structure: &Sexp,
+        depth: usize,
+        col: usize,
+        show_redirects: bool,
+    ) -> std::io::Result<()> {
+        if let Some(prefix) = structure.quote_prefix() {
+            write!(w, "{}", prefix)?;
+            let datum = structure.iter().nth(1).unwrap().0;
+            let col = col + prefix.len();
+            return self.write_sexp_at(w, datum, depth + 1, Some(col), show_redirects);
+        }
+        if let Some(max) = self.print_settings.max_depth {
+            if depth >= max {
+                return write!(w, "(..)");
+            }
+        }
+
+        let elems = structure.iter().collect::<Vec<_>>();
+        let headed = matches!(
+            elems.first(),
+            Some((
+                Sexp::Primitive(Primitive::Symbol(_) | Primitive::Node(_)),
+                true
+            ))
+        );
+        self.write_paren(w, "(", depth)?;
+        self.write_broken_elems(w, &elems, headed, depth, col + 1, show_redirects)?;
+        self.write_paren(w, ")", depth)
+    }
+
+    // Write elems (and proper-ness) starting at col, filling each line with
+    // as many as fit. Elems spanning multiple lines end their line, though
+    // the first arg of headed lists always shares the head's line.
+    fn write_broken_elems<W: std::io::Write>(
+        &self,
+        w: &mut W,
+        elems: &[(&Sexp, bool)],
+        headed: bool,
+        depth: usize,
+        col: usize,
+        show_redirects: bool,
+    ) -> std::io::Result<()> {
+        let settings = &self.print_settings;
+        let max_width = settings.max_width.unwrap_or(usize::MAX);
+        let rest_col = if headed {
+            col - 1 + settings.indent
+        } else {
+            col
+        };
+        let mut curr_col = col;
+        let mut prev_broken = false;
+        for (i, (elem, proper)) in elems.iter().enumerate() {
+            if let Some(max) = settings.max_length {
+                if i >= max {
+                    write!(w, " ...")?;
+                    break;
+                }
+            }
+
+            let tail = if *proper { "" } else { ". " };
+            if i > 0 {
+                let mut flat = vec![];
+                self.write_sexp_at(&mut flat, elem, depth + 1, None, show_redirects)?;
+                let fits = end_col(&flat, curr_col + 1 + tail.len()) <= max_width;
+                if (i == 1 && headed) || (fits && !prev_broken) {
+                    write!(w, " ")?;
+                    curr_col += 1;
+                } else {
+                    write!(w, "\n{}", " ".repeat(rest_col))?;
+                    curr_col = rest_col;
+                }
+            }
+            write!(w, "{}", tail)?;
+            curr_col += tail.len();
+
+            let mut buf = vec![];
+            self.write_sexp_at(&mut buf, elem, depth + 1, Some(curr_col), show_redirects)?;
+            prev_broken = buf.contains(&b'\n');
+            curr_col = end_col(&buf, curr_col);
+            w.write_all(&buf)?;
+        }
+        Ok(())
+    }
+
+    fn write_paren<W: std::io::Write>(
+        &self,
+        w: &mut W,
+        paren: &str,
+        depth: usize,
+    ) -> std::io::Result<()> {
+        match self.print_settings.paren_color(depth) {
+            Some((r, g, b)) => write!(w, "{}", paren.truecolor(r, g, b)),
+            None => write!(w, "{}", paren),
+        }
+    }
+
     fn write_primitive<W: std::io::Write>(
         &self,
         w: &mut W,
         primitive: &Primitive,
         depth: usize,
+        col: Option<usize>,
         show_redirects: bool,
     ) -> std::io::Result<()> {
         const MAX_DEPTH: usize = 16;
 
+        // Write structure after a redirect prefix.
+        let write_redirect = |w: &mut W, prefix: &str, s: &Sexp, show_redirects| {
+            write!(w, "{}", prefix)?;
+            let col = col.map(|col| col + prefix.chars().count());
+            self.write_sexp_at(w, s, depth, col, show_redirects)
+        };
         match primitive {
             Primitive::Node(node) => {
-                // Write Nodes as their designation if possible.
-                if let Some(sym) = self.lookup_name(*node) {
+                // Write Nodes as their designation if possible, falling
+                // back on the default designations of their own env.
+                let name = if self.print_settings.label_nodes {
+                    self.lookup_name(*node).or_else(|| {
+                        self.lookup_name_with(*node, &[Node::new(node.env(), LocalNode::default())])
+                    })
+                } else {
+                    None
+                };
+                if let Some(sym) = name {
                     write!(w, "{}", sym.as_str())
                 } else if let Some(triple) = self
                     .access_env(node.env())
@@ -784,7 +1189,7 @@ impl Agent {
                     .node_as_triple(node.local())
                 {
                     let s = triple.reify(self);
-                    self.write_sexp(w, &s, depth, show_redirects)
+                    self.write_sexp_at(w, &s, depth, col, show_redirects)
                 } else {
                     let s = match self.concretize(*node) {
                         Ok(structure) => structure,
@@ -792,43 +1197,99 @@ impl Agent {
                             return Err(io::Error::new(io::ErrorKind::Other, err.to_string()));
                         }
                     };
-                    if show_redirects {
-                        write!(w, "{}->", node)?;
-                    } else {
+                    if !show_redirects {
                         return write!(w, "{}", node);
                     }
 
                     // If we recurse unconditionally, cycles will cause stack
                     // overflows.
                     if s == (*node).into() || depth > MAX_DEPTH {
-                        write!(w, "{}", node)
+                        write!(w, "{}->{}", node, node)
                     } else {
-                        self.write_sexp(w, &s, depth, show_redirects)
+                        write_redirect(w, &format!("{}->", node), &s, show_redirects)
                     }
                 }
             }
             Primitive::Procedure(procedure) => {
-                if show_redirects {
-                    write!(w, "[Procedure]->")?;
-                }
                 let s = *self.reify(&procedure).unwrap();
-                self.write_sexp(w, &s, depth, true)
+                let prefix = if show_redirects { "[Procedure]->" } else { "" };
+                write_redirect(w, prefix, &s, true)
             }
             Primitive::SymNodeTable(table) => {
-                if show_redirects {
-                    write!(w, "[SymNodeTable]->")?;
-                }
-                let s = *self.reify(&table).unwrap();
-                self.write_sexp(w, &s, depth, false)
+                let prefix = if show_redirects {
+                    "[SymNodeTable]->"
+                } else {
+                    ""
+                };
+                write_redirect(w, prefix, &table.to_form(), false)
+            }
+            Primitive::SymSexpTable(table) => {
+                let prefix = if show_redirects {
+                    "[SymSexpTable]->"
+                } else {
+                    ""
+                };
+                write_redirect(w, prefix, &table.to_form(), false)
             }
             Primitive::LocalNodeTable(table) => {
-                if show_redirects {
-                    write!(w, "[LocalNodeTable]->")?;
+                let prefix = if show_redirects {
+                    "[LocalNodeTable]->"
+                } else {
+                    ""
+                };
+                write_redirect(w, prefix, &table.to_form(), false)
+            }
+            Primitive::Vector(vector) => {
+                let elems = vector.iter().map(|elem| (elem, true)).collect::<Vec<_>>();
+                let mut flat = vec![];
+                write!(flat, "[")?;
+                for (i, elem) in vector.iter().enumerate() {
+                    if i > 0 {
+                        write!(flat, " ")?;
+                    }
+                    self.write_sexp_at(&mut flat, elem, depth + 1, None, show_redirects)?;
+                }
+                write!(flat, "]")?;
+
+                match (col, self.print_settings.max_width) {
+                    (Some(col), Some(max_width)) if end_col(&flat, col) > max_width => {
+                        write!(w, "[")?;
+                        self.write_broken_elems(w, &elems, false, depth, col + 1, show_redirects)?;
+                        write!(w, "]")
+                    }
+                    _ => w.write_all(&flat),
                 }
-                let s = *self.reify(&table).unwrap();
-                self.write_sexp(w, &s, depth, false)
             }
             _ => write!(w, "{}", primitive),
         }
     }
 }
+
+
+// Column just past output written starting at col, ignoring color codes.
+fn end_col(output: &[u8], col: usize) -> usize {
+    let output = String::from_utf8_lossy(output);
+    let (last_line, col) = match output.rfind('\n') {
+        Some(i) => (&output[i + 1..], 0),
+        None => (&output[..], col),
+    };
+
+    let mut width = col;
+    let mut chars = last_line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // Skip through the end of the escape sequence.
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            width += 1;
+        }
+    }
+    width
+}
+
+// Modification time of the file at path, in nanoseconds since the UNIX epoch.
+fn modified_nanos(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    let since_epoch = modified.duration_since(std::time::UNIX_EPOCH).ok()?;
+    u64::try_from(since_epoch.as_nanos()).ok()
+}
@@ -0,0 +1,99 @@
+//! Input & output of Agents, as used by builtins like println & read-line
+//! and by error traces.
+
+use std::cell::{RefCell, RefMut};
+use std::io::{self, stdin, stdout, BufRead, Write};
+use std::rc::Rc;
+
+use crate::parser::Parser;
+use crate::primitive::symbol_policies::policy_base;
+use crate::primitive::SymbolGrammar;
+use crate::token::Tokenizer;
+
+
+pub type SharedOutput = Rc<RefCell<dyn Write>>;
+pub type SharedInput = Rc<RefCell<dyn BufRead>>;
+/// Tokenizer & Parser state of the read builtin, holding input read past the
+/// last form returned.
+pub type FormReader = (Tokenizer<()>, Parser);
+
+/// Handles which Agents write to & read from, defaulting to stdout & stdin.
+///
+/// Clones, like Agent forks, share handles. To capture output, e.g. in tests,
+/// keep a clone of the buffer written to:
+///
+///   let buffer = Rc::new(RefCell::new(Vec::<u8>::new()));
+///   agent.set_io(AgentIo::default().with_output(buffer.clone()));
+#[derive(Clone, Default)]
+pub struct AgentIo {
+    output: Option<SharedOutput>,
+    input: Option<SharedInput>,
+    // Shared like the input it buffers.
+    reader: Rc<RefCell<Option<FormReader>>>,
+}
+
+impl AgentIo {
+    pub fn with_output(mut self, output: SharedOutput) -> Self {
+        self.output = Some(output);
+        self
+    }
+
+    pub fn with_input(mut self, input: SharedInput) -> Self {
+        self.input = Some(input);
+        self.reader = Default::default();
+        self
+    }
+
+    /// Write to the output through f, flushing afterwards.
+    pub fn write<F: FnOnce(&mut dyn Write) -> io::Result<()>>(&self, f: F) -> io::Result<()> {
+        match &self.output {
+            Some(output) => {
+                let mut output = output.borrow_mut();
+                f(&mut *output)?;
+                output.flush()
+            }
+            None => {
+                let mut output = stdout().lock();
+                f(&mut output)?;
+                output.flush()
+            }
+        }
+    }
+
+    /// Read the next line from the input, without its line ending. Returns
+    /// None at the end of input.
+    pub fn read_line(&self) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        let size = match &self.input {
+            Some(input) => input.borrow_mut().read_line(&mut line)?,
+            // Only lock stdin while reading, so others can too.
+            None => stdin().lock().read_line(&mut line)?,
+        };
+        if size == 0 {
+            return Ok(None);
+        }
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+
+    /// Reader of forms from the input, created with grammar if there's none
+    /// in progress.
+    pub fn form_reader(&self, grammar: &SymbolGrammar) -> RefMut<'_, FormReader> {
+        RefMut::map(self.reader.borrow_mut(), |reader| {
+            reader.get_or_insert_with(|| {
+                let tokenizer = Tokenizer::new(policy_base).with_grammar(grammar.clone());
+                (tokenizer, Parser::new())
+            })
+        })
+    }
+
+    /// Drop the form reader along with any input it buffered.
+    pub fn clear_form_reader(&self) {
+        self.reader.replace(None);
+    }
+}
@@ -33,10 +33,6 @@ pub struct AmlangContext {
     let_rec: LocalNode,
     env_find: LocalNode,
     env_jump: LocalNode,
-    #[serde(rename = "true")]
-    t: LocalNode,
-    #[serde(rename = "false")]
-    f: LocalNode,
     eq: LocalNode,
     #[serde(rename = "table-sym-node")]
     sym_node_table: LocalNode,
@@ -50,6 +46,32 @@ pub struct AmlangContext {
     anon: LocalNode,
     #[serde(rename = "$")]
     self_ref: LocalNode,
+    car: LocalNode,
+    cdr: LocalNode,
+    cons: LocalNode,
+    append: LocalNode,
+    quasiquote: LocalNode,
+    unquote: LocalNode,
+    unquote_splicing: LocalNode,
+    defmacro: LocalNode,
+    #[serde(rename = "match")]
+    pattern_match: LocalNode,
+    is_cons: LocalNode,
+    #[serde(rename = "__match-fail")]
+    match_fail: LocalNode,
+    and: LocalNode,
+    or: LocalNode,
+    #[serde(rename = "vector-ref")]
+    vector_ref: LocalNode,
+    #[serde(rename = "vector-set!")]
+    vector_set: LocalNode,
+    #[serde(rename = "table-insert!")]
+    table_insert: LocalNode,
+    #[serde(rename = "table-remove!")]
+    table_remove: LocalNode,
+    #[serde(rename = "load")]
+    load_file: LocalNode,
+    module: LocalNode,
 }
 
 impl<'de> Context<'de> for AmlangContext {}
@@ -5,6 +5,7 @@ use log::debug;
 use super::amlang_context::AmlangContext;
 use super::amlang_wrappers::*;
 use super::interpreter::{Interpreter, InterpreterState};
+use super::vm_interpreter::VmInterpreter;
 use super::Agent;
 use crate::agent::lang_error::{ExpectedCount, LangError};
 use crate::continuation::Continuation;
@@ -13,7 +14,13 @@ use crate::error::Error;
 use crate::primitive::prelude::*;
 use crate::primitive::table::Table;
 use crate::sexp::{Cons, HeapSexp, Sexp};
+use crate::span::SpanTree;
 
+use self::AbstractionKind::*;
+
+
+// Designation, within impl envs, of the predicate linking params to names.
+const PARAM_NAME: &str = "__param_name";
 
 #[derive(Debug)]
 pub struct AmlangInterpreter {
@@ -32,6 +39,32 @@ impl AmlangInterpreter {
             context,
         }
     }
+
+    /// Names of the params of proc, if it's an abstraction built by this
+    /// Interpreter.
+    pub fn param_names(agent: &Agent, proc: &Procedure) -> Option<Vec<Symbol>> {
+        let params = match proc {
+            Procedure::UserAbstraction(params, _)
+            | Procedure::InterpreterAbstraction(params, _)
+            | Procedure::MacroAbstraction(params, _) => params,
+            _ => return None,
+        };
+        let designation = PARAM_NAME.to_symbol_or_panic(policy_admin);
+        params
+            .iter()
+            .map(|param| {
+                // See make_lambda.
+                let env = agent.access_env(param.env())?;
+                let predicate = env.match_designation(&designation, LocalNode::default())?;
+                let triple = env
+                    .match_but_object(param.local(), predicate.local())
+                    .triples()
+                    .next()?;
+                let name = Node::new(param.env(), env.triple_object(triple));
+                Symbol::try_from(agent.concretize(name).ok()?).ok()
+            })
+            .collect()
+    }
 }
 
 impl InterpreterState for AmlangInterpreter {
@@ -41,6 +74,14 @@ impl InterpreterState for AmlangInterpreter {
 }
 
 
+// Kind of Procedure produced by make_lambda.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum AbstractionKind {
+    User,
+    Interpreter,
+    Macro,
+}
+
 struct ExecutingInterpreter<'a> {
     state: &'a mut AmlangInterpreter,
     agent: &'a mut Agent,
@@ -64,11 +105,33 @@ impl<'a> ExecutingInterpreter<'a> {
         &mut self.agent
     }
 
+    // Predicate linking params to their names in the impl env, defined upon
+    // first use.
+    fn param_name_predicate(&mut self) -> Result<Node, Error> {
+        let impl_env = self.state.impl_env;
+        let designation = PARAM_NAME.to_symbol_or_panic(policy_admin);
+        let existing = self
+            .agent()
+            .access_env(impl_env)
+            .and_then(|env| env.match_designation(&designation, LocalNode::default()));
+        if let Some(predicate) = existing {
+            return Ok(predicate);
+        }
+
+        let predicate = self.agent_mut().define_to(impl_env, None)?;
+        self.agent_mut()
+            .access_env_mut(impl_env)
+            .unwrap()
+            .insert_designation(predicate, designation, LocalNode::default());
+        Ok(predicate)
+    }
+
     fn make_lambda(
         &mut self,
         params: Vec<Symbol>,
         body: HeapSexp,
-        reflect: bool,
+        body_spans: &[SpanTree],
+        kind: AbstractionKind,
     ) -> Result<(Procedure, SymNodeTable), Error> {
         let mut surface = Vec::new();
         let mut frame = SymNodeTable::default();
@@ -86,23 +149,22 @@ impl<'a> ExecutingInterpreter<'a> {
             }
             frame.insert(symbol.clone(), node);
             surface.push(node);
-            let _name = self.agent_mut().define_to(impl_env, Some(symbol.into()))?;
-            // TODO(feat) Bring back when we have multi-env triples.
-            // // Unlike amlang designation, label predicate must be imported.
-            // let raw_predicate = context_node!(label, self.agent().context());
-            // let label_predicate = self.agent_mut().import(raw_predicate)?;
-            // self.agent_mut().tell(node, label_predicate, name)?;
+            // Link params to their names, for param_names.
+            let name = self.agent_mut().define_to(impl_env, Some(symbol.into()))?;
+            let predicate = self.param_name_predicate()?;
+            self.agent_mut().tell_to(impl_env, node, predicate, name)?;
         }
 
         self.state.eval_state.push(frame);
         let res = (|| {
             let mut body_nodes = vec![];
-            for (elem, proper) in body.into_iter() {
+            for (i, (elem, proper)) in body.into_iter().enumerate() {
                 if !proper {
                     return err!(self.agent(), LangError::InvalidSexp(*elem));
                 }
-                let eval = self.interpret(*elem)?;
-                let node = self.node_or_insert(eval)?;
+                let span = body_spans.get(i);
+                let eval = self.interpret_spanned(*elem, span)?;
+                let node = self.node_or_insert(eval, span)?;
                 body_nodes.push(node);
             }
 
@@ -113,31 +175,248 @@ impl<'a> ExecutingInterpreter<'a> {
                     .define_to(impl_env, Some(Procedure::Sequence(body_nodes).into()))?
             };
 
-            if reflect {
-                Ok(Procedure::InterpreterAbstraction(surface, body))
-            } else {
-                Ok(Procedure::UserAbstraction(surface, body))
-            }
+            Ok(match kind {
+                User => Procedure::UserAbstraction(surface, body),
+                Interpreter => Procedure::InterpreterAbstraction(surface, body),
+                Macro => Procedure::MacroAbstraction(surface, body),
+            })
         })();
         let frame = self.state.eval_state.pop().unwrap();
         Ok((res?, frame))
     }
 
+    /// Apply macro to unevaluated args, returning the expansion.
+    ///
+    /// The application and its args are stored in impl env, and
+    /// executed by a VmInterpreter.
+    fn expand_macro(&mut self, macro_node: Node, args: Option<HeapSexp>) -> Result<Sexp, Error> {
+        let arg_nodes = self.evlis(args, &[], false)?;
+        let impl_env = self.state.impl_env;
+        let application = self.agent_mut().define_to(
+            impl_env,
+            Some(Procedure::Application(macro_node, arg_nodes).into()),
+        )?;
+
+        let vm = VmInterpreter::new(impl_env, impl_env, self.state.context.clone());
+        let context = context_node!(defmacro, self.state.context);
+        self.agent_mut()
+            .sub_interpret(application.into(), Box::new(vm), context)
+    }
+
+    /// Translate quasiquote template into code which constructs it.
+    ///
+    /// The code refers to the quote, cons & append nodes directly rather
+    /// than by name, so that local bindings can't change its meaning.
+    fn quasiquote(&self, template: Sexp, level: usize) -> Result<Sexp, Error> {
+        let context = &self.state.context;
+        let quote = Sexp::from(context_node!(quote, context));
+        let cons = Sexp::from(context_node!(cons, context));
+        let quoted = |sexp: Sexp| list!(quote.clone(), sexp);
+        if let Sexp::Primitive(_) = template {
+            return Ok(quoted(template));
+        }
+
+        for name in ["unquote", "unquote-splicing", "quasiquote"] {
+            if let Some(arg) = unwrap_form(&template, name) {
+                let inner_level = match name {
+                    "quasiquote" => level + 1,
+                    "unquote" if level == 1 => return Ok(arg.clone()),
+                    "unquote-splicing" if level == 1 => {
+                        return err!(
+                            self.agent(),
+                            LangError::InvalidState {
+                                actual: "unquote-splicing outside of list".into(),
+                                expected: "unquote-splicing within list".into(),
+                            }
+                        );
+                    }
+                    _ => level - 1,
+                };
+                // Rebuild (name inner) for nested templates.
+                let inner = self.quasiquote(arg.clone(), inner_level)?;
+                return Ok(list!(
+                    cons.clone(),
+                    quoted(name.to_symbol_or_panic(policy_base).into()),
+                    list!(cons, inner, quoted(Sexp::default())),
+                ));
+            }
+        }
+
+        let (car, cdr) = Cons::try_from(template).unwrap().consume();
+        let car = match car {
+            Some(car) => *car,
+            None => return Ok(quoted(Sexp::default())),
+        };
+        let cdr = self.quasiquote(cdr.map_or(Sexp::default(), |e| *e), level)?;
+        if level == 1 {
+            if let Some(arg) = unwrap_form(&car, "unquote-splicing") {
+                let append = context_node!(append, context);
+                return Ok(list!(append, arg.clone(), cdr));
+            }
+        }
+        Ok(list!(cons, self.quasiquote(car, level)?, cdr))
+    }
+
+    /// Compile match clauses into nested branches.
+    ///
+    /// Each clause falls through to a thunk for the remaining clauses, the
+    /// last of which raises a NonExhaustiveMatch error. The scrutinee and
+    /// thunks are bound to fresh Nodes and the expansion refers to context
+    /// Nodes, so that only pattern variables are visible to clause bodies.
+    fn compile_match(
+        &mut self,
+        expr: Sexp,
+        clauses: Vec<(HeapSexp, HeapSexp)>,
+    ) -> Result<Sexp, Error> {
+        let context = self.state.context.clone();
+        let impl_env = self.state.impl_env;
+        let value = self.agent_mut().define_to(impl_env, None)?;
+
+        let fail = list!(context_node!(match_fail, context), value);
+        let mut code = self.interpret(fail)?;
+        for (pattern, body) in clauses.into_iter().rev() {
+            let next = self.agent_mut().define_to(impl_env, None)?;
+            let mut body = *body;
+            body.push_front(context_node!(progn, context));
+            let test = self.compile_pattern(*pattern, value.into(), body, list!(next))?;
+            let test = self.interpret(test)?;
+
+            let code_node = self.node_or_insert(code, None)?;
+            let thunk = Procedure::UserAbstraction(vec![], code_node);
+            code = self.bind(next, thunk.into(), test)?;
+        }
+        let expr = self.interpret(expr)?;
+        self.bind(value, expr, code)
+    }
+
+    /// Compile and/or into nested Branches which short-circuit on the
+    /// first false (and) or non-false (or) value.
+    ///
+    /// Branches are built directly, and or's tested values are bound to
+    /// fresh Nodes, so that local bindings can't change their meaning.
+    fn compile_connective(
+        &mut self,
+        args: Option<HeapSexp>,
+        conjunction: bool,
+    ) -> Result<Sexp, Error> {
+        let mut exprs = vec![];
+        if let Some(args) = args {
+            for (expr, proper) in args.into_iter() {
+                if !proper {
+                    return err!(self.agent(), LangError::InvalidSexp(*expr));
+                }
+                let expr = self.interpret(*expr)?;
+                exprs.push(expr);
+            }
+        }
+
+        let impl_env = self.state.impl_env;
+        let mut code = exprs.pop().unwrap_or_else(|| conjunction.into());
+        for expr in exprs.into_iter().rev() {
+            let rest = self.node_or_insert(code, None)?;
+            code = if conjunction {
+                let test = self.node_or_insert(expr, None)?;
+                let fail = self.node_or_insert(false.into(), None)?;
+                Procedure::Branch((test, rest, fail).into()).into()
+            } else {
+                let value = self.agent_mut().define_to(impl_env, None)?;
+                let branch = Procedure::Branch((value, value, rest).into());
+                self.bind(value, expr, branch.into())?
+            };
+        }
+        Ok(code)
+    }
+
+    /// Translate pattern into a test of the value produced by access,
+    /// resulting in success or fail.
+    fn compile_pattern(
+        &self,
+        pattern: Sexp,
+        access: Sexp,
+        success: Sexp,
+        fail: Sexp,
+    ) -> Result<Sexp, Error> {
+        let context = &self.state.context;
+        let branch = context_node!(branch, context);
+        let eq = context_node!(eq, context);
+        let quote = context_node!(quote, context);
+        let test = match pattern {
+            Sexp::Primitive(Primitive::Symbol(symbol)) => {
+                return Ok(if symbol.as_str() == "_" {
+                    success
+                } else {
+                    let let_basic = context_node!(let_basic, context);
+                    list!(let_basic, list!(list!(symbol, access)), success)
+                });
+            }
+            Sexp::Primitive(_) => list!(eq, access, pattern),
+            _ if pattern.is_none() => list!(eq, access, list!(quote, Sexp::default())),
+            _ if unwrap_form(&pattern, "quote").is_some() => {
+                let datum = unwrap_form(&pattern, "quote").unwrap().clone();
+                list!(eq, access, list!(quote, datum))
+            }
+            _ if unwrap_form(&pattern, "unquote").is_some() => {
+                let expr = unwrap_form(&pattern, "unquote").unwrap().clone();
+                list!(eq, access, expr)
+            }
+            Sexp::Cons(cons) => {
+                let (car, cdr) = cons.consume();
+                let cdr_access = list!(context_node!(cdr, context), access.clone());
+                let cdr_pattern = cdr.map_or(Sexp::default(), |e| *e);
+                let inner = self.compile_pattern(cdr_pattern, cdr_access, success, fail.clone())?;
+
+                let car_access = list!(context_node!(car, context), access.clone());
+                let car_pattern = car.map_or(Sexp::default(), |e| *e);
+                let inner = self.compile_pattern(car_pattern, car_access, inner, fail.clone())?;
+                let is_cons = list!(context_node!(is_cons, context), access);
+                return Ok(list!(branch, is_cons, inner, fail));
+            }
+        };
+        Ok(list!(branch, test, success, fail))
+    }
+
+    /// Bind param, a fresh Node, to the result of arg within body, where
+    /// both arg and body are already interpreted.
+    ///
+    /// Unlike let, param is only reachable by Node rather than by name.
+    fn bind(&mut self, param: Node, arg: Sexp, body: Sexp) -> Result<Sexp, Error> {
+        let body_node = self.node_or_insert(body, None)?;
+        let proc = Procedure::UserAbstraction(vec![param], body_node);
+        let proc_node = self.node_or_insert(proc.into(), None)?;
+        let arg_node = self.node_or_insert(arg, None)?;
+        Ok(Procedure::Application(proc_node, vec![arg_node]).into())
+    }
+
     // If we need Nodes in a particular context, we must abstract existing
     // Sexps into the env. However, if the sexp is already a Node, just use it
     // directly rather than create a stack of abstractions.
-    fn node_or_insert(&mut self, sexp: Sexp) -> Result<Node, Error> {
+    fn node_or_insert(&mut self, sexp: Sexp, span: Option<&SpanTree>) -> Result<Node, Error> {
         if let Ok(node) = <Node>::try_from(&sexp) {
             Ok(node)
         } else {
             let env = self.state.impl_env;
-            self.agent_mut().define_to(env, Some(sexp))
+            self.define_spanned(env, sexp, span)
         }
     }
 
+    // Define sexp in env, recording where it came from if known.
+    fn define_spanned(
+        &mut self,
+        env: LocalNode,
+        sexp: Sexp,
+        span: Option<&SpanTree>,
+    ) -> Result<Node, Error> {
+        let node = self.agent_mut().define_to(env, Some(sexp))?;
+        if let (Some(map), Some(tree)) = (self.agent().source_map(), span) {
+            map.borrow_mut().insert_node(node, tree.span);
+        }
+        Ok(node)
+    }
+
     fn evlis(
         &mut self,
         structures: Option<HeapSexp>,
+        spans: &[SpanTree],
         should_interpret: bool,
     ) -> Result<Vec<Node>, Error> {
         if structures.is_none() {
@@ -147,17 +426,18 @@ impl<'a> ExecutingInterpreter<'a> {
         // TODO(perf) Return Cow.
         let s = structures.unwrap();
         let mut args = Vec::<Node>::with_capacity(s.iter().count());
-        for (structure, proper) in s.into_iter() {
+        for (i, (structure, proper)) in s.into_iter().enumerate() {
             if !proper {
                 return err!(self.agent(), LangError::InvalidSexp(*structure));
             }
 
+            let span = spans.get(i);
             let arg_node = if should_interpret {
-                let val = self.interpret(*structure)?;
-                self.node_or_insert(val)?
+                let val = self.interpret_spanned(*structure, span)?;
+                self.node_or_insert(val, span)?
             } else {
                 let env = self.state.impl_env;
-                self.agent_mut().define_to(env, Some(*structure))?
+                self.define_spanned(env, *structure, span)?
             };
             args.push(arg_node);
         }
@@ -167,6 +447,7 @@ impl<'a> ExecutingInterpreter<'a> {
     fn evlis_def(
         &mut self,
         structures: Option<HeapSexp>,
+        spans: &[SpanTree],
         first_interface: bool,
     ) -> Result<Vec<Node>, Error> {
         if structures.is_none() {
@@ -181,21 +462,28 @@ impl<'a> ExecutingInterpreter<'a> {
                 return err!(self.agent(), LangError::InvalidSexp(*structure));
             }
 
-            let arg_node = if i == 0 && first_interface {
-                self.agent_mut().define(Some(*structure))?
+            let env = if i == 0 && first_interface {
+                self.agent().pos().env()
             } else {
-                let env = self.state.impl_env;
-                self.agent_mut().define_to(env, Some(*structure))?
+                self.state.impl_env
             };
+            let arg_node = self.define_spanned(env, *structure, spans.get(i))?;
             args.push(arg_node);
         }
         Ok(args)
     }
-}
 
-impl<'a> Interpreter for ExecutingInterpreter<'a> {
-    fn interpret(&mut self, structure: Sexp) -> Result<Sexp, Error> {
+    /// Interpret structure, given the spans it was parsed from (if known).
+    ///
+    /// Generated code has no spans, nor do its elements.
+    fn interpret_spanned(
+        &mut self,
+        structure: Sexp,
+        span: Option<&SpanTree>,
+    ) -> Result<Sexp, Error> {
         debug!("Interpreting: {}", structure);
+        let spans = span.map_or(&[][..], |tree| &tree.children[..]);
+        let child_spans = |from: usize| spans.get(from..).unwrap_or(&[]);
         match structure {
             Sexp::Primitive(primitive) => {
                 if let Primitive::Symbol(symbol) = &primitive {
@@ -205,7 +493,7 @@ impl<'a> Interpreter for ExecutingInterpreter<'a> {
                         }
                     }
                 }
-                return self.agent_mut().designate(primitive);
+                self.agent_mut().designate(primitive)
             }
 
             Sexp::Cons(cons) => {
@@ -220,10 +508,18 @@ impl<'a> Interpreter for ExecutingInterpreter<'a> {
                     }
                 };
 
-                let eval_car = self.interpret(*car)?;
+                // Nodes in operator position stand for themselves, just as
+                // symbols resolve to Nodes, so that expansions can name
+                // Procedures hygienically.
+                let eval_car = match *car {
+                    Sexp::Primitive(Primitive::Node(node)) => node.into(),
+                    car => self.interpret_spanned(car, spans.first())?,
+                };
                 let node = match eval_car {
                     Sexp::Primitive(Primitive::Procedure(_))
-                    | Sexp::Primitive(Primitive::Node(_)) => self.node_or_insert(eval_car)?,
+                    | Sexp::Primitive(Primitive::Node(_)) => {
+                        self.node_or_insert(eval_car, spans.first())?
+                    }
                     _ => {
                         return err!(
                             self.agent(),
@@ -237,36 +533,92 @@ impl<'a> Interpreter for ExecutingInterpreter<'a> {
                 let context = &self.state.context;
                 match node {
                     _ if context_node!(quote, context) == node => {
-                        return Ok(*quote_wrapper(cdr, self.agent())?);
+                        Ok(*quote_wrapper(cdr, self.agent())?)
                     }
                     _ if context_node!(lambda, context) == node
                         || context_node!(fexpr, context) == node =>
                     {
                         let (params, body) = make_lambda_wrapper(cdr, &self.agent())?;
-                        let reflect = node.local() == *context.fexpr();
-                        let (proc, _) = self.make_lambda(params, body, reflect)?;
-                        return Ok(proc.into());
+                        let kind = if node.local() == *context.fexpr() {
+                            Interpreter
+                        } else {
+                            User
+                        };
+                        let (proc, _) = self.make_lambda(params, body, child_spans(2), kind)?;
+                        Ok(proc.into())
+                    }
+                    _ if context_node!(defmacro, context) == node => {
+                        let (name, params, body) = defmacro_wrapper(cdr, self.agent())?;
+                        // Name macro before interpreting body to allow for recursion.
+                        let macro_node = self.agent_mut().define(None)?;
+                        self.agent_mut().declare_name(name, macro_node)?;
+                        let (proc, _) = self.make_lambda(params, body, child_spans(3), Macro)?;
+                        self.agent_mut().set(macro_node, Some(proc.into()))?;
+                        Ok(macro_node.into())
+                    }
+                    _ if context_node!(quasiquote, context) == node => {
+                        let template = quote_wrapper(cdr, self.agent())?;
+                        let code = self.quasiquote(*template, 1)?;
+                        self.interpret_spanned(code, None)
+                    }
+                    _ if context_node!(pattern_match, context) == node => {
+                        let (expr, clauses) = match_wrapper(cdr, self.agent())?;
+                        self.compile_match(*expr, clauses)
+                    }
+                    _ if context_node!(and, context) == node
+                        || context_node!(or, context) == node =>
+                    {
+                        let conjunction = node == context_node!(and, context);
+                        self.compile_connective(cdr, conjunction)
+                    }
+                    _ if context_node!(sym_node_table, context) == node
+                        || context_node!(sym_sexp_table, context) == node
+                        || context_node!(local_node_table, context) == node =>
+                    {
+                        let symbol_keys = node != context_node!(local_node_table, context);
+                        let flattened = table_wrapper(cdr, symbol_keys, self.agent())?;
+                        let args = self.evlis(Some(flattened), &[], true)?;
+                        Ok(Procedure::Application(node, args).into())
+                    }
+                    _ if context_node!(unquote, context) == node
+                        || context_node!(unquote_splicing, context) == node =>
+                    {
+                        err!(
+                            self.agent(),
+                            LangError::InvalidState {
+                                actual: "unquote outside of quasiquote".into(),
+                                expected: "unquote within quasiquote".into(),
+                            }
+                        )
                     }
                     _ if context_node!(let_basic, context) == node
                         || context_node!(let_rec, context) == node =>
                     {
                         let (params, exprs, body) = let_wrapper(cdr, &self.agent())?;
                         let recursive = node.local() == *context.let_rec();
-                        let (proc, frame) = self.make_lambda(params, body, false)?;
-                        let proc_node = self.node_or_insert(proc.into())?;
+                        let (proc, frame) = self.make_lambda(params, body, child_spans(2), User)?;
+                        let proc_node = self.node_or_insert(proc.into(), None)?;
 
+                        // Spans of the expr of each (name expr) binding.
+                        let expr_spans = spans.get(1).map_or(vec![], |bindings| {
+                            bindings
+                                .children
+                                .iter()
+                                .filter_map(|binding| binding.children.get(1).cloned())
+                                .collect()
+                        });
                         let args = if recursive {
                             self.state.eval_state.push(frame);
-                            let res = self.evlis(Some(exprs), true);
+                            let res = self.evlis(Some(exprs), &expr_spans, true);
                             self.state.eval_state.pop();
                             res?
                         } else {
-                            self.evlis(Some(exprs), true)?
+                            self.evlis(Some(exprs), &expr_spans, true)?
                         };
-                        return Ok(Procedure::Application(proc_node, args).into());
+                        Ok(Procedure::Application(proc_node, args).into())
                     }
                     _ if context_node!(branch, context) == node => {
-                        let args = self.evlis(cdr, true)?;
+                        let args = self.evlis(cdr, child_spans(1), true)?;
                         if args.len() != 3 {
                             return err!(
                                 self.agent(),
@@ -277,30 +629,71 @@ impl<'a> Interpreter for ExecutingInterpreter<'a> {
                             );
                         }
                         let proc = Procedure::Branch((args[0], args[1], args[2]).into());
-                        return Ok(proc.into());
+                        Ok(proc.into())
                     }
                     _ if context_node!(progn, context) == node => {
-                        let args = self.evlis(cdr, true)?;
-                        return Ok(Procedure::Sequence(args).into());
+                        let args = self.evlis(cdr, child_spans(1), true)?;
+                        Ok(Procedure::Sequence(args).into())
                     }
                     _ if context_node!(def, context) == node
                         || context_node!(anon, context) == node =>
                     {
-                        let args = self.evlis_def(cdr, node == context_node!(def, context))?;
-                        return Ok(Procedure::Application(node, args).into());
+                        let first_interface = node == context_node!(def, context);
+                        let args = self.evlis_def(cdr, child_spans(1), first_interface)?;
+                        Ok(Procedure::Application(node, args).into())
                     }
                     _ => {
                         let should_interpret = match self.agent_mut().designate(node.into())? {
+                            Sexp::Primitive(Primitive::Procedure(Procedure::MacroAbstraction(
+                                _,
+                                _,
+                            ))) => {
+                                let expansion = self.expand_macro(node, cdr)?;
+                                return self.interpret_spanned(expansion, None);
+                            }
                             Sexp::Primitive(Primitive::Procedure(
                                 Procedure::InterpreterAbstraction(_, _),
                             )) => false,
                             _ => true,
                         };
-                        let args = self.evlis(cdr, should_interpret)?;
-                        return Ok(Procedure::Application(node, args).into());
+                        let args = self.evlis(cdr, child_spans(1), should_interpret)?;
+                        Ok(Procedure::Application(node, args).into())
                     }
                 }
             }
         }
     }
 }
+
+impl<'a> Interpreter for ExecutingInterpreter<'a> {
+    fn interpret(&mut self, structure: Sexp) -> Result<Sexp, Error> {
+        // Spans are only known for top-level Sexps straight from a Parser
+        // sharing the Agent's SourceMap.
+        let tree = match self.agent().source_map() {
+            Some(map) => {
+                let mut map = map.borrow_mut();
+                let tree = map.pop_parsed(&structure);
+                map.set_root(tree.as_ref().map(|tree| tree.span));
+                tree
+            }
+            None => None,
+        };
+        self.interpret_spanned(structure, tree.as_ref())
+    }
+}
+
+
+// If sexp is of the form (name arg), return arg.
+fn unwrap_form<'s>(sexp: &'s Sexp, name: &str) -> Option<&'s Sexp> {
+    if let Sexp::Primitive(_) = sexp {
+        return None;
+    }
+    let mut iter = sexp.iter();
+    match (iter.next(), iter.next(), iter.next()) {
+        (Some((head, true)), Some((arg, true)), None) => match <&Symbol>::try_from(head) {
+            Ok(symbol) if symbol.as_str() == name => Some(arg),
+            _ => None,
+        },
+        _ => None,
+    }
+}
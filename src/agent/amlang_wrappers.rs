@@ -1,7 +1,8 @@
 use crate::agent::lang_error::{ExpectedCount, LangError};
 use crate::agent::Agent;
 use crate::error::Error;
-use crate::primitive::{Node, Primitive, Symbol};
+use crate::primitive::symbol_policies::policy_base;
+use crate::primitive::{Node, Primitive, Symbol, ToSymbol};
 use crate::sexp::{ConsList, HeapSexp, Sexp, SexpIntoIter};
 
 
@@ -68,6 +69,62 @@ pub fn make_lambda_wrapper(
     };
 }
 
+pub fn defmacro_wrapper(
+    args: Option<HeapSexp>,
+    agent: &Agent,
+) -> Result<(Symbol, Vec<Symbol>, HeapSexp), Error> {
+    if args.is_none() {
+        return err!(
+            agent,
+            LangError::WrongArgumentCount {
+                given: 0,
+                expected: ExpectedCount::AtLeast(3),
+            }
+        );
+    }
+
+    let (name, tail) = break_sexp!(args.unwrap() => (Symbol; remainder), agent)?;
+    let (params, body) = make_lambda_wrapper(tail, agent)?;
+    Ok((name, params, body))
+}
+
+pub fn match_wrapper(
+    args: Option<HeapSexp>,
+    agent: &Agent,
+) -> Result<(HeapSexp, Vec<(HeapSexp, HeapSexp)>), Error> {
+    if args.is_none() {
+        return err!(
+            agent,
+            LangError::WrongArgumentCount {
+                given: 0,
+                expected: ExpectedCount::AtLeast(1),
+            }
+        );
+    }
+
+    let (expr, tail) = break_sexp!(args.unwrap() => (HeapSexp; remainder), agent)?;
+    let mut clauses = vec![];
+    for (clause, proper) in tail.map_or(SexpIntoIter::default(), |e| e.into_iter()) {
+        if !proper {
+            return err!(agent, LangError::InvalidSexp(*clause));
+        }
+        let (pattern, body) = break_sexp!(clause => (HeapSexp; remainder), agent)?;
+        match body {
+            Some(body) => clauses.push((pattern, body)),
+            None => {
+                return err!(
+                    agent,
+                    LangError::InvalidArgument {
+                        given: Sexp::from(pattern),
+                        expected: "match clause of form (pattern body...)".into(),
+                    }
+                );
+            }
+        }
+    }
+    Ok((expr, clauses))
+}
+
 pub fn let_wrapper(
     args: Option<HeapSexp>,
     agent: &Agent,
@@ -116,6 +173,48 @@ pub fn let_wrapper(
     };
 }
 
+/// Flatten a table constructor's args into an evaluable list, quoting keys if
+/// they are symbols. Table-lnode takes an env arg before its entries.
+pub fn table_wrapper(
+    args: Option<HeapSexp>,
+    symbol_keys: bool,
+    agent: &Agent,
+) -> Result<HeapSexp, Error> {
+    let mut iter = args.map_or(SexpIntoIter::default(), |e| e.into_iter());
+    let mut flattened = ConsList::new();
+    if !symbol_keys {
+        match iter.next() {
+            Some((env, true)) => flattened.append(env),
+            Some((env, false)) => return err!(agent, LangError::InvalidSexp(*env)),
+            None => {
+                return err!(
+                    agent,
+                    LangError::WrongArgumentCount {
+                        given: 0,
+                        expected: ExpectedCount::AtLeast(1),
+                    }
+                );
+            }
+        }
+    }
+
+    for (entry, proper) in iter {
+        if !proper {
+            return err!(agent, LangError::InvalidSexp(*entry));
+        }
+        if symbol_keys {
+            let (key, val) = break_sexp!(entry => (Symbol, HeapSexp), agent)?;
+            flattened.append(list!("quote".to_symbol_or_panic(policy_base), key));
+            flattened.append(val);
+        } else {
+            let (key, val) = break_sexp!(entry => (HeapSexp, HeapSexp), agent)?;
+            flattened.append(key);
+            flattened.append(val);
+        }
+    }
+    Ok(HeapSexp::new(flattened.release()))
+}
+
 pub fn tell_wrapper(args: &Vec<Node>, agent: &Agent) -> Result<(Node, Node, Node), Error> {
     if args.len() != 3 {
         return err!(
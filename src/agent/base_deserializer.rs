@@ -45,7 +45,7 @@ impl<'de> BaseDeserializer<'de> {
         match sexp {
             Sexp::Primitive(Primitive::Node(node)) => Ok(node),
             Sexp::Primitive(Primitive::Symbol(sym)) => {
-                match policy_env_serde(sym.as_str()).unwrap() {
+                match policy_env_serde(sym.as_str(), self.agent.symbol_grammar()).unwrap() {
                     AdminSymbolInfo::Identifier => {
                         if let Ok(resolved) = self.agent.resolve_name(&sym) {
                             Ok(resolved.into())
@@ -59,13 +59,13 @@ impl<'de> BaseDeserializer<'de> {
                         Ok(triple.node().globalize(self.agent))
                     }
                     AdminSymbolInfo::GlobalNode(env, node) => Ok(Node::new(env, node)),
-                    AdminSymbolInfo::GlobalTriple(env, idx) => Ok(Node::new(
-                        env,
-                        self.agent.env().triple_from_index(idx).node(),
-                    )),
+                    AdminSymbolInfo::GlobalTriple(env, idx) => match self.agent.access_env(env) {
+                        Some(env_obj) => Ok(Node::new(env, env_obj.triple_from_index(idx).node())),
+                        None => err!(self.agent, LangError::UnboundSymbol(sym.clone())),
+                    },
                 }
             }
-            sexp @ _ => err!(
+            sexp => err!(
                 self.agent,
                 DeserializeError::UnexpectedType {
                     given: sexp,
@@ -82,6 +82,7 @@ where
 {
     let mut sexps = pull_transform!(StringReader::new(s)
                                     =>> Tokenizer::new(policy_base)
+                                            .with_grammar(agent.symbol_grammar().clone())
                                     =>. Parser::new());
     if let Some(sexp) = sexps.next() {
         if let Some(extra) = sexps.next() {
@@ -115,18 +116,16 @@ impl<'de, 'a> de::Deserializer<'de> for &'a mut BaseDeserializer<'de> {
     where
         V: Visitor<'de>,
     {
-        let node = as_type!(Node, self.input());
-        // TODO(perf, func) Might be better to directly access context somehow.
-        let b = if node
-            == self
+        let input = self.input();
+        if let Ok(node) = Node::try_from(&input) {
+            // Support envs serialized before the introduction of Bool, which
+            // referenced the true & false Nodes of lang.env.
+            let t = self
                 .agent
-                .resolve_name(&"true".to_symbol_or_panic(policy_base))?
-        {
-            true
-        } else {
-            false
-        };
-        visitor.visit_bool(b)
+                .resolve_name(&"true".to_symbol_or_panic(policy_base))?;
+            return visitor.visit_bool(node == t);
+        }
+        visitor.visit_bool(as_type!(bool, input))
     }
 
     fn deserialize_i8<V>(self, visitor: V) -> Result<V::Value, Error>
@@ -55,11 +55,15 @@ impl<'a> BaseSerializer<'a> {
     fn serialize_node(&self, sexp: Sexp) -> Result<HeapSexp, Error> {
         let (_name, env, local) = break_sexp!(sexp => (Symbol, HeapSexp, HeapSexp), self.agent)?;
         let e = LocalId::try_from(
-            *<&Number>::try_from(Cons::try_from(env).unwrap().cdr().unwrap()).unwrap(),
+            <&Number>::try_from(Cons::try_from(env).unwrap().cdr().unwrap())
+                .unwrap()
+                .clone(),
         )
         .unwrap();
         let l = LocalId::try_from(
-            *<&Number>::try_from(Cons::try_from(local).unwrap().cdr().unwrap()).unwrap(),
+            <&Number>::try_from(Cons::try_from(local).unwrap().cdr().unwrap())
+                .unwrap()
+                .clone(),
         )
         .unwrap();
         Ok(Node::new(LocalNode::new(e), LocalNode::new(l)).into())
@@ -79,7 +83,7 @@ impl<'a, 'b> ser::Serializer for &'a mut BaseSerializer<'b> {
     type SerializeStructVariant = Self;
 
     fn serialize_bool(self, v: bool) -> Result<Self::Ok, Self::Error> {
-        self.serialize_symbol(if v { "true" } else { "false" })
+        Ok(v.into())
     }
 
     fn serialize_i8(self, v: i8) -> Result<Self::Ok, Self::Error> {
@@ -94,6 +98,9 @@ impl<'a, 'b> ser::Serializer for &'a mut BaseSerializer<'b> {
     fn serialize_i64(self, v: i64) -> Result<Self::Ok, Self::Error> {
         Ok(Number::I64(v).into())
     }
+    fn serialize_i128(self, v: i128) -> Result<Self::Ok, Self::Error> {
+        Ok(Number::GenericInt(v).into())
+    }
 
     fn serialize_u8(self, v: u8) -> Result<Self::Ok, Self::Error> {
         Ok(Number::U8(v).into())
@@ -8,6 +8,7 @@ use crate::agent::Agent;
 use crate::env::LocalNode;
 use crate::error::Error;
 use crate::introspect::Introspection;
+use crate::primitive::symbol_policies::policy_admin;
 use crate::primitive::{policy_base, Node, ToSymbol};
 use crate::sexp::{Cons, Sexp};
 
@@ -46,7 +47,8 @@ pub trait Context<'de>: Deserialize<'de> + Sized {
             let remaining = all_fields.difference(&provided);
             for name in remaining {
                 info!("{}: Bootstrapping field {}", introspection.name(), name);
-                let sym = name.to_symbol_or_panic(policy_base);
+                // Fields of meta contexts are dunder-prefixed.
+                let sym = name.to_symbol_or_panic(policy_admin);
                 let val = agent.define_to(context_node.env(), None)?;
                 agent
                     .access_env_mut(context_node.env())
@@ -86,6 +88,8 @@ pub struct MetaEnvContext {
     import_table: LocalNode,
     #[serde(rename = "__serialize_path")]
     serialize_path: LocalNode,
+    #[serde(rename = "__module_source")]
+    module_source: LocalNode,
 }
 
 impl MetaEnvContext {
@@ -96,6 +100,7 @@ impl MetaEnvContext {
             imports: e,
             import_table: e,
             serialize_path: e,
+            module_source: e,
         }
     }
 }
@@ -1,6 +1,10 @@
+use std::fmt;
+
+use crate::agent::Agent;
+use crate::env::LocalNode;
 use crate::error::ErrorKind;
 use crate::primitive::Symbol;
-use crate::sexp::{Cons, Sexp};
+use crate::sexp::Sexp;
 
 #[derive(Debug)]
 pub enum DeserializeError {
@@ -20,41 +24,85 @@ pub enum DeserializeError {
     UnrecognizedBuiltIn(Symbol),
 }
 
-impl ErrorKind for DeserializeError {
-    // TODO(func) Model within env rather than fall back on strings.
-    fn reify(&self) -> Sexp {
-        let inner = match self {
-            Self::IoError(err) => {
-                list!("IoError", format!("{}", err))
-            }
-            Self::MissingData => {
-                list!("MissingData")
-            }
-            Self::ExtraneousData(sexp) => {
-                list!("ExtraneousData", sexp.clone())
+impl PartialEq for DeserializeError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            // io::Errors aren't PartialEq.
+            (Self::IoError(a), Self::IoError(b)) => {
+                a.kind() == b.kind() && a.to_string() == b.to_string()
             }
+            (Self::ExtraneousData(a), Self::ExtraneousData(b)) => a == b,
+            (
+                Self::UnexpectedType { given, expected },
+                Self::UnexpectedType {
+                    given: other_given,
+                    expected: other_expected,
+                },
+            ) => given == other_given && expected == other_expected,
+            (Self::UnexpectedCommand(a), Self::UnexpectedCommand(b)) => a == b,
+            (Self::UnrecognizedBuiltIn(a), Self::UnrecognizedBuiltIn(b)) => a == b,
+            (Self::MissingData, Self::MissingData)
+            | (Self::MissingHeaderSection, Self::MissingHeaderSection)
+            | (Self::MissingNodeSection, Self::MissingNodeSection)
+            | (Self::MissingTripleSection, Self::MissingTripleSection)
+            | (Self::ExpectedSymbol, Self::ExpectedSymbol) => true,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IoError(err) => write!(f, "{}", err),
+            Self::MissingData => write!(f, "missing data"),
+            Self::ExtraneousData(sexp) => write!(f, "extraneous data {}", sexp),
             Self::UnexpectedType { given, expected } => {
-                list!("UnexpectedType", given.clone(), expected.clone())
-            }
-            Self::MissingHeaderSection => {
-                list!("MissingHeaderSection")
-            }
-            Self::MissingNodeSection => {
-                list!("MissingNodeSection")
-            }
-            Self::MissingTripleSection => {
-                list!("MissingTripleSection")
-            }
-            Self::UnexpectedCommand(sexp) => {
-                list!("UnexpectedCommand", sexp.clone())
-            }
-            Self::ExpectedSymbol => {
-                list!("ExpectedSymbol")
-            }
-            Self::UnrecognizedBuiltIn(symbol) => {
-                list!("UnrecognizedBuiltIn", symbol.clone())
+                write!(f, "unexpected type of {}; expected {}", given, expected)
             }
+            Self::MissingHeaderSection => write!(f, "missing header section"),
+            Self::MissingNodeSection => write!(f, "missing node section"),
+            Self::MissingTripleSection => write!(f, "missing triple section"),
+            Self::UnexpectedCommand(sexp) => write!(f, "unexpected command {}", sexp),
+            Self::ExpectedSymbol => write!(f, "expected symbol"),
+            Self::UnrecognizedBuiltIn(symbol) => write!(f, "unrecognized builtin {}", symbol),
+        }
+    }
+}
+
+impl ErrorKind for DeserializeError {
+    fn reify(&self, agent: &Agent) -> Sexp {
+        let context = agent.error_context();
+        let (kind, fields): (_, Vec<(LocalNode, Sexp)>) = match self {
+            Self::IoError(err) => (
+                context.io_error(),
+                vec![(*context.message(), err.to_string().into())],
+            ),
+            Self::MissingData => (context.missing_data(), vec![]),
+            Self::ExtraneousData(sexp) => (
+                context.extraneous_data(),
+                vec![(*context.given(), sexp.clone())],
+            ),
+            Self::UnexpectedType { given, expected } => (
+                context.unexpected_type(),
+                vec![
+                    (*context.given(), given.clone()),
+                    (*context.expected(), expected.clone().into()),
+                ],
+            ),
+            Self::MissingHeaderSection => (context.missing_header_section(), vec![]),
+            Self::MissingNodeSection => (context.missing_node_section(), vec![]),
+            Self::MissingTripleSection => (context.missing_triple_section(), vec![]),
+            Self::UnexpectedCommand(sexp) => (
+                context.unexpected_command(),
+                vec![(*context.given(), sexp.clone())],
+            ),
+            Self::ExpectedSymbol => (context.expected_symbol(), vec![]),
+            Self::UnrecognizedBuiltIn(symbol) => (
+                context.unrecognized_builtin(),
+                vec![(*context.symbol(), symbol.clone().into())],
+            ),
         };
-        Cons::new(Sexp::from("DeserializeError"), inner).into()
+        context.reify_error(*context.deserialize_error(), *kind, fields)
     }
 }
@@ -20,13 +20,22 @@ impl EnvHeader {
         let node_count = env.all_nodes().len();
         let triple_count = env.match_all().len();
         Self {
-            file_version: Version::new(0, 0, 5).into(),
+            file_version: Self::current_version().into(),
             node_count,
             triple_count,
             unrecognized: SymSexpTable::default(),
         }
     }
 
+    /// Version of the .env format written by this crate.
+    pub fn current_version() -> Version {
+        Version::new(0, 0, 6)
+    }
+
+    pub fn file_version(&self) -> Version {
+        self.file_version.clone().into()
+    }
+
     pub fn node_count(&self) -> usize {
         self.node_count
     }
@@ -43,8 +52,9 @@ impl Serialize for EnvHeader {
     {
         let mut state = serializer.serialize_struct("header", 3)?;
         state.serialize_field("version", &self.file_version)?;
-        state.serialize_field("node-count", &self.node_count)?;
-        state.serialize_field("triple-count", &self.triple_count)?;
+        // Write counts untyped so that any version can read the header.
+        state.serialize_field("node-count", &(self.node_count as i128))?;
+        state.serialize_field("triple-count", &(self.triple_count as i128))?;
         for (k, v) in self.unrecognized.as_map() {
             state.serialize_field(
                 // UB, but SerializeStruct isn't working with us here.
@@ -91,7 +101,7 @@ impl<'de> Deserialize<'de> for EnvHeader {
                             "version" => Ok(Field::Version),
                             "node-count" => Ok(Field::NodeCount),
                             "triple-count" => Ok(Field::TripleCount),
-                            other @ _ => Ok(Field::Unrecognized(other.to_string())),
+                            other => Ok(Field::Unrecognized(other.to_string())),
                         }
                     }
                 }
@@ -1,16 +1,21 @@
 use log::{debug, info, warn};
 use std::borrow::Borrow;
+use std::cell::RefCell;
 use std::collections::{BTreeSet, HashMap};
 use std::convert::TryFrom;
-use std::fs::File;
+use std::fs::{read_to_string, File};
 use std::io::{BufWriter, Write};
 use std::path::Path;
+use std::rc::Rc;
 
+use super::agent::EnvFactory;
 use super::amlang_wrappers::quote_wrapper;
 use super::context::{Context, MetaEnvContext};
 use super::deserialize_error::DeserializeError::*;
 use super::env_header::EnvHeader;
 use super::env_policy::EnvPolicy;
+use super::env_sources::{EnvSources, META_PATH};
+use super::error_context::ErrorContext;
 use super::Agent;
 use crate::builtins::generate_builtin_map;
 use crate::env::meta_env::MetaEnv;
@@ -18,23 +23,30 @@ use crate::env::{Environment, LocalNode};
 use crate::error::Error;
 use crate::primitive::prelude::*;
 use crate::primitive::symbol_policies::policy_env_serde;
-use crate::sexp::Sexp;
-use crate::stream::input::FileReader;
+use crate::sexp::{Cons, Sexp, SexpIntoIter};
 
 
+type TableEntries = Vec<(Sexp, Sexp)>;
+
 pub struct EnvManager<Policy: EnvPolicy> {
     agent: Agent,
-    policy: Policy,
+    // Shared with the Agent's EnvFactory.
+    policy: Rc<RefCell<Policy>>,
 }
 
 impl<Policy: EnvPolicy> EnvManager<Policy> {
+    /// Bootstrap from envs in the directory at in_path, as per
+    /// EnvSources::at_root.
     pub fn bootstrap<P: AsRef<Path>>(in_path: P) -> Result<Self, Error> {
-        let mut policy = Policy::default();
-        let meta = MetaEnv::new(EnvManager::create_env(&mut policy, LocalNode::default()));
+        Self::bootstrap_from(&EnvSources::at_root(in_path))
+    }
 
-        let amlang_base = Path::new(env!("CARGO_MANIFEST_DIR"))
-            .canonicalize()
-            .unwrap();
+    pub fn bootstrap_from(sources: &EnvSources) -> Result<Self, Error> {
+        let policy = Rc::new(RefCell::new(Policy::default()));
+        let meta = MetaEnv::new(EnvManager::create_env(
+            &mut *policy.borrow_mut(),
+            LocalNode::default(),
+        ));
 
         // Bootstrap meta env.
         let meta_agent = Agent::new(
@@ -46,10 +58,17 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
             agent: meta_agent,
             policy: policy,
         };
-
-        let mut meta_path = in_path.as_ref().canonicalize().unwrap().to_path_buf();
-        meta_path.push("meta.env");
-        manager.deserialize_curr_env(meta_path)?;
+        manager.agent.set_symbol_grammar(sources.grammar().clone());
+        // Envs created through Agents also use the manager's policy.
+        let factory_policy = manager.policy.clone();
+        manager
+            .agent
+            .set_env_factory(EnvFactory::new(move |env_node| {
+                EnvManager::create_env(&mut *factory_policy.borrow_mut(), env_node)
+            }));
+
+        let meta_contents = manager.read_source(META_PATH, sources.read_or_upstream(META_PATH))?;
+        manager.deserialize_curr_env_from(META_PATH, &meta_contents)?;
         manager.agent.context_metaenv =
             MetaEnvContext::load(manager.agent().pos(), manager.agent_mut())?;
         info!("Meta env bootstrapping complete.");
@@ -58,10 +77,24 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
         let lang_env = manager.agent().find_env("lang.env").unwrap();
         manager.initialize_env_node(lang_env);
         manager.agent_mut().jump_env(lang_env);
-        let lang_path = amlang_base.join("envs/lang.env");
-        manager.deserialize_curr_env(lang_path)?;
+        let lang_contents = manager.read_source("lang.env", sources.read_upstream("lang.env"))?;
+        manager.deserialize_curr_env_from("lang.env", &lang_contents)?;
         info!("Lang env bootstrapping complete.");
 
+        // Bootstrap error env, adding it to meta if missing.
+        let error_env = match manager.agent().find_env("error.env") {
+            Some(env) => {
+                manager.initialize_env_node(env);
+                env
+            }
+            None => manager.insert_new_env("error.env"),
+        };
+        manager.agent_mut().jump_env(error_env);
+        let error_contents =
+            manager.read_source("error.env", sources.read_upstream("error.env"))?;
+        manager.deserialize_curr_env_from("error.env", &error_contents)?;
+        info!("Error env bootstrapping complete.");
+
         // Load all other envs.
         // TODO(func) Allow for delayed loading of environments.
         let env_triples = meta
@@ -70,22 +103,32 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
             .triples();
         for triple in env_triples {
             let subject_node = meta.base().triple_subject(triple);
-            if subject_node == lang_env {
+            if subject_node == lang_env || subject_node == error_env {
                 continue;
             }
 
             let object_node = meta.base().triple_object(triple);
             let entry = meta.base().entry(object_node);
             let object = entry.structure();
-            let env_path = <&LangPath>::try_from(&*object).unwrap();
+            let env_path = <&LangPath>::try_from(object).unwrap().as_std_path();
 
             manager.initialize_env_node(subject_node);
             manager
                 .agent_mut()
                 .jump(Node::new(subject_node, LocalNode::default()));
-            manager.deserialize_curr_env(env_path.as_std_path())?;
+            let name = sources.resolve(env_path).to_string_lossy().into_owned();
+            match manager.read_source(&name, sources.read(env_path))? {
+                Some(contents) => manager.deserialize_curr_env_from(&name, &contents)?,
+                None if sources.root().is_some() => manager.warn_missing(&name),
+                None => debug!("No contents for env {}; starting empty.", name),
+            }
         }
 
+        manager.agent.context_error = ErrorContext::load(
+            Node::new(error_env, LocalNode::default()),
+            manager.agent_mut(),
+        )?;
+
         manager.agent_mut().jump_env(lang_env);
         Ok(manager)
     }
@@ -121,7 +164,7 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
     }
 
     fn initialize_env_node(&mut self, env_node: LocalNode) {
-        let env = EnvManager::create_env(&mut self.policy, env_node);
+        let env = EnvManager::create_env(&mut *self.policy.borrow_mut(), env_node);
         self.agent_mut().meta_mut().insert_env(env_node, env);
     }
 }
@@ -165,8 +208,9 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
                 continue;
             }
 
+            // Relative env paths are relative to out_path, as in bootstrap.
             self.agent_mut().jump_env(env);
-            self.serialize_curr_env(path.as_std_path())?;
+            self.serialize_curr_env(out_path.as_ref().join(path.as_std_path()))?;
         }
 
         self.agent_mut().jump(original_pos);
@@ -192,27 +236,9 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
         // Serialize nodes except self node.
         for node in env.all_nodes().into_iter().skip(1) {
             let s = env.entry(node).owned();
-            let (write_structure, add_quote) = match &s {
-                // Don't quote structures with special deserialize ops.
-                Some(sexp) => match sexp {
-                    Sexp::Primitive(Primitive::SymNodeTable(_)) => (true, false),
-                    Sexp::Primitive(Primitive::LocalNodeTable(_)) => (true, false),
-                    Sexp::Primitive(Primitive::BuiltIn(_)) => (true, false),
-                    Sexp::Primitive(Primitive::Procedure(_)) => (true, false),
-                    Sexp::Primitive(Primitive::Node(_)) => (true, false),
-                    Sexp::Primitive(Primitive::LangPath(_)) => (true, false),
-                    Sexp::Primitive(Primitive::LangString(_)) => (true, false),
-                    _ => (true, true),
-                },
-                _ => (false, false),
-            };
-
             let node = node.globalize(self.agent());
-            if write_structure {
-                let mut structure = s.unwrap();
-                if add_quote {
-                    structure = list!("quote".to_symbol_or_panic(policy_admin), structure);
-                }
+            if let Some(structure) = s {
+                let structure = EnvManager::<Policy>::structure_form(structure);
                 self.serialize_list_internal(&mut w, &list!(node, structure), 0)?;
             } else {
                 write!(&mut w, " ")?; // Add space to align with structured lines.
@@ -248,31 +274,57 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
         Ok(())
     }
 
+    /// Structure as written in serialized envs, with strings escaped, so that
+    /// it reads back the same.
+    pub fn serialize_structure(&self, structure: &Sexp) -> std::io::Result<String> {
+        let mut out = vec![];
+        self.serialize_list_internal(&mut out, structure, 0)?;
+        let serialized = String::from_utf8(out)
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))?;
+        Ok(serialized.trim_end().to_string())
+    }
+
     pub fn deserialize_curr_env<P: AsRef<Path>>(&mut self, in_path: P) -> Result<(), Error> {
-        debug!("Deserializing env {}", in_path.as_ref().to_string_lossy());
-        let mut input = match FileReader::new(in_path.as_ref()) {
-            Ok(input) => input,
+        let name = in_path.as_ref().to_string_lossy();
+        let contents = match read_to_string(in_path.as_ref()) {
+            Ok(contents) => contents,
             Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => {
-                warn!("Env file not found: {}", in_path.as_ref().to_string_lossy());
-                warn!(
-                    "Leaving env {} unchanged. If this is intended, then all is well.",
-                    self.agent().pos().env()
-                );
+                self.warn_missing(&name);
                 return Ok(());
             }
             Err(err) => return err!(self.agent(), IoError(err)),
         };
+        self.deserialize_curr_env_from(&name, &contents)
+    }
+
+    /// Deserialize contents into the current env, name only being used for
+    /// logging & errors.
+    pub fn deserialize_curr_env_from(&mut self, name: &str, contents: &str) -> Result<(), Error> {
+        debug!("Deserializing env {}", name);
+        let mut input = contents.lines();
 
         let header = if let Some(line) = input.next() {
-            let header = Sexp::parse_with(line?.as_str(), policy_env_serde)?;
+            let header = self.parse_line(line)?;
             self.agent_mut().reflect::<EnvHeader>(header)?
         } else {
             return err!(self.agent(), MissingHeaderSection);
         };
+        if header.file_version() > EnvHeader::current_version() {
+            return Err(Error::adhoc(
+                "UnsupportedVersion",
+                format!(
+                    "{} is version {}, but only up to {} is supported",
+                    name,
+                    header.file_version(),
+                    EnvHeader::current_version()
+                ),
+            ));
+        }
 
         // Deserialize designations first in case we need it for nodes/triples.
-        let mut context_input = FileReader::new(in_path.as_ref()).unwrap();
-        context_input.seek_line(5 + header.node_count() + header.triple_count())?;
+        let mut context_input = contents
+            .lines()
+            .skip(5 + header.node_count() + header.triple_count());
         self.deserialize_designations(&mut context_input)?;
 
         input.next();
@@ -280,11 +332,7 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
         input.next();
         self.deserialize_triples(&mut input)?;
 
-        info!(
-            "Loaded env {} from \"{}\".",
-            self.agent().pos().env(),
-            in_path.as_ref().to_string_lossy()
-        );
+        info!("Loaded env {} from \"{}\".", self.agent().pos().env(), name);
         debug!("  Node count:    {}", self.agent().env().all_nodes().len());
         debug!(
             "  Triple count:  {}",
@@ -293,6 +341,79 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
         Ok(())
     }
 
+    fn read_source<T>(&self, name: &str, read: std::io::Result<T>) -> Result<T, Error> {
+        match read {
+            Ok(contents) => Ok(contents),
+            Err(err) => err!(self.agent(), IoError(err))
+                .map_err(|err| err.wrap_adhoc("EnvErr", format!("Reading {} failed", name))),
+        }
+    }
+
+    fn warn_missing(&self, name: &str) {
+        warn!("Env file not found: {}", name);
+        warn!(
+            "Leaving env {} unchanged. If this is intended, then all is well.",
+            self.agent().pos().env()
+        );
+    }
+
+
+    // Quote structures without special deserialize ops.
+    fn structure_form(structure: Sexp) -> Sexp {
+        match structure {
+            Sexp::Primitive(Primitive::SymNodeTable(_))
+            | Sexp::Primitive(Primitive::SymSexpTable(_))
+            | Sexp::Primitive(Primitive::LocalNodeTable(_))
+            | Sexp::Primitive(Primitive::BuiltIn(_))
+            | Sexp::Primitive(Primitive::Procedure(_))
+            | Sexp::Primitive(Primitive::Node(_))
+            | Sexp::Primitive(Primitive::LangPath(_))
+            | Sexp::Primitive(Primitive::LangString(_))
+            | Sexp::Primitive(Primitive::Char(_))
+            | Sexp::Primitive(Primitive::Bool(_)) => structure,
+            _ => list!(
+                "quote".to_symbol_or_panic(policy_admin),
+                EnvManager::<Policy>::quoted_form(structure)
+            ),
+        }
+    }
+
+    // Wrap primitives which the parser can't produce in (__embed ..) so that
+    // they're written in their structure form within quoted data. Data which
+    // itself uses __embed has it escaped as (__embed '__embed).
+    fn quoted_form(data: Sexp) -> Sexp {
+        let embed = || "__embed".to_symbol_or_panic(policy_admin);
+        match data {
+            Sexp::Primitive(Primitive::Number(_))
+            | Sexp::Primitive(Primitive::Symbol(_))
+            | Sexp::Primitive(Primitive::LangString(_))
+            | Sexp::Primitive(Primitive::Char(_))
+            | Sexp::Primitive(Primitive::Bool(_)) => data,
+            Sexp::Primitive(Primitive::Vector(vector)) => Vector::from(
+                vector
+                    .into_vec()
+                    .into_iter()
+                    .map(EnvManager::<Policy>::quoted_form)
+                    .collect::<Vec<_>>(),
+            )
+            .into(),
+            Sexp::Primitive(primitive) => list!(embed(), primitive),
+            Sexp::Cons(cons) => {
+                let (car, cdr) = cons.consume();
+                let car = car.map(|car| match *car {
+                    Sexp::Primitive(Primitive::Symbol(ref sym)) if *sym == embed() => {
+                        Box::new(list!(
+                            embed(),
+                            list!("quote".to_symbol_or_panic(policy_admin), embed())
+                        ))
+                    }
+                    car => Box::new(EnvManager::<Policy>::quoted_form(car)),
+                });
+                let cdr = cdr.map(|cdr| Box::new(EnvManager::<Policy>::quoted_form(*cdr)));
+                Cons::new(car, cdr).into()
+            }
+        }
+    }
 
     fn serialize_list_internal<W: std::io::Write>(
         &self,
@@ -322,10 +443,12 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
         depth: usize,
     ) -> std::io::Result<()> {
         match primitive {
+            Primitive::Number(num) => write!(w, "{}", num.to_literal()),
             Primitive::LangString(s) => write!(w, "\"{}\"", s.clone().to_escaped()),
             Primitive::Symbol(symbol) => write!(w, "{}", symbol.as_str()),
             Primitive::LangPath(path) => {
-                write!(w, "(__path \"{}\")", path.as_std_path().to_string_lossy())
+                let path = LangString::new(path.as_std_path().to_string_lossy());
+                write!(w, "(__path \"{}\")", path.to_escaped())
             }
             Primitive::BuiltIn(builtin) => write!(w, "(__builtin {})", builtin.name()),
             Primitive::Procedure(proc) => {
@@ -333,12 +456,24 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
                 self.serialize_list_internal(w, &sexp, depth)
             }
             Primitive::SymNodeTable(table) => {
-                let sexp = *self.agent().reify(&table).unwrap();
-                self.serialize_list_internal(w, &sexp, depth)
+                self.serialize_list_internal(w, &table.to_form(), depth)
+            }
+            Primitive::SymSexpTable(table) => {
+                let form = table.to_form_with(|v| EnvManager::<Policy>::structure_form(v.clone()));
+                self.serialize_list_internal(w, &form, depth)
             }
             Primitive::LocalNodeTable(table) => {
-                let sexp = *self.agent().reify(&table).unwrap();
-                self.serialize_list_internal(w, &sexp, depth)
+                self.serialize_list_internal(w, &table.to_form(), depth)
+            }
+            Primitive::Vector(vector) => {
+                write!(w, "[")?;
+                for (i, elem) in vector.iter().enumerate() {
+                    if i > 0 {
+                        write!(w, " ")?;
+                    }
+                    self.serialize_list_internal(w, elem, depth + 1)?;
+                }
+                write!(w, "]")
             }
             Primitive::Node(node) => {
                 // Write Nodes as their designation if possible.
@@ -370,19 +505,19 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
         write!(w, "^{}", node.local().id())
     }
 
-    fn deserialize_nodes(
+    fn deserialize_nodes<'a, I: Iterator<Item = &'a str>>(
         &mut self,
-        reader: &mut FileReader,
+        reader: &mut I,
         node_count: usize,
     ) -> Result<(), Error> {
         debug!("Deserializing nodes");
         let section_line = if let Some(line) = reader.next() {
-            line?
+            line
         } else {
             return err!(self.agent(), MissingNodeSection);
         };
 
-        let header = Sexp::parse_with(section_line.as_str(), policy_env_serde)?;
+        let header = self.parse_line(section_line)?;
         let (command, section) = break_sexp!(header => (Symbol, Symbol), self.agent())?;
         if command.as_str() != "section" || section.as_str() != "nodes" {
             return err!(self.agent(), UnexpectedCommand(list!(command, section)));
@@ -390,8 +525,8 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
 
         let builtins = generate_builtin_map();
         for _i in 1..node_count {
-            let line = reader.next().unwrap()?;
-            let entry = Sexp::parse_with(line.as_str(), policy_env_serde)?;
+            let line = reader.next().unwrap();
+            let entry = self.parse_line(line)?;
             match entry {
                 Sexp::Primitive(primitive) => {
                     if let Primitive::Symbol(_sym) = primitive {
@@ -412,20 +547,18 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
 
     fn parse_node(&self, sym: &Symbol) -> Result<Node, Error> {
         let agent = self.agent();
-        match policy_env_serde(sym.as_str()).unwrap() {
-            AdminSymbolInfo::Identifier => agent.resolve_name_with(sym, &[agent.pos()]),
-            AdminSymbolInfo::LocalNode(node) => Ok(node.globalize(agent)),
-            AdminSymbolInfo::LocalTriple(idx) => {
-                let triple = agent.env().triple_from_index(idx);
-                Ok(triple.node().globalize(agent))
-            }
-            AdminSymbolInfo::GlobalNode(env, node) => Ok(Node::new(env, node)),
-            AdminSymbolInfo::GlobalTriple(env, idx) => {
-                Ok(Node::new(env, agent.env().triple_from_index(idx).node()))
-            }
+        match agent.node_reference(sym) {
+            Some(node) => Ok(node),
+            None => agent.resolve_name_with(sym, &[agent.pos()]),
         }
     }
 
+    // Parse a line of a serialized env, with identifiers as per the Agent's
+    // SymbolGrammar.
+    fn parse_line(&self, line: &str) -> Result<Sexp, Error> {
+        Sexp::parse_with_grammar(line, policy_env_serde, self.agent().symbol_grammar())
+    }
+
     fn eval_structure(
         &mut self,
         sexp: Sexp,
@@ -434,6 +567,8 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
         match sexp {
             Sexp::Primitive(Primitive::Symbol(sym)) => return Ok(self.parse_node(&sym)?.into()),
             Sexp::Primitive(Primitive::LangString(s)) => return Ok(s.into()),
+            Sexp::Primitive(Primitive::Char(c)) => return Ok(c.into()),
+            Sexp::Primitive(Primitive::Bool(b)) => return Ok(b.into()),
             _ => {}
         }
 
@@ -441,15 +576,43 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
         if let Some(t) = Primitive::type_from_discriminator(command.as_str()) {
             return Ok(match t {
                 "Procedure" => self.agent_mut().reflect::<Procedure>(sexp)?.into(),
-                "LocalNodeTable" => self.agent_mut().reflect::<LocalNodeTable>(sexp)?.into(),
-                "SymNodeTable" => self.agent_mut().reflect::<SymNodeTable>(sexp)?.into(),
-                _ => panic!(),
+                "SymNodeTable" => {
+                    let mut table = SymNodeTable::default();
+                    for (k, v) in self.table_entries(sexp, false)?.1 {
+                        let (k, v) = break_sexp!(list!(k, v) => (Symbol, Symbol), self.agent())?;
+                        table.insert(k, self.parse_node(&v)?);
+                    }
+                    table.into()
+                }
+                "SymSexpTable" => {
+                    let mut table = SymSexpTable::default();
+                    for (k, v) in self.table_entries(sexp, false)?.1 {
+                        let (k,) = break_sexp!(list!(k) => (Symbol), self.agent())?;
+                        table.insert(k, self.eval_structure(v, builtins)?);
+                    }
+                    table.into()
+                }
+                "LocalNodeTable" => {
+                    let (env, entries) = self.table_entries(sexp, true)?;
+                    let (env,) = break_sexp!(list!(env.unwrap()) => (Symbol), self.agent())?;
+                    let mut table = LocalNodeTable::in_env(self.parse_node(&env)?.local());
+                    for (k, v) in entries {
+                        let (k, v) = break_sexp!(list!(k, v) => (Symbol, Symbol), self.agent())?;
+                        table.insert(self.parse_node(&k)?.local(), self.parse_node(&v)?.local());
+                    }
+                    table.into()
+                }
+                // Types without a serialized form.
+                _ => return err!(self.agent(), UnexpectedCommand(command.clone().into())),
             });
         }
 
         let (command, cdr) = break_sexp!(sexp => (Symbol; remainder), self.agent())?;
         match command.as_str() {
-            "quote" => Ok(*quote_wrapper(cdr, self.agent())?),
+            "quote" => {
+                let data = *quote_wrapper(cdr, self.agent())?;
+                self.eval_quoted(data, builtins)
+            }
             "__builtin" => {
                 if let Ok(sym) = <Symbol>::try_from(*quote_wrapper(cdr, self.agent())?) {
                     if let Some(builtin) = builtins.get(sym.as_str()) {
@@ -465,27 +628,100 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
                 let (path,) = break_sexp!(cdr.unwrap() => (LangString), self.agent())?;
                 Ok(LangPath::new(path.as_str().into()).into())
             }
-            _ => panic!("{}", command),
+            _ => err!(self.agent(), UnexpectedCommand(command.into())),
+        }
+    }
+
+    // Inverse of quoted_form.
+    fn eval_quoted(
+        &mut self,
+        data: Sexp,
+        builtins: &HashMap<&'static str, BuiltIn>,
+    ) -> Result<Sexp, Error> {
+        match data {
+            Sexp::Primitive(Primitive::Vector(vector)) => {
+                let mut elems = vec![];
+                for elem in vector.into_vec() {
+                    elems.push(self.eval_quoted(elem, builtins)?);
+                }
+                Ok(Vector::from(elems).into())
+            }
+            Sexp::Cons(cons) => {
+                if let Ok(sym) = <&Symbol>::try_from(cons.car()) {
+                    if sym.as_str() == "__embed" {
+                        let (_, embedded) =
+                            break_sexp!(Sexp::from(cons) => (Symbol, Sexp), self.agent())?;
+                        return self.eval_structure(embedded, builtins);
+                    }
+                }
+                let (car, cdr) = cons.consume();
+                let car = match car {
+                    Some(car) => Some(Box::new(self.eval_quoted(*car, builtins)?)),
+                    None => None,
+                };
+                let cdr = match cdr {
+                    Some(cdr) => Some(Box::new(self.eval_quoted(*cdr, builtins)?)),
+                    None => None,
+                };
+                Ok(Cons::new(car, cdr).into())
+            }
+            _ => Ok(data),
+        }
+    }
+
+    // Break table form into its header (if expected) and (key value) entries.
+    fn table_entries(
+        &self,
+        form: Sexp,
+        has_header: bool,
+    ) -> Result<(Option<Sexp>, TableEntries), Error> {
+        let (_, tail) = break_sexp!(form => (Symbol; remainder), self.agent())?;
+        let mut iter = tail.map_or(SexpIntoIter::default(), |e| e.into_iter());
+        let header = if has_header {
+            match iter.next() {
+                Some((header, true)) => Some(*header),
+                _ => return err!(self.agent(), MissingData),
+            }
+        } else {
+            None
+        };
+
+        let mut entries = vec![];
+        for (entry, proper) in iter {
+            if !proper {
+                return err!(
+                    self.agent(),
+                    UnexpectedType {
+                        given: *entry,
+                        expected: "table entry".to_string(),
+                    }
+                );
+            }
+            entries.push(break_sexp!(entry => (Sexp, Sexp), self.agent())?);
         }
+        Ok((header, entries))
     }
 
-    fn deserialize_triples(&mut self, reader: &mut FileReader) -> Result<(), Error> {
+    fn deserialize_triples<'a, I: Iterator<Item = &'a str>>(
+        &mut self,
+        reader: &mut I,
+    ) -> Result<(), Error> {
         debug!("Deserializing triples");
         let section_line = if let Some(line) = reader.next() {
-            line?
+            line
         } else {
             return err!(self.agent(), MissingTripleSection);
         };
 
-        let header = Sexp::parse_with(section_line.as_str(), policy_env_serde)?;
+        let header = self.parse_line(section_line)?;
         let (command, section) = break_sexp!(header => (Symbol, Symbol), self.agent())?;
         if command.as_str() != "section" || section.as_str() != "triples" {
             return err!(self.agent(), UnexpectedCommand(list!(command, section)));
         }
 
-        let mut line = reader.next().unwrap()?;
+        let mut line = reader.next().unwrap();
         while !line.is_empty() {
-            let triple = Sexp::parse_with(line.as_str(), policy_env_serde)?;
+            let triple = self.parse_line(line)?;
             let (s, p, o) = break_sexp!(triple => (Symbol, Symbol, Symbol), self.agent())?;
 
             let subject = self.parse_node(&s)?;
@@ -494,15 +730,18 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
 
             self.agent_mut().tell(subject, predicate, object)?;
 
-            line = reader.next().unwrap()?;
+            line = reader.next().unwrap();
         }
         Ok(())
     }
 
-    fn deserialize_designations(&mut self, reader: &mut FileReader) -> Result<(), Error> {
+    fn deserialize_designations<'a, I: Iterator<Item = &'a str>>(
+        &mut self,
+        reader: &mut I,
+    ) -> Result<(), Error> {
         debug!("Deserializing designations");
         while let Some(section_line) = reader.next() {
-            let header = Sexp::parse_with(section_line?.as_str(), policy_env_serde)?;
+            let header = self.parse_line(section_line)?;
             let (command, section, designator) =
                 break_sexp!(header => (Symbol, Symbol, Symbol), self.agent())?;
             if command.as_str() != "section" || section.as_str() != "designation" {
@@ -512,9 +751,9 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
                 );
             }
 
-            let mut line = reader.next().unwrap()?;
+            let mut line = reader.next().unwrap();
             while !line.is_empty() {
-                let pair = Sexp::parse_with(line.as_str(), policy_env_serde)?;
+                let pair = self.parse_line(line)?;
                 let (name, node_id) = break_sexp!(pair => (Symbol, Symbol), self.agent())?;
 
                 // TODO(func) Generic handling of designator "type".
@@ -523,7 +762,7 @@ impl<Policy: EnvPolicy> EnvManager<Policy> {
                     .env_mut()
                     .insert_designation(node, name, LocalNode::default());
 
-                line = reader.next().unwrap()?;
+                line = reader.next().unwrap();
             }
 
             // TODO(func) Generic handling of desired d-chain/context-state.
@@ -4,7 +4,7 @@ use crate::env::raw_overlay::RawOverlay;
 use crate::env::Environment;
 
 
-pub trait EnvPolicy: Default {
+pub trait EnvPolicy: Default + 'static {
     // Note the 'static requirement due to the fact that
     // Primitive::Env uses dyn Environment, which implicitly is
     // + 'static.
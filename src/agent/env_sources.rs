@@ -0,0 +1,204 @@
+//! Serialized env contents for EnvManager to bootstrap from.
+
+use log::warn;
+use std::collections::HashMap;
+use std::fs::read_to_string;
+use std::io::{ErrorKind, Read};
+use std::path::{Path, PathBuf};
+
+use crate::primitive::SymbolGrammar;
+
+
+/// Name of the meta env, relative to the root.
+pub const META_PATH: &str = "meta.env";
+
+// Copies of upstream Amlang's envs, so that binaries needn't be run from a
+// source checkout.
+const EMBEDDED: [(&str, &str); 3] = [
+    ("meta.env", include_str!("../../envs/meta.env")),
+    ("lang.env", include_str!("../../envs/lang.env")),
+    ("error.env", include_str!("../../envs/error.env")),
+];
+
+/// Where EnvManager reads envs from, keyed by the paths recorded for them in
+/// the meta env.
+///
+/// Contents given through with_env take precedence over files. Other envs
+/// are read from files, relative paths being resolved against the root
+/// rather than the current dir; without a root, they start out empty.
+///
+/// Unless given, meta.env (lacking one in the root), lang.env & error.env
+/// are those of upstream Amlang, as embedded in the crate or, for
+/// development, read from its envs/ directory.
+///
+/// Envs are read, and the bootstrapped Agent reads identifiers, as per the
+/// standard SymbolGrammar unless given another through with_grammar.
+#[derive(Clone, Debug, Default)]
+pub struct EnvSources {
+    root: Option<PathBuf>,
+    upstream_dir: Option<PathBuf>,
+    contents: HashMap<PathBuf, String>,
+    grammar: Option<SymbolGrammar>,
+}
+
+impl EnvSources {
+    /// Read envs from files under root.
+    pub fn at_root<P: AsRef<Path>>(root: P) -> Self {
+        Self {
+            root: Some(root.as_ref().to_path_buf()),
+            ..Default::default()
+        }
+    }
+
+    /// Use upstream Amlang's envs/ directory both as the root and for
+    /// upstream envs, so that changes to lang.env & error.env can be saved;
+    /// the development mode counterpart of InitOptions::RootRun.
+    pub fn root_run() -> Self {
+        Self::at_root(upstream_dir()).with_upstream_dir(upstream_dir())
+    }
+
+    /// Read only envs given through with_env, or those of upstream Amlang.
+    pub fn in_memory() -> Self {
+        Self::default()
+    }
+
+    /// Use contents for the env at path, e.g. "meta.env" or "lang.env".
+    pub fn with_env<P: AsRef<Path>, S: Into<String>>(mut self, path: P, contents: S) -> Self {
+        self.contents
+            .insert(path.as_ref().to_path_buf(), contents.into());
+        self
+    }
+
+    /// Read upstream envs from files in dir rather than embedded copies.
+    pub fn with_upstream_dir<P: AsRef<Path>>(mut self, dir: P) -> Self {
+        self.upstream_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// Use everything read from reader for the env at path, as per with_env.
+    pub fn with_env_reader<P: AsRef<Path>, R: Read>(
+        self,
+        path: P,
+        mut reader: R,
+    ) -> std::io::Result<Self> {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        Ok(self.with_env(path, contents))
+    }
+
+    pub fn with_grammar(mut self, grammar: SymbolGrammar) -> Self {
+        self.grammar = Some(grammar);
+        self
+    }
+
+    pub fn grammar(&self) -> &SymbolGrammar {
+        self.grammar.as_ref().unwrap_or(SymbolGrammar::standard())
+    }
+
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    /// Whether the root holds upstream Amlang's envs, which must then be left
+    /// in place. Roots which can't be told apart from upstream dirs are
+    /// presumed to.
+    pub fn upstream_root(&self) -> bool {
+        match &self.root {
+            Some(root) => {
+                same_dir(root, &upstream_dir())
+                    || self.upstream_dir.iter().any(|dir| same_dir(root, dir))
+            }
+            None => false,
+        }
+    }
+
+    /// Path resolved against the root, if any.
+    pub fn resolve<P: AsRef<Path>>(&self, path: P) -> PathBuf {
+        match &self.root {
+            Some(root) => root.join(path),
+            None => path.as_ref().to_path_buf(),
+        }
+    }
+
+    /// Contents of the env at path, or None if there's neither given
+    /// contents nor a file under the root.
+    pub(super) fn read<P: AsRef<Path>>(&self, path: P) -> std::io::Result<Option<String>> {
+        if let Some(contents) = self.contents.get(path.as_ref()) {
+            return Ok(Some(contents.clone()));
+        }
+        if self.root.is_none() {
+            return Ok(None);
+        }
+        match read_to_string(self.resolve(path)) {
+            Ok(contents) => Ok(Some(contents)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Contents of the env at path, falling back to upstream Amlang's.
+    pub(super) fn read_or_upstream<P: AsRef<Path>>(&self, path: P) -> std::io::Result<String> {
+        match self.read(path.as_ref())? {
+            Some(contents) => Ok(contents),
+            None => self.read_upstream(path),
+        }
+    }
+
+    /// Contents of upstream Amlang's env at path, unless given.
+    ///
+    /// Envs missing from the upstream dir fall back to the embedded copies.
+    pub(super) fn read_upstream<P: AsRef<Path>>(&self, path: P) -> std::io::Result<String> {
+        if let Some(contents) = self.contents.get(path.as_ref()) {
+            return Ok(contents.clone());
+        }
+        let embedded = embedded_env(path.as_ref());
+        if let Some(dir) = &self.upstream_dir {
+            match (read_to_string(dir.join(path.as_ref())), embedded) {
+                (Ok(contents), _) => return Ok(contents),
+                (Err(err), Some(_)) => {
+                    warn!(
+                        "Reading upstream {} failed, using embedded copy: {}",
+                        path.as_ref().display(),
+                        err
+                    );
+                }
+                (Err(err), None) => return Err(err),
+            }
+        }
+        match embedded {
+            Some(contents) => Ok(contents.to_string()),
+            None => Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("{} isn't an upstream env", path.as_ref().display()),
+            )),
+        }
+    }
+}
+
+
+/// Copy of upstream Amlang's env at path, as embedded in the crate.
+pub fn embedded_env<P: AsRef<Path>>(path: P) -> Option<&'static str> {
+    EMBEDDED
+        .iter()
+        .find(|(name, _)| Path::new(name) == path.as_ref())
+        .map(|(_, contents)| *contents)
+}
+
+// Upstream Amlang's envs/ directory in the source tree.
+fn upstream_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("envs")
+}
+
+// Whether root & dir are the same directory, as canonicalized. Missing dirs
+// can't be the root, but failing to canonicalize either otherwise counts as
+// a match.
+fn same_dir(root: &Path, dir: &Path) -> bool {
+    let dir = match dir.canonicalize() {
+        Ok(dir) => dir,
+        Err(err) => return err.kind() != ErrorKind::NotFound,
+    };
+    match root.canonicalize() {
+        Ok(root) => root == dir,
+        Err(_) => true,
+    }
+}
@@ -0,0 +1,203 @@
+use derive_getters::Getters;
+use serde::{Deserialize, Serialize};
+
+use super::context::Context;
+use crate::env::LocalNode;
+use crate::primitive::Node;
+use crate::sexp::Sexp;
+
+
+/// Nodes of error.env, used to reify ErrorKinds into Amlang structures.
+///
+/// Reified errors take the form (Class Kind (field value)...), e.g.
+/// (LangError UnboundSymbol (symbol foo)).
+#[derive(Clone, Debug, Deserialize, Getters, Serialize)]
+pub struct ErrorContext {
+    node: Node,
+
+    // Error classes.
+    #[serde(rename = "LangError")]
+    lang_error: LocalNode,
+    #[serde(rename = "GenericError")]
+    generic_error: LocalNode,
+    #[serde(rename = "StdError")]
+    std_error: LocalNode,
+    #[serde(rename = "DeserializeError")]
+    deserialize_error: LocalNode,
+    #[serde(rename = "TokenizeError")]
+    tokenize_error: LocalNode,
+    #[serde(rename = "ParseError")]
+    parse_error: LocalNode,
+
+    // Error kinds.
+    #[serde(rename = "InvalidArgument")]
+    invalid_argument: LocalNode,
+    #[serde(rename = "InvalidState")]
+    invalid_state: LocalNode,
+    #[serde(rename = "InvalidSexp")]
+    invalid_sexp: LocalNode,
+    #[serde(rename = "WrongArgumentCount")]
+    wrong_argument_count: LocalNode,
+    #[serde(rename = "UnboundSymbol")]
+    unbound_symbol: LocalNode,
+    #[serde(rename = "AlreadyBoundSymbol")]
+    already_bound_symbol: LocalNode,
+    #[serde(rename = "DuplicateTriple")]
+    duplicate_triple: LocalNode,
+    #[serde(rename = "RejectedTriple")]
+    rejected_triple: LocalNode,
+    #[serde(rename = "Unsupported")]
+    unsupported: LocalNode,
+    #[serde(rename = "NonExhaustiveMatch")]
+    non_exhaustive_match: LocalNode,
+    #[serde(rename = "Overflow")]
+    overflow: LocalNode,
+    #[serde(rename = "DivisionByZero")]
+    division_by_zero: LocalNode,
+    #[serde(rename = "AdHoc")]
+    ad_hoc: LocalNode,
+    #[serde(rename = "Nested")]
+    nested: LocalNode,
+    #[serde(rename = "IoError")]
+    io_error: LocalNode,
+    #[serde(rename = "MissingData")]
+    missing_data: LocalNode,
+    #[serde(rename = "ExtraneousData")]
+    extraneous_data: LocalNode,
+    #[serde(rename = "UnexpectedType")]
+    unexpected_type: LocalNode,
+    #[serde(rename = "MissingHeaderSection")]
+    missing_header_section: LocalNode,
+    #[serde(rename = "MissingNodeSection")]
+    missing_node_section: LocalNode,
+    #[serde(rename = "MissingTripleSection")]
+    missing_triple_section: LocalNode,
+    #[serde(rename = "UnexpectedCommand")]
+    unexpected_command: LocalNode,
+    #[serde(rename = "ExpectedSymbol")]
+    expected_symbol: LocalNode,
+    #[serde(rename = "UnrecognizedBuiltIn")]
+    unrecognized_builtin: LocalNode,
+    #[serde(rename = "InvalidSymbol")]
+    invalid_symbol: LocalNode,
+    #[serde(rename = "DepthOverflow")]
+    depth_overflow: LocalNode,
+    #[serde(rename = "TrailingQuote")]
+    trailing_quote: LocalNode,
+    #[serde(rename = "UnmatchedOpen")]
+    unmatched_open: LocalNode,
+    #[serde(rename = "UnmatchedClose")]
+    unmatched_close: LocalNode,
+    #[serde(rename = "IsolatedPeriod")]
+    isolated_period: LocalNode,
+    #[serde(rename = "NotPenultimatePeriod")]
+    not_penultimate_period: LocalNode,
+    #[serde(rename = "UnterminatedString")]
+    unterminated_string: LocalNode,
+    #[serde(rename = "InvalidEscape")]
+    invalid_escape: LocalNode,
+
+    // Error fields.
+    given: LocalNode,
+    expected: LocalNode,
+    actual: LocalNode,
+    symbol: LocalNode,
+    triple: LocalNode,
+    reason: LocalNode,
+    message: LocalNode,
+    name: LocalNode,
+    body: LocalNode,
+    parent: LocalNode,
+    child: LocalNode,
+    token: LocalNode,
+    line: LocalNode,
+    col: LocalNode,
+}
+
+impl ErrorContext {
+    pub(super) fn placeholder() -> Self {
+        let e = LocalNode::default();
+        Self {
+            node: Node::new(e, e),
+
+            lang_error: e,
+            generic_error: e,
+            std_error: e,
+            deserialize_error: e,
+            tokenize_error: e,
+            parse_error: e,
+
+            invalid_argument: e,
+            invalid_state: e,
+            invalid_sexp: e,
+            wrong_argument_count: e,
+            unbound_symbol: e,
+            already_bound_symbol: e,
+            duplicate_triple: e,
+            rejected_triple: e,
+            unsupported: e,
+            non_exhaustive_match: e,
+            overflow: e,
+            division_by_zero: e,
+            ad_hoc: e,
+            nested: e,
+            io_error: e,
+            missing_data: e,
+            extraneous_data: e,
+            unexpected_type: e,
+            missing_header_section: e,
+            missing_node_section: e,
+            missing_triple_section: e,
+            unexpected_command: e,
+            expected_symbol: e,
+            unrecognized_builtin: e,
+            invalid_symbol: e,
+            depth_overflow: e,
+            trailing_quote: e,
+            unmatched_open: e,
+            unmatched_close: e,
+            isolated_period: e,
+            not_penultimate_period: e,
+            unterminated_string: e,
+            invalid_escape: e,
+
+            given: e,
+            expected: e,
+            actual: e,
+            symbol: e,
+            triple: e,
+            reason: e,
+            message: e,
+            name: e,
+            body: e,
+            parent: e,
+            child: e,
+            token: e,
+            line: e,
+            col: e,
+        }
+    }
+
+    pub fn globalize(&self, local: LocalNode) -> Node {
+        Node::new(self.node.env(), local)
+    }
+
+    /// Build (class kind (field value)...) out of context nodes.
+    pub fn reify_error<I: IntoIterator<Item = (LocalNode, Sexp)>>(
+        &self,
+        class: LocalNode,
+        kind: LocalNode,
+        fields: I,
+    ) -> Sexp {
+        let mut elements = vec![
+            Sexp::from(self.globalize(class)),
+            Sexp::from(self.globalize(kind)),
+        ];
+        for (field, value) in fields {
+            elements.push(list!(self.globalize(field), value));
+        }
+        elements.into()
+    }
+}
+
+impl<'de> Context<'de> for ErrorContext {}
@@ -6,11 +6,13 @@ use crate::sexp::Sexp;
 use crate::stream::Transform;
 
 
+type Handler<'a> = Box<dyn FnMut(&mut Agent, Sexp) -> Result<Sexp, Error> + 'a>;
+
 /// Use Agent as Transform.
 pub struct TransformExecutor<'a> {
     agent: &'a mut Agent,
     cache: VecDeque<Sexp>,
-    handler: fn(&mut Agent, Sexp) -> Result<Sexp, Error>,
+    handler: Handler<'a>,
 }
 
 impl<'a> TransformExecutor<'a> {
@@ -18,18 +20,18 @@ impl<'a> TransformExecutor<'a> {
         Self {
             agent: agent,
             cache: Default::default(),
-            handler: Self::default_handler,
+            handler: Box::new(Self::default_handler),
         }
     }
 
-    pub fn custom(
+    pub fn custom<F: FnMut(&mut Agent, Sexp) -> Result<Sexp, Error> + 'a>(
         agent: &'a mut Agent,
-        handler: fn(&mut Agent, Sexp) -> Result<Sexp, Error>,
+        handler: F,
     ) -> Self {
         Self {
             agent: agent,
             cache: Default::default(),
-            handler: handler,
+            handler: Box::new(handler),
         }
     }
 
@@ -0,0 +1,272 @@
+//! Persistent record of interpreted inputs.
+//!
+//! Entries live in the env of the Agent's HistoryContext, as atoms linked by
+//! triples to nodes holding their fields:
+//!
+//!   (entry input <input>)      The Sexp as read, prior to interpretation.
+//!   (entry result <result>)    Or (entry error <reified error>).
+//!   (entry session <session>)  Session atom, itself linked to its start time.
+//!   (entry seq <n>)            Position within the session, from 0.
+//!   (entry time <secs>)        Seconds since the UNIX epoch.
+
+use std::convert::TryFrom;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::history_context::HistoryContext;
+use super::Agent;
+use crate::agent::lang_error::LangError;
+use crate::env::LocalNode;
+use crate::error::Error;
+use crate::primitive::prelude::*;
+use crate::sexp::Sexp;
+
+
+/// Records entries into the Agent's history env as part of a single session.
+pub struct HistorySession {
+    session: Node,
+    seq: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistoryEntry {
+    pub node: Node,
+    pub input: Sexp,
+    /// Result, or the reified error.
+    pub outcome: Result<Sexp, Sexp>,
+    pub session: Node,
+    pub seq: usize,
+    pub time: u64,
+}
+
+impl HistorySession {
+    /// Start a new session in the history env of agent.
+    pub fn start(agent: &mut Agent) -> Result<Self, Error> {
+        let context = context(agent)?;
+        let env = context.node().env();
+        let session = agent.define_to(env, None)?;
+        link(
+            agent,
+            &context,
+            session,
+            *context.time(),
+            Number::U64(now()).into(),
+        )?;
+        Ok(Self { session, seq: 0 })
+    }
+
+    pub fn node(&self) -> Node {
+        self.session
+    }
+
+    /// Define input in the history env, to be recorded once interpreted.
+    pub fn define_input(&self, agent: &mut Agent, input: Sexp) -> Result<Node, Error> {
+        let env = context(agent)?.node().env();
+        agent.define_to(env, Some(input))
+    }
+
+    /// Record input, as defined by define_input, and what interpreting it
+    /// resulted in as the next entry of the session.
+    pub fn record(
+        &mut self,
+        agent: &mut Agent,
+        input: Node,
+        outcome: &Result<Sexp, Error>,
+    ) -> Result<Node, Error> {
+        let context = context(agent)?;
+        let env = context.node().env();
+        let entry = agent.define_to(env, None)?;
+        agent.tell_to(env, entry, Node::new(env, *context.input()), input)?;
+        match outcome {
+            Ok(result) => link(agent, &context, entry, *context.result(), result.clone())?,
+            Err(err) => {
+                let reified = err.kind().reify(agent);
+                link(agent, &context, entry, *context.error(), reified)?
+            }
+        };
+        let predicate = Node::new(context.node().env(), *context.session());
+        agent.tell_to(context.node().env(), entry, predicate, self.session)?;
+        link(
+            agent,
+            &context,
+            entry,
+            *context.seq(),
+            Number::USize(self.seq).into(),
+        )?;
+        link(
+            agent,
+            &context,
+            entry,
+            *context.time(),
+            Number::U64(now()).into(),
+        )?;
+
+        self.seq += 1;
+        Ok(entry)
+    }
+}
+
+
+impl HistoryEntry {
+    /// All entries of agent's history env, across sessions, in the order
+    /// they were recorded.
+    pub fn all(agent: &Agent) -> Result<Vec<Self>, Error> {
+        Self::last(agent, usize::MAX)
+    }
+
+    /// The last n entries of agent's history env, in the order they were
+    /// recorded. Only these are loaded, entries being defined in order.
+    pub fn last(agent: &Agent, n: usize) -> Result<Vec<Self>, Error> {
+        let context = context(agent)?;
+        let env = context.node().env();
+        let seq = Node::new(env, *context.seq());
+
+        let mut nodes = agent
+            .ask_from(env, None, Some(seq), None)?
+            .triples()
+            .map(|triple| agent.access_env(env).unwrap().triple_subject(triple))
+            .collect::<Vec<_>>();
+        nodes.sort_by_key(|node| node.id());
+        let recent = nodes.split_off(nodes.len().saturating_sub(n));
+
+        let mut entries = vec![];
+        for node in recent {
+            if let Some(entry) = Self::load(agent, &context, Node::new(env, node))? {
+                entries.push(entry);
+            }
+        }
+        entries.sort_by_key(|entry| (entry.time, entry.seq));
+        Ok(entries)
+    }
+
+    /// Entries recorded at or after time, in the order they were recorded.
+    pub fn since(agent: &Agent, time: u64) -> Result<Vec<Self>, Error> {
+        let mut entries = Self::all(agent)?;
+        entries.retain(|entry| entry.time >= time);
+        Ok(entries)
+    }
+
+    /// Whether the entry's input or outcome involves node, either directly
+    /// or through a symbol which agent currently resolves to node.
+    pub fn touches(&self, agent: &Agent, node: Node) -> bool {
+        let outcome = match &self.outcome {
+            Ok(result) => result,
+            Err(err) => err,
+        };
+        involves(agent, &self.input, node) || involves(agent, outcome, node)
+    }
+
+    /// Entry at node, or None if node isn't a complete entry.
+    pub fn get(agent: &Agent, node: Node) -> Result<Option<Self>, Error> {
+        Self::load(agent, &context(agent)?, node)
+    }
+
+    /// Fields as a table, for use from Amlang.
+    pub fn to_table(&self) -> SymSexpTable {
+        let mut table = SymSexpTable::default();
+        let mut insert = |name: &str, val: Sexp| {
+            table.insert(name.to_symbol_or_panic(policy_base), val);
+        };
+        insert("input", self.input.clone());
+        match &self.outcome {
+            Ok(result) => insert("result", result.clone()),
+            Err(err) => insert("error", err.clone()),
+        }
+        insert("session", self.session.into());
+        insert("seq", Number::USize(self.seq).into());
+        insert("time", Number::U64(self.time).into());
+        table
+    }
+
+    fn load(agent: &Agent, context: &HistoryContext, node: Node) -> Result<Option<Self>, Error> {
+        let field = |predicate: LocalNode| -> Result<Option<Sexp>, Error> {
+            let predicate = Node::new(node.env(), predicate);
+            match agent
+                .ask_from(node.env(), Some(node), Some(predicate), None)?
+                .objects()
+                .next()
+            {
+                Some(object) => Ok(Some(agent.designate(Node::new(node.env(), object).into())?)),
+                None => Ok(None),
+            }
+        };
+        let number = |sexp: Option<Sexp>| sexp.and_then(|sexp| Number::try_from(sexp).ok());
+
+        let input = match field(*context.input())? {
+            Some(input) => input,
+            None => return Ok(None),
+        };
+        let outcome = match (field(*context.result())?, field(*context.error())?) {
+            (Some(result), _) => Ok(result),
+            (None, Some(err)) => Err(err),
+            (None, None) => return Ok(None),
+        };
+        let session = node_object(agent, node, *context.session())?;
+        let seq = number(field(*context.seq())?).and_then(|n| usize::try_from(n).ok());
+        let time = number(field(*context.time())?).and_then(|n| u64::try_from(n).ok());
+
+        Ok(match (session, seq, time) {
+            (Some(session), Some(seq), Some(time)) => Some(Self {
+                node,
+                input,
+                outcome,
+                session,
+                seq,
+                time,
+            }),
+            _ => None,
+        })
+    }
+}
+
+
+/// Seconds since the UNIX epoch, as recorded in history entries.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn context(agent: &Agent) -> Result<HistoryContext, Error> {
+    match agent.history_context() {
+        Some(context) => Ok(context.clone()),
+        None => err!(
+            agent,
+            LangError::InvalidState {
+                actual: "no history context".into(),
+                expected: "Agent with history context".into(),
+            }
+        ),
+    }
+}
+
+// Link subject to a new node holding structure, through predicate.
+fn link(
+    agent: &mut Agent,
+    context: &HistoryContext,
+    subject: Node,
+    predicate: LocalNode,
+    structure: Sexp,
+) -> Result<Node, Error> {
+    let env = context.node().env();
+    let object = agent.define_to(env, Some(structure))?;
+    agent.tell_to(env, subject, Node::new(env, predicate), object)
+}
+
+fn node_object(agent: &Agent, subject: Node, predicate: LocalNode) -> Result<Option<Node>, Error> {
+    let env = subject.env();
+    Ok(agent
+        .ask_from(env, Some(subject), Some(Node::new(env, predicate)), None)?
+        .objects()
+        .next()
+        .map(|object| Node::new(env, object)))
+}
+
+fn involves(agent: &Agent, sexp: &Sexp, node: Node) -> bool {
+    match sexp {
+        Sexp::Primitive(Primitive::Node(n)) => *n == node,
+        Sexp::Primitive(Primitive::Symbol(symbol)) => agent.resolve_name(symbol).ok() == Some(node),
+        Sexp::Primitive(_) => false,
+        Sexp::Cons(_) => sexp.iter().any(|(elem, _)| involves(agent, elem, node)),
+    }
+}
@@ -0,0 +1,26 @@
+use derive_getters::Getters;
+use serde::{Deserialize, Serialize};
+
+use super::context::Context;
+use crate::env::LocalNode;
+use crate::primitive::Node;
+
+
+/// Nodes of history.env, used to record inputs as history entries.
+///
+/// Each entry is an atom with triples linking it to its input, result or
+/// error, session, seq & time. See HistorySession.
+#[derive(Clone, Debug, Deserialize, Getters, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct HistoryContext {
+    node: Node,
+
+    input: LocalNode,
+    result: LocalNode,
+    error: LocalNode,
+    session: LocalNode,
+    seq: LocalNode,
+    time: LocalNode,
+}
+
+impl<'de> Context<'de> for HistoryContext {}
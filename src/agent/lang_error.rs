@@ -1,9 +1,11 @@
 use std::borrow::Cow;
 use std::fmt;
 
+use crate::agent::Agent;
+use crate::env::LocalNode;
 use crate::error::ErrorKind;
 use crate::primitive::{Number, Symbol};
-use crate::sexp::{Cons, Sexp};
+use crate::sexp::Sexp;
 
 
 #[derive(Clone, Debug, PartialEq)]
@@ -26,6 +28,9 @@ pub enum LangError {
     DuplicateTriple(Sexp),
     RejectedTriple(Sexp, Sexp),
     Unsupported(Cow<'static, str>),
+    NonExhaustiveMatch(Sexp),
+    Overflow(Sexp),
+    DivisionByZero(Sexp),
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -33,48 +38,102 @@ pub enum ExpectedCount {
     Exactly(usize),
     AtLeast(usize),
     AtMost(usize),
+    // For arguments taken in pairs, possibly after a leading one.
+    Even,
+    Odd,
 }
 
 
 impl ErrorKind for LangError {
-    // TODO(func) Model within env rather than fall back on strings.
-    fn reify(&self) -> Sexp {
-        let inner = match self {
+    fn reify(&self, agent: &Agent) -> Sexp {
+        let context = agent.error_context();
+        let (kind, fields): (_, Vec<(LocalNode, Sexp)>) = match self {
+            Self::InvalidArgument { given, expected } => (
+                context.invalid_argument(),
+                vec![
+                    (*context.given(), given.clone()),
+                    (*context.expected(), expected.clone().into_owned().into()),
+                ],
+            ),
+            Self::InvalidState { actual, expected } => (
+                context.invalid_state(),
+                vec![
+                    (*context.actual(), actual.clone().into_owned().into()),
+                    (*context.expected(), expected.clone().into_owned().into()),
+                ],
+            ),
+            Self::InvalidSexp(val) => (
+                context.invalid_sexp(),
+                vec![(*context.given(), val.clone())],
+            ),
+            Self::WrongArgumentCount { given, expected } => (
+                context.wrong_argument_count(),
+                vec![
+                    (*context.given(), Number::USize(*given).into()),
+                    (*context.expected(), expected.to_string().into()),
+                ],
+            ),
+            Self::UnboundSymbol(symbol) => (
+                context.unbound_symbol(),
+                vec![(*context.symbol(), symbol.clone().into())],
+            ),
+            Self::AlreadyBoundSymbol(symbol) => (
+                context.already_bound_symbol(),
+                vec![(*context.symbol(), symbol.clone().into())],
+            ),
+            Self::DuplicateTriple(sexp) => (
+                context.duplicate_triple(),
+                vec![(*context.triple(), sexp.clone())],
+            ),
+            Self::RejectedTriple(triple, reason) => (
+                context.rejected_triple(),
+                vec![
+                    (*context.triple(), triple.clone()),
+                    (*context.reason(), reason.clone()),
+                ],
+            ),
+            Self::Unsupported(msg) => (
+                context.unsupported(),
+                vec![(*context.message(), msg.clone().into_owned().into())],
+            ),
+            Self::NonExhaustiveMatch(given) => (
+                context.non_exhaustive_match(),
+                vec![(*context.given(), given.clone())],
+            ),
+            Self::Overflow(given) => (context.overflow(), vec![(*context.given(), given.clone())]),
+            Self::DivisionByZero(given) => (
+                context.division_by_zero(),
+                vec![(*context.given(), given.clone())],
+            ),
+        };
+        context.reify_error(*context.lang_error(), *kind, fields)
+    }
+}
+
+impl fmt::Display for LangError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
             Self::InvalidArgument { given, expected } => {
-                list!(
-                    "InvalidArgument",
-                    ("given", given.clone()),
-                    ("expected", expected.clone().into_owned()),
-                )
+                write!(f, "invalid argument {}; expected {}", given, expected)
             }
             Self::InvalidState { actual, expected } => {
-                list!(
-                    "InvalidState",
-                    ("actual", actual.clone().into_owned()),
-                    ("expected", expected.clone().into_owned()),
-                )
-            }
-            Self::InvalidSexp(val) => list!("InvalidSexp", val.clone()),
-            Self::WrongArgumentCount { given, expected } => list!(
-                "WrongArgumentCount",
-                ("given", Number::USize(*given),),
-                ("expected", expected.to_string(),),
-            ),
-            Self::UnboundSymbol(symbol) => {
-                list!("UnboundSymbol", symbol.clone())
+                write!(f, "invalid state {}; expected {}", actual, expected)
             }
-            Self::AlreadyBoundSymbol(symbol) => {
-                list!("AlreadyBoundSymbol", symbol.clone())
-            }
-            Self::DuplicateTriple(sexp) => {
-                list!("DuplicateTriple", sexp.clone())
+            Self::InvalidSexp(val) => write!(f, "invalid sexp {}", val),
+            Self::WrongArgumentCount { given, expected } => {
+                write!(f, "wrong argument count {}; expected {}", given, expected)
             }
+            Self::UnboundSymbol(symbol) => write!(f, "unbound symbol {}", symbol),
+            Self::AlreadyBoundSymbol(symbol) => write!(f, "symbol {} is already bound", symbol),
+            Self::DuplicateTriple(sexp) => write!(f, "duplicate triple {}", sexp),
             Self::RejectedTriple(triple, reason) => {
-                list!("RejectedTriple", triple.clone(), reason.clone(),)
+                write!(f, "rejected triple {}: {}", triple, reason)
             }
-            Self::Unsupported(msg) => list!("Unsupported", msg.clone().into_owned()),
-        };
-        Cons::new(Sexp::from("LangError"), inner).into()
+            Self::Unsupported(msg) => write!(f, "unsupported: {}", msg),
+            Self::NonExhaustiveMatch(given) => write!(f, "no match clause for {}", given),
+            Self::Overflow(given) => write!(f, "overflow in {}", given),
+            Self::DivisionByZero(given) => write!(f, "division by zero in {}", given),
+        }
     }
 }
 
@@ -84,6 +143,8 @@ impl fmt::Display for ExpectedCount {
             Self::Exactly(exactly) => write!(f, "{}", exactly),
             Self::AtLeast(minimum) => write!(f, "AtLeast {}", minimum),
             Self::AtMost(maximum) => write!(f, "AtMost {}", maximum),
+            Self::Even => write!(f, "Even"),
+            Self::Odd => write!(f, "Odd"),
         };
     }
 }
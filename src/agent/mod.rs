@@ -1,14 +1,20 @@
 // Public exports.
 pub use agent::Agent;
+pub use agent_io::AgentIo;
 pub use amlang_context::AmlangContext;
 pub use amlang_interpreter::AmlangInterpreter;
 pub use base_deserializer::BaseDeserializer;
 pub use base_serializer::BaseSerializer;
 pub use context::Context;
 pub use env_manager::EnvManager;
+pub use env_sources::EnvSources;
+pub use error_context::ErrorContext;
 pub use executor::TransformExecutor;
+pub use history::{HistoryEntry, HistorySession};
+pub use history_context::HistoryContext;
 pub use interpreter::NullInterpreter;
 pub use lang_error::{ExpectedCount, LangError};
+pub use print_settings::PrintSettings;
 pub use vm_interpreter::VmInterpreter;
 
 // Public mods.
@@ -16,15 +22,22 @@ pub use vm_interpreter::VmInterpreter;
 pub mod context;
 pub mod agent;
 pub mod agent_frames;
+pub mod agent_io;
 pub mod amlang_context;
 pub mod amlang_interpreter;
 pub mod base_deserializer;
 pub mod base_serializer;
 pub mod env_manager;
 pub mod env_policy;
+pub mod env_sources;
+pub mod error_context;
 pub mod executor;
+pub mod history;
+pub mod history_context;
 pub mod interpreter;
 pub mod lang_error;
+pub mod print_settings;
+pub mod script;
 pub mod vm_interpreter;
 
 // Private mods.
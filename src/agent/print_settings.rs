@@ -0,0 +1,141 @@
+//! Settings controlling how an Agent prints structures.
+//!
+//! Settings are also accessible by name, so that Amlang can introspect and
+//! adjust them (see the print-settings & set-print-setting! builtins).
+
+use std::convert::TryFrom;
+
+use crate::primitive::prelude::*;
+use crate::sexp::Sexp;
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PrintSettings {
+    /// Lists which don't fit within this width are broken across lines.
+    /// None prints everything on one line.
+    pub max_width: Option<usize>,
+    /// Indentation of the body of broken lists, relative to their open paren.
+    pub indent: usize,
+    /// Whether to color parens by depth.
+    pub color: bool,
+    /// Whether to show what Nodes & Procedures resolve to, e.g. [1:2]->3.
+    pub show_redirects: bool,
+    /// Any list longer than this will simply be suffixed with "..." after
+    /// this many elements.
+    pub max_length: Option<usize>,
+    /// Lists nested deeper than this are written as (..).
+    pub max_depth: Option<usize>,
+    /// Whether to write Nodes as their designations when possible, rather
+    /// than as raw Nodes.
+    pub label_nodes: bool,
+}
+
+impl Default for PrintSettings {
+    fn default() -> Self {
+        Self {
+            max_width: Some(80),
+            indent: 2,
+            color: true,
+            show_redirects: true,
+            max_length: Some(64),
+            max_depth: Some(16),
+            label_nodes: true,
+        }
+    }
+}
+
+
+impl PrintSettings {
+    pub const NAMES: [&'static str; 7] = [
+        "max-width",
+        "indent",
+        "color",
+        "show-redirects",
+        "max-length",
+        "max-depth",
+        "label-nodes",
+    ];
+
+    /// Value of setting name, with unset limits as '().
+    pub fn get(&self, name: &str) -> Option<Sexp> {
+        fn limit(val: Option<usize>) -> Sexp {
+            val.map(|n| Number::USize(n).into()).unwrap_or_default()
+        }
+
+        let val = match name {
+            "max-width" => limit(self.max_width),
+            "indent" => Number::USize(self.indent).into(),
+            "color" => self.color.into(),
+            "show-redirects" => self.show_redirects.into(),
+            "max-length" => limit(self.max_length),
+            "max-depth" => limit(self.max_depth),
+            "label-nodes" => self.label_nodes.into(),
+            _ => return None,
+        };
+        Some(val)
+    }
+
+    /// Set setting name to val, failing with a description of what was
+    /// expected if either is invalid.
+    pub fn set(&mut self, name: &str, val: Sexp) -> Result<(), &'static str> {
+        fn limit(val: Sexp) -> Result<Option<usize>, &'static str> {
+            if val.is_none() {
+                Ok(None)
+            } else {
+                count(val).map(Some)
+            }
+        }
+        fn count(val: Sexp) -> Result<usize, &'static str> {
+            Number::try_from(val)
+                .ok()
+                .and_then(|n| usize::try_from(n).ok())
+                .ok_or("nonnegative integer")
+        }
+        fn flag(val: Sexp) -> Result<bool, &'static str> {
+            bool::try_from(val).map_err(|_| "bool")
+        }
+
+        match name {
+            "max-width" => self.max_width = limit(val)?,
+            "indent" => self.indent = count(val)?,
+            "color" => self.color = flag(val)?,
+            "show-redirects" => self.show_redirects = flag(val)?,
+            "max-length" => self.max_length = limit(val)?,
+            "max-depth" => self.max_depth = limit(val)?,
+            "label-nodes" => self.label_nodes = flag(val)?,
+            _ => return Err("print setting name"),
+        }
+        Ok(())
+    }
+
+    pub fn to_table(&self) -> SymSexpTable {
+        let mut table = SymSexpTable::default();
+        for name in Self::NAMES {
+            table.insert(
+                name.to_symbol_or_panic(policy_base),
+                self.get(name).unwrap(),
+            );
+        }
+        table
+    }
+
+    pub fn paren_color(&self, depth: usize) -> Option<(u8, u8, u8)> {
+        if !self.color {
+            return None;
+        }
+        Some(paren_color(depth))
+    }
+}
+
+
+/// Color of parens at depth, shared by printing & the REPL highlighter.
+pub fn paren_color(depth: usize) -> (u8, u8, u8) {
+    match depth % 6 {
+        0 => (0, 255, 204),
+        1 => (204, 51, 0),
+        2 => (153, 255, 102),
+        3 => (153, 102, 255),
+        4 => (255, 255, 102),
+        _ => (255, 179, 179),
+    }
+}
@@ -0,0 +1,133 @@
+//! Non-interactive interpretation of Amlang source, e.g. from .aml files.
+
+use std::path::Path;
+
+use super::Agent;
+use crate::env::LocalNode;
+use crate::error::Error;
+use crate::parser::Parser;
+use crate::primitive::prelude::*;
+use crate::sexp::Sexp;
+use crate::stream::input::FileReader;
+use crate::stream::Transform;
+use crate::token::{Token, Tokenizer};
+
+
+/// Name under which bind_args designates script arguments.
+pub const ARGS_NAME: &str = "script-args";
+
+/// Interpret each form read from lines, stopping at the first error.
+///
+/// Returns the last result. If agent has a source map, lines are recorded in
+/// it as source name so errors can point back at them.
+pub fn run<I: Iterator<Item = Result<String, Error>>>(
+    agent: &mut Agent,
+    name: &str,
+    lines: I,
+) -> Result<Sexp, Error> {
+    let (mut tokenizer, mut parser) = stages(agent, name);
+
+    // Drive the stages directly, rather than through pull_transform!, so
+    // that forms left open at the end of input are reported.
+    let mut last = Sexp::default();
+    for line in lines {
+        <dyn Transform<String, Token>>::input(&mut tokenizer, line)?;
+        while let Some(token) = <dyn Transform<String, Token>>::output(&mut tokenizer) {
+            parser.input(token)?;
+            while let Some(sexp) = parser.output() {
+                last = agent.interpret(sexp?)?;
+            }
+        }
+    }
+    tokenizer.finish().map_err(Error::no_cont)?;
+    parser.finish().map_err(Error::no_cont)?;
+    Ok(last)
+}
+
+/// Interpret the file at path, as per run.
+pub fn run_file<P: AsRef<Path>>(agent: &mut Agent, path: P) -> Result<Sexp, Error> {
+    let input = FileReader::new(path.as_ref())?;
+    run(agent, &path.as_ref().to_string_lossy(), input)
+}
+
+/// Read all forms from lines without interpreting them, failing on any
+/// syntax error.
+///
+/// As with run, spans are recorded in agent's source map, if any; the Sexps
+/// must then be interpreted in order.
+pub fn read<I: Iterator<Item = Result<String, Error>>>(
+    agent: &Agent,
+    name: &str,
+    lines: I,
+) -> Result<Vec<Sexp>, Error> {
+    let (mut tokenizer, mut parser) = stages(agent, name);
+    let mut forms = vec![];
+    for line in lines {
+        <dyn Transform<String, Token>>::input(&mut tokenizer, line)?;
+        while let Some(token) = <dyn Transform<String, Token>>::output(&mut tokenizer) {
+            parser.input(token)?;
+            while let Some(sexp) = parser.output() {
+                forms.push(sexp?);
+            }
+        }
+    }
+    tokenizer.finish().map_err(Error::no_cont)?;
+    parser.finish().map_err(Error::no_cont)?;
+    Ok(forms)
+}
+
+/// Read the file at path, as per read.
+pub fn read_file<P: AsRef<Path>>(agent: &Agent, path: P) -> Result<Vec<Sexp>, Error> {
+    let input = FileReader::new(path.as_ref())?;
+    read(agent, &path.as_ref().to_string_lossy(), input)
+}
+
+/// Designate args as a list of strings under ARGS_NAME in the current env,
+/// replacing any previous args.
+pub fn bind_args<S: AsRef<str>, I: IntoIterator<Item = S>>(
+    agent: &mut Agent,
+    args: I,
+) -> Result<Node, Error> {
+    let args = args
+        .into_iter()
+        .map(|arg| Sexp::from(LangString::new(arg.as_ref())))
+        .collect::<Vec<_>>();
+    let name = ARGS_NAME.to_symbol_or_panic(policy_base);
+    let env = agent.pos().env();
+    match agent.resolve_name(&name) {
+        Ok(node) if node.env() == env => {
+            agent.set(node, Some(args.into()))?;
+            Ok(node)
+        }
+        _ => {
+            let node = agent.define(Some(args.into()))?;
+            agent
+                .access_env_mut(env)
+                .unwrap()
+                .insert_designation(node, name, LocalNode::default());
+            Ok(node)
+        }
+    }
+}
+
+/// Clear the args bound by bind_args, e.g. so that saving envs doesn't
+/// persist them. The designation is kept for bind_args to reuse.
+pub fn unbind_args(agent: &mut Agent) -> Result<(), Error> {
+    let name = ARGS_NAME.to_symbol_or_panic(policy_base);
+    let env = agent.pos().env();
+    match agent.resolve_name(&name) {
+        Ok(node) if node.env() == env => agent.set(node, None),
+        _ => Ok(()),
+    }
+}
+
+fn stages(agent: &Agent, name: &str) -> (Tokenizer<()>, Parser) {
+    let mut tokenizer = Tokenizer::new(policy_base).with_grammar(agent.symbol_grammar().clone());
+    let mut parser = Parser::new();
+    if let Some(map) = agent.source_map().cloned() {
+        let source = map.borrow_mut().add_source(name);
+        tokenizer = tokenizer.with_source(map.clone(), source);
+        parser = parser.with_source(map, source);
+    }
+    (tokenizer, parser)
+}
@@ -1,12 +1,15 @@
 use log::debug;
 use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
 
 use super::agent_frames::ExecFrame;
 use super::amlang_context::AmlangContext;
 use super::amlang_wrappers::*;
 use super::interpreter::{Interpreter, InterpreterState};
+use super::script;
 use super::Agent;
 use crate::agent::lang_error::{ExpectedCount, LangError};
+use crate::builtins::bounded_index;
 use crate::env::LocalNode;
 use crate::error::Error;
 use crate::primitive::prelude::*;
@@ -20,6 +23,8 @@ pub struct VmInterpreter {
     impl_env: LocalNode,
 
     context: AmlangContext,
+    // Files being loaded, innermost last.
+    loading: Vec<PathBuf>,
 }
 
 impl VmInterpreter {
@@ -29,6 +34,7 @@ impl VmInterpreter {
             impl_env,
 
             context,
+            loading: vec![],
         }
     }
 }
@@ -84,20 +90,11 @@ impl<'a> ExecutingInterpreter<'a> {
                         let (pred, a, b) = *t;
                         let cond = self.exec(pred)?;
 
-                        // TODO(func) Integrate actual boolean type.
-                        let context = &self.state.context;
-                        if cond == context_node!(t, context).into() {
-                            Ok(self.exec(a)?)
-                        } else if cond == context_node!(f, context).into() {
+                        // Only false is falsey.
+                        if cond == false.into() {
                             Ok(self.exec(b)?)
                         } else {
-                            err!(
-                                self.agent(),
-                                LangError::InvalidArgument {
-                                    given: cond,
-                                    expected: "true or false Node".into(),
-                                }
-                            )
+                            Ok(self.exec(a)?)
                         }
                     }
                     Procedure::Sequence(seq) => {
@@ -109,6 +106,7 @@ impl<'a> ExecutingInterpreter<'a> {
                     }
                     lambda @ Procedure::UserAbstraction(..) => Ok(lambda.into()),
                     fexpr @ Procedure::InterpreterAbstraction(..) => Ok(fexpr.into()),
+                    mac @ Procedure::MacroAbstraction(..) => Ok(mac.into()),
                 }
             }
             _ => Ok(meaning),
@@ -144,6 +142,10 @@ impl<'a> ExecutingInterpreter<'a> {
             | Sexp::Primitive(Primitive::Procedure(Procedure::InterpreterAbstraction(
                 params,
                 body_node,
+            )))
+            | Sexp::Primitive(Primitive::Procedure(Procedure::MacroAbstraction(
+                params,
+                body_node,
             ))) => {
                 if arg_nodes.len() != params.len() {
                     return err!(
@@ -165,7 +167,7 @@ impl<'a> ExecutingInterpreter<'a> {
 
                 self.exec(body_node)
             }
-            not_proc @ _ => err!(
+            not_proc => err!(
                 self.agent(),
                 LangError::InvalidArgument {
                     given: not_proc.clone(),
@@ -184,6 +186,34 @@ impl<'a> ExecutingInterpreter<'a> {
         }
     }
 
+    // Local part of the Node node executes to, which must be in env.
+    fn exec_to_env_node(&mut self, node: Node, env: LocalNode) -> Result<LocalNode, Error> {
+        let node = self.exec_to_node(node)?;
+        if node.env() != env {
+            return err!(
+                self.agent(),
+                LangError::InvalidArgument {
+                    given: node.into(),
+                    expected: format!("Node in env {}", env).into(),
+                }
+            );
+        }
+        Ok(node.local())
+    }
+
+    fn exec_symbol(&mut self, node: Node) -> Result<Symbol, Error> {
+        match Symbol::try_from(self.exec(node)?) {
+            Ok(symbol) => Ok(symbol),
+            Err(given) => err!(
+                self.agent(),
+                LangError::InvalidArgument {
+                    given,
+                    expected: "Symbol".into(),
+                }
+            ),
+        }
+    }
+
     fn apply_special(
         &mut self,
         special_node: LocalNode,
@@ -291,6 +321,219 @@ impl<'a> ExecutingInterpreter<'a> {
                 }
                 Ok(node.into())
             }
+            _ if *context.vector() == special_node => {
+                let mut elements = Vec::with_capacity(arg_nodes.len());
+                for node in arg_nodes {
+                    elements.push(self.exec(node)?);
+                }
+                Ok(Vector::from(elements).into())
+            }
+            _ if *context.vector_ref() == special_node => {
+                if arg_nodes.len() != 2 {
+                    return err!(
+                        self.agent(),
+                        LangError::WrongArgumentCount {
+                            given: arg_nodes.len(),
+                            expected: ExpectedCount::Exactly(2),
+                        }
+                    );
+                }
+
+                // Index Vectors structuring Nodes in place rather than
+                // copying them out through exec.
+                let mut node = arg_nodes[0];
+                if self.with_vector(node, |_| ()).is_none() {
+                    match self.exec(arg_nodes[0])? {
+                        Sexp::Primitive(Primitive::Node(target)) => node = target,
+                        Sexp::Primitive(Primitive::Vector(vector)) => {
+                            let index = self.exec_index(arg_nodes[1], vector.len())?;
+                            return Ok(vector.into_vec().swap_remove(index));
+                        }
+                        given => {
+                            return err!(
+                                self.agent(),
+                                LangError::InvalidArgument {
+                                    given,
+                                    expected: "Vector or Node designating a Vector".into(),
+                                }
+                            );
+                        }
+                    }
+                }
+                let len = match self.with_vector(node, |vector| vector.len()) {
+                    Some(len) => len,
+                    None => {
+                        return err!(
+                            self.agent(),
+                            LangError::InvalidArgument {
+                                given: node.into(),
+                                expected: "Vector or Node designating a Vector".into(),
+                            }
+                        );
+                    }
+                };
+                let index = self.exec_index(arg_nodes[1], len)?;
+                Ok(self
+                    .with_vector(node, |vector| vector.as_slice()[index].clone())
+                    .unwrap())
+            }
+            _ if *context.vector_set() == special_node => {
+                if arg_nodes.len() != 3 {
+                    return err!(
+                        self.agent(),
+                        LangError::WrongArgumentCount {
+                            given: arg_nodes.len(),
+                            expected: ExpectedCount::Exactly(3),
+                        }
+                    );
+                }
+
+                // Mutate the Vector in place, so the target must be a Node
+                // whose structure is a Vector rather than a bare value.
+                let node = if self.with_vector(arg_nodes[0], |_| ()).is_some() {
+                    arg_nodes[0]
+                } else {
+                    self.exec_to_node(arg_nodes[0])?
+                };
+                let len = match self.with_vector(node, |vector| vector.len()) {
+                    Some(len) => len,
+                    None => {
+                        return err!(
+                            self.agent(),
+                            LangError::InvalidArgument {
+                                given: node.into(),
+                                expected: "Node designating a Vector".into(),
+                            }
+                        );
+                    }
+                };
+                let index = self.exec_index(arg_nodes[1], len)?;
+                let val = self.exec(arg_nodes[2])?;
+                let mut entry = self
+                    .agent_mut()
+                    .access_env_mut(node.env())
+                    .unwrap()
+                    .entry_mut(node.local());
+                if let Some(Sexp::Primitive(Primitive::Vector(vector))) = entry.as_option() {
+                    *vector.get_mut(index).unwrap() = val;
+                }
+                Ok(node.into())
+            }
+            _ if *context.sym_node_table() == special_node
+                || *context.sym_sexp_table() == special_node =>
+            {
+                let sexp_values = *context.sym_sexp_table() == special_node;
+                if arg_nodes.len() % 2 == 1 {
+                    return err!(
+                        self.agent(),
+                        LangError::WrongArgumentCount {
+                            given: arg_nodes.len(),
+                            expected: ExpectedCount::Even,
+                        }
+                    );
+                }
+
+                let mut node_table = SymNodeTable::default();
+                let mut sexp_table = SymSexpTable::default();
+                for pair in arg_nodes.chunks(2) {
+                    let key = self.exec_symbol(pair[0])?;
+                    if sexp_values {
+                        sexp_table.insert(key, self.exec(pair[1])?);
+                    } else {
+                        node_table.insert(key, self.exec_to_node(pair[1])?);
+                    }
+                }
+                if sexp_values {
+                    Ok(sexp_table.into())
+                } else {
+                    Ok(node_table.into())
+                }
+            }
+            _ if *context.local_node_table() == special_node => {
+                if arg_nodes.len() % 2 != 1 {
+                    return err!(
+                        self.agent(),
+                        LangError::WrongArgumentCount {
+                            given: arg_nodes.len(),
+                            expected: ExpectedCount::Odd,
+                        }
+                    );
+                }
+
+                // Accept either an env's meta node or any node within the env.
+                let env = self.exec_to_node(arg_nodes[0])?;
+                let env = if env.env() == LocalNode::default() {
+                    env.local()
+                } else {
+                    env.env()
+                };
+                let mut table = LocalNodeTable::in_env(env);
+                for pair in arg_nodes[1..].chunks(2) {
+                    let key = self.exec_to_env_node(pair[0], env)?;
+                    let val = self.exec_to_env_node(pair[1], env)?;
+                    table.insert(key, val);
+                }
+                Ok(table.into())
+            }
+            _ if *context.table_insert() == special_node
+                || *context.table_remove() == special_node =>
+            {
+                let is_insert = *context.table_insert() == special_node;
+                let expected = if is_insert { 3 } else { 2 };
+                if arg_nodes.len() != expected {
+                    return err!(
+                        self.agent(),
+                        LangError::WrongArgumentCount {
+                            given: arg_nodes.len(),
+                            expected: ExpectedCount::Exactly(expected),
+                        }
+                    );
+                }
+
+                // As with vector-set!, mutate the table of the target Node.
+                let node = self.exec_to_node(arg_nodes[0])?;
+                let structure: Sexp = match self.agent().designate(node.into())? {
+                    Sexp::Primitive(Primitive::SymNodeTable(mut table)) => {
+                        let key = self.exec_symbol(arg_nodes[1])?;
+                        if is_insert {
+                            table.insert(key, self.exec_to_node(arg_nodes[2])?);
+                        } else {
+                            table.remove(&key);
+                        }
+                        table.into()
+                    }
+                    Sexp::Primitive(Primitive::SymSexpTable(mut table)) => {
+                        let key = self.exec_symbol(arg_nodes[1])?;
+                        if is_insert {
+                            table.insert(key, self.exec(arg_nodes[2])?);
+                        } else {
+                            table.remove(&key);
+                        }
+                        table.into()
+                    }
+                    Sexp::Primitive(Primitive::LocalNodeTable(mut table)) => {
+                        let key = self.exec_to_env_node(arg_nodes[1], table.env())?;
+                        if is_insert {
+                            let val = self.exec_to_env_node(arg_nodes[2], table.env())?;
+                            table.insert(key, val);
+                        } else {
+                            table.remove(&key);
+                        }
+                        table.into()
+                    }
+                    _ => {
+                        return err!(
+                            self.agent(),
+                            LangError::InvalidArgument {
+                                given: node.into(),
+                                expected: "Node designating a table".into(),
+                            }
+                        );
+                    }
+                };
+                self.agent_mut().set(node, Some(structure))?;
+                Ok(node.into())
+            }
             _ if *context.import() == special_node => {
                 if arg_nodes.len() != 1 {
                     return err!(
@@ -307,6 +550,25 @@ impl<'a> ExecutingInterpreter<'a> {
                 let imported = self.agent_mut().import(original)?;
                 Ok(imported.into())
             }
+            _ if *context.load_file() == special_node || *context.module() == special_node => {
+                if arg_nodes.len() != 1 {
+                    return err!(
+                        self.agent(),
+                        LangError::WrongArgumentCount {
+                            given: arg_nodes.len(),
+                            expected: ExpectedCount::Exactly(1),
+                        }
+                    );
+                }
+
+                let is_load = *context.load_file() == special_node;
+                let path = self.exec_path(arg_nodes[0])?;
+                if is_load {
+                    self.load(&path)
+                } else {
+                    Ok(self.module(&path)?.into())
+                }
+            }
             _ if *context.apply() == special_node => {
                 let (proc_node, args_node) = apply_wrapper(&arg_nodes, &self.agent())?;
                 let proc_sexp = self.agent_mut().designate(proc_node.into())?;
@@ -356,6 +618,163 @@ impl<'a> ExecutingInterpreter<'a> {
         }
     }
 
+    // Apply f to the Vector structuring node, if any, without copying it.
+    fn with_vector<R, F: FnOnce(&Vector) -> R>(&self, node: Node, f: F) -> Option<R> {
+        let entry = self.agent().access_env(node.env())?.entry(node.local());
+        match entry.as_option() {
+            Some(Sexp::Primitive(Primitive::Vector(vector))) => Some(f(vector)),
+            _ => None,
+        }
+    }
+
+    // Index into a Vector of length len.
+    fn exec_index(&mut self, node: Node, len: usize) -> Result<usize, Error> {
+        match Number::try_from(self.exec(node)?) {
+            Ok(num) => bounded_index(num, len, self.agent()),
+            Err(given) => err!(
+                self.agent(),
+                LangError::InvalidArgument {
+                    given,
+                    expected: "Number".into(),
+                }
+            ),
+        }
+    }
+
+    // Canonical path of a file to load, relative to that being loaded if any.
+    fn exec_path(&mut self, node: Node) -> Result<PathBuf, Error> {
+        let path = match self.exec(node)? {
+            Sexp::Primitive(Primitive::LangString(s)) => PathBuf::from(s.as_str()),
+            Sexp::Primitive(Primitive::LangPath(path)) => path.as_std_path().to_path_buf(),
+            given => {
+                return err!(
+                    self.agent(),
+                    LangError::InvalidArgument {
+                        given,
+                        expected: "String or Path".into(),
+                    }
+                );
+            }
+        };
+        let path = match self.state.loading.last().and_then(|file| file.parent()) {
+            Some(dir) => dir.join(path),
+            None => path,
+        };
+        Ok(path.canonicalize()?)
+    }
+
+    // Evaluate the forms of the file at path in the current env, returning
+    // the last result.
+    fn load(&mut self, path: &Path) -> Result<Sexp, Error> {
+        if self.state.loading.iter().any(|file| file == path) {
+            return err!(
+                self.agent(),
+                LangError::InvalidState {
+                    actual: format!("{} loaded while loading it", path.display()).into(),
+                    expected: "acyclic loads".into(),
+                }
+            );
+        }
+
+        let forms = script::read_file(self.agent(), path)?;
+        let interpreter_context = context_node!(load_file, self.state.context);
+        self.state.loading.push(path.to_path_buf());
+        let mut result = Ok(Sexp::default());
+        let mut forms = forms.into_iter();
+        for form in &mut forms {
+            result = self.eval(form, None, interpreter_context);
+            if result.is_err() {
+                break;
+            }
+        }
+        self.state.loading.pop();
+
+        // Drop spans of forms we never got to.
+        if let Some(map) = self.agent().source_map() {
+            let mut map = map.borrow_mut();
+            for form in forms {
+                map.pop_parsed(&form);
+            }
+        }
+        result
+    }
+
+    // Evaluate the file at path into its own env, unless already done, and
+    // make its designations available to the current env. Returns the env.
+    fn module(&mut self, path: &Path) -> Result<Node, Error> {
+        let env = match self.agent().module_env(path) {
+            Some(env) => env,
+            None => {
+                // Only listed once loaded, so failed loads aren't saved. Their
+                // env stays in place for continuations still referring into it,
+                // e.g. those of the error.
+                let env = self.agent_mut().create_unlisted_env()?;
+                let original_pos = self.agent().pos();
+                let original_dchain = self.agent().designation_chain().clone();
+
+                // Modules only see Amlang nodes & their own designations.
+                let lang_context = *self.state.context.node();
+                let dchain = self.agent_mut().designation_chain_mut();
+                dchain.clear();
+                dchain.push_back(lang_context);
+                dchain.push_back(Node::new(env, LocalNode::default()));
+                self.agent_mut().jump_env(env);
+
+                let result = self.load(path);
+                self.agent_mut().jump(original_pos);
+                *self.agent_mut().designation_chain_mut() = original_dchain;
+                result?;
+                let env_path = self.module_env_path(path);
+                self.agent_mut().list_env(env, env_path);
+                self.agent_mut().register_module(env, path);
+                env
+            }
+        };
+
+        // Exports of versions of the module since modified give way to these.
+        let superseded = self
+            .agent()
+            .module_envs(path)
+            .into_iter()
+            .filter(|module_env| *module_env != env)
+            .map(|module_env| Node::new(module_env, LocalNode::default()))
+            .collect::<Vec<_>>();
+        self.agent_mut()
+            .designation_chain_mut()
+            .retain(|node| !superseded.contains(node));
+
+        let exports = Node::new(env, LocalNode::default());
+        if !self.agent().designation_chain().contains(&exports) {
+            self.agent_mut().designation_chain_mut().push_back(exports);
+        }
+        let exported = self
+            .agent()
+            .access_env(env)
+            .unwrap()
+            .designation_pairs(LocalNode::default())
+            .into_iter()
+            .map(|(_, node)| *node)
+            .collect::<Vec<_>>();
+        for node in exported {
+            self.agent_mut().import(node)?;
+        }
+        Ok(Node::new(LocalNode::default(), env))
+    }
+
+    // Serialization path for the env of the module at path, named after it.
+    fn module_env_path(&self, path: &Path) -> PathBuf {
+        let stem = path
+            .file_stem()
+            .map_or("module".into(), |stem| stem.to_string_lossy());
+        let mut name = format!("{}.env", stem);
+        let mut i = 2;
+        while self.agent().find_env(&name).is_some() {
+            name = format!("{}-{}.env", stem, i);
+            i += 1;
+        }
+        PathBuf::from(name)
+    }
+
     // If we need Nodes in a particular context, we must abstract existing
     // Sexps into the env. However, if the sexp is already a Node, just use it
     // directly rather than create a stack of abstractions.
@@ -390,7 +809,14 @@ impl<'a> Interpreter for ExecutingInterpreter<'a> {
             node
         } else {
             let env = self.state.history_env;
-            self.agent_mut().define_to(env, Some(structure))?
+            let node = self.agent_mut().define_to(env, Some(structure))?;
+            if let Some(map) = self.agent().source_map() {
+                let mut map = map.borrow_mut();
+                if let Some(span) = map.take_root() {
+                    map.insert_node(node, span);
+                }
+            }
+            node
         };
         self.exec(node)
     }
@@ -1,48 +1,86 @@
-use lazy_static::lazy_static;
-
+use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::mem;
+use std::convert::TryFrom;
 
 use crate::agent::lang_error::LangError;
 use crate::agent::Agent;
 use crate::env::LocalNode;
 use crate::error::Error;
+use crate::primitive::number::NumberError;
 use crate::primitive::prelude::*;
-use crate::sexp::{Cons, HeapSexp, Sexp};
+use crate::sexp::{Cons, ConsList, HeapSexp, Sexp};
+
+
+mod history;
+mod io;
+mod string;
+mod table;
 
 
 // Used for bootstrapping and auxiliary purposes, not as an environment.
 pub fn generate_builtin_map() -> HashMap<&'static str, BuiltIn> {
-    macro_rules! builtins {
-        [$($x:expr),*] => {
-            {
-                let mut m = HashMap::new();
-                $(m.insert(stringify!($x), BuiltIn::new(stringify!($x), $x));)*
-                m
-            }
-        };
-        [$($n:tt : $x:expr),+ ,] => {
-            builtins![$($n : $x),*]
-        };
-    }
-
-    builtins![
-        car, cdr, cons, list_len, println, eq, curr, jump, env_find, env_jump, add, sub, mul, div
-    ]
+    let mut m = builtins![
+        car,
+        cdr,
+        cons,
+        is_cons,
+        list_len,
+        append,
+        print_settings,
+        set_print_setting,
+        eq,
+        not,
+        match_fail,
+        curr,
+        jump,
+        env_find,
+        env_jump,
+        add,
+        sub,
+        mul,
+        div,
+        modulo,
+        abs,
+        min,
+        max,
+        lt,
+        le,
+        gt,
+        ge,
+        num_eq,
+        vector_len,
+        vector_to_list,
+        list_to_vector,
+        vector_slice
+    ];
+    m.extend(history::generate_builtin_map());
+    m.extend(io::generate_builtin_map());
+    m.extend(string::generate_builtin_map());
+    m.extend(table::generate_builtin_map());
+    m
 }
 
 // Auto-gen builtins from raw rust functions.
 wrap_builtin!(car_(Cons) => car);
 wrap_builtin!(cdr_(Cons) => cdr);
 wrap_builtin!(cons_(HeapSexp, HeapSexp) => cons);
+wrap_builtin!(is_cons_(Sexp) => is_cons);
 wrap_builtin!(list_len_(HeapSexp) => list_len);
-wrap_builtin!(println_(Sexp) => println);
+wrap_builtin!(print_settings_() => print_settings);
+wrap_builtin!(set_print_setting_(Symbol, Sexp) => set_print_setting);
 wrap_builtin!(eq_(Sexp, Sexp) => eq);
+wrap_builtin!(not_(Sexp) => not);
+wrap_builtin!(match_fail_(Sexp) => match_fail);
 wrap_builtin!(curr_() => curr);
 wrap_builtin!(jump_(Node) => jump);
 wrap_builtin!(env_find_(LangString) => env_find);
 wrap_builtin!(env_jump_(Node) => env_jump);
-
+wrap_builtin!(modulo_(Number, Number) => modulo);
+wrap_builtin!(abs_(Number) => abs);
+wrap_builtin!(vector_len_(Vector) => vector_len);
+wrap_builtin!(vector_to_list_(Vector) => vector_to_list);
+wrap_builtin!(list_to_vector_(HeapSexp) => list_to_vector);
+wrap_builtin!(vector_slice_(Vector, Number, Number) => vector_slice);
 
 fn car_(cons: Cons, _agent: &mut Agent) -> Result<Sexp, Error> {
     if let Some(val) = cons.consume().0 {
@@ -66,6 +104,10 @@ fn cons_(car: HeapSexp, cdr: HeapSexp, _agent: &mut Agent) -> Result<Cons, Error
     Ok(Cons::new(to_option(car), to_option(cdr)))
 }
 
+fn is_cons_(sexp: Sexp, _agent: &mut Agent) -> Result<bool, Error> {
+    Ok(matches!(sexp, Sexp::Cons(_)) && !sexp.is_none())
+}
+
 fn list_len_(sexp: HeapSexp, agent: &mut Agent) -> Result<Number, Error> {
     let mut count = 0;
     for (_elem, proper) in sexp.iter() {
@@ -83,27 +125,71 @@ fn list_len_(sexp: HeapSexp, agent: &mut Agent) -> Result<Number, Error> {
     Ok(Number::USize(count).into())
 }
 
-fn println_(arg: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
-    agent.print_sexp(&arg);
-    println!("");
-    Ok(Sexp::default())
-}
+fn append(args: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    // All but the last list are copied; the last becomes the tail.
+    let mut lists = vec![];
+    for (list, proper) in args.into_iter() {
+        if !proper {
+            return err!(agent, LangError::InvalidSexp(*list));
+        }
+        lists.push(list);
+    }
+    let tail = match lists.pop() {
+        Some(tail) => tail,
+        None => return Ok(Sexp::default()),
+    };
 
-fn eq_(a: Sexp, b: Sexp, agent: &mut Agent) -> Result<Node, Error> {
-    // TODO(perf) Would be better to cache the Node, not Sym, but
-    // having trouble passing agent into lazy_static. Alternatively,
-    // if we could access the Context from an Agent or copy context to
-    // a builtin, that would work too.
-    lazy_static! {
-        static ref T: Symbol = "true".to_symbol_or_panic(policy_base);
-        static ref F: Symbol = "false".to_symbol_or_panic(policy_base);
+    let mut res = ConsList::new();
+    for list in lists {
+        for (elem, proper) in list.iter() {
+            if !proper {
+                return err!(
+                    agent,
+                    LangError::InvalidArgument {
+                        given: (*list).clone(),
+                        expected: "Proper list".into()
+                    }
+                );
+            }
+            res.append(elem.clone());
+        }
     }
+    // Prefer to represent '() using None.
+    Ok(res.release_with_tail(if tail.is_none() { None } else { Some(tail) }))
+}
+
+fn print_settings_(agent: &mut Agent) -> Result<SymSexpTable, Error> {
+    Ok(agent.print_settings().to_table())
+}
 
-    if a == b {
-        agent.resolve_name(&T)
+fn set_print_setting_(name: Symbol, val: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    let (given, expected) = if agent.print_settings().get(name.as_str()).is_none() {
+        (name.into(), "print setting name")
     } else {
-        agent.resolve_name(&F)
-    }
+        match agent.print_settings_mut().set(name.as_str(), val.clone()) {
+            Ok(()) => return Ok(Sexp::default()),
+            Err(expected) => (val, expected),
+        }
+    };
+    err!(
+        agent,
+        LangError::InvalidArgument {
+            given,
+            expected: expected.into(),
+        }
+    )
+}
+
+fn eq_(a: Sexp, b: Sexp, _agent: &mut Agent) -> Result<bool, Error> {
+    Ok(a == b)
+}
+
+fn not_(a: Sexp, _agent: &mut Agent) -> Result<bool, Error> {
+    Ok(a == false.into())
+}
+
+fn match_fail_(given: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    err!(agent, LangError::NonExhaustiveMatch(given))
 }
 
 fn curr_(agent: &mut Agent) -> Result<Node, Error> {
@@ -140,90 +226,192 @@ fn env_find_(path: LangString, agent: &mut Agent) -> Result<Sexp, Error> {
 }
 
 
-fn add(args: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
-    let (mut curr, mut tail) = break_sexp!(args => (Number; remainder), agent)?;
-    let curr_d = mem::discriminant(&curr);
-    while tail != None {
-        let (num, new_tail) = break_sexp!(tail.unwrap() => (Number; remainder), agent)?;
-        tail = new_tail;
+fn vector_len_(vector: Vector, _agent: &mut Agent) -> Result<Number, Error> {
+    Ok(Number::USize(vector.len()))
+}
+
+fn vector_to_list_(vector: Vector, _agent: &mut Agent) -> Result<Sexp, Error> {
+    Ok(vector.into_vec().into())
+}
+
+fn list_to_vector_(list: HeapSexp, agent: &mut Agent) -> Result<Vector, Error> {
+    let mut elements = vec![];
+    for (elem, proper) in list.into_iter() {
+        if !proper {
+            return err!(agent, LangError::InvalidSexp(*elem));
+        }
+        elements.push(*elem);
+    }
+    Ok(elements.into())
+}
 
-        if mem::discriminant(&num) != curr_d {
+fn vector_slice_(
+    (vector, start, end): (Vector, Number, Number),
+    agent: &mut Agent,
+) -> Result<Sexp, Error> {
+    let len = vector.len();
+    let start = bounded_index(start, len + 1, agent)?;
+    let end = match usize::try_from(end.clone()) {
+        Ok(i) if start <= i && i <= len => i,
+        _ => {
             return err!(
                 agent,
                 LangError::InvalidArgument {
-                    given: num.clone().into(),
-                    expected: std::borrow::Cow::Owned(format!("discriminant {:?}", curr_d))
+                    given: end.into(),
+                    expected: format!("end index in [{}, {}]", start, len).into(),
                 }
             );
         }
-        curr += num;
+    };
+    Ok(Vector::from(vector.as_slice()[start..end].to_vec()).into())
+}
+
+/// Convert index to a usize less than bound.
+pub(crate) fn bounded_index(index: Number, bound: usize, agent: &Agent) -> Result<usize, Error> {
+    match usize::try_from(index.clone()) {
+        Ok(i) if i < bound => Ok(i),
+        _ => err!(
+            agent,
+            LangError::InvalidArgument {
+                given: index.into(),
+                expected: format!("index in [0, {})", bound).into(),
+            }
+        ),
     }
-    Ok(curr.into())
+}
+
+fn add(args: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    fold_numbers(args, agent, Number::checked_add)
 }
 
 fn sub(args: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    fold_numbers(args, agent, Number::checked_sub)
+}
+
+fn mul(args: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    fold_numbers(args, agent, Number::checked_mul)
+}
+
+fn div(args: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    fold_numbers(args, agent, Number::checked_div)
+}
+
+fn modulo_(a: Number, b: Number, agent: &mut Agent) -> Result<Number, Error> {
+    match a.checked_mod(&b) {
+        Ok(num) => Ok(num),
+        Err(e) => err!(agent, number_error(e, list!(a, b))),
+    }
+}
+
+fn abs_(a: Number, agent: &mut Agent) -> Result<Number, Error> {
+    match a.checked_abs() {
+        Ok(num) => Ok(num),
+        Err(e) => err!(agent, number_error(e, list!(a))),
+    }
+}
+
+fn min(args: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    select_number(args, agent, Ordering::Less)
+}
+
+fn max(args: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    select_number(args, agent, Ordering::Greater)
+}
+
+fn lt(args: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    compare_numbers(args, agent, |o| o == Ordering::Less)
+}
+
+fn le(args: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    compare_numbers(args, agent, |o| o != Ordering::Greater)
+}
+
+fn gt(args: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    compare_numbers(args, agent, |o| o == Ordering::Greater)
+}
+
+fn ge(args: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    compare_numbers(args, agent, |o| o != Ordering::Less)
+}
+
+fn num_eq(args: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    compare_numbers(args, agent, |o| o == Ordering::Equal)
+}
+
+/// Left fold of a checked arithmetic op over Number args.
+fn fold_numbers(
+    args: Sexp,
+    agent: &mut Agent,
+    op: fn(&Number, &Number) -> Result<Number, NumberError>,
+) -> Result<Sexp, Error> {
     let (mut curr, mut tail) = break_sexp!(args => (Number; remainder), agent)?;
-    let curr_d = mem::discriminant(&curr);
     while tail != None {
         let (num, new_tail) = break_sexp!(tail.unwrap() => (Number; remainder), agent)?;
         tail = new_tail;
 
-        if mem::discriminant(&num) != curr_d {
-            return err!(
-                agent,
-                LangError::InvalidArgument {
-                    given: num.clone().into(),
-                    expected: std::borrow::Cow::Owned(format!("discriminant {:?}", curr_d))
-                }
-            );
-        }
-        curr -= num;
+        curr = match op(&curr, &num) {
+            Ok(res) => res,
+            Err(e) => return err!(agent, number_error(e, list!(curr, num))),
+        };
     }
     Ok(curr.into())
 }
 
-fn mul(args: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+/// Whether pred holds for each adjacent pair of Number args.
+///
+/// Unordered pairs (i.e. involving NaN) never satisfy pred.
+fn compare_numbers(
+    args: Sexp,
+    agent: &mut Agent,
+    pred: fn(Ordering) -> bool,
+) -> Result<Sexp, Error> {
     let (mut curr, mut tail) = break_sexp!(args => (Number; remainder), agent)?;
-    let curr_d = mem::discriminant(&curr);
+    let mut result = true;
     while tail != None {
         let (num, new_tail) = break_sexp!(tail.unwrap() => (Number; remainder), agent)?;
         tail = new_tail;
 
-        if mem::discriminant(&num) != curr_d {
-            return err!(
-                agent,
-                LangError::InvalidArgument {
-                    given: num.clone().into(),
-                    expected: std::borrow::Cow::Owned(format!("discriminant {:?}", curr_d))
-                }
-            );
-        }
-        curr *= num;
+        result &= curr.numeric_cmp(&num).is_some_and(pred);
+        curr = num;
     }
-    Ok(curr.into())
+    Ok(result.into())
 }
 
-fn div(args: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+/// Select the Number arg furthest in the direction of ordering.
+fn select_number(args: Sexp, agent: &mut Agent, ordering: Ordering) -> Result<Sexp, Error> {
     let (mut curr, mut tail) = break_sexp!(args => (Number; remainder), agent)?;
-    let curr_d = mem::discriminant(&curr);
     while tail != None {
         let (num, new_tail) = break_sexp!(tail.unwrap() => (Number; remainder), agent)?;
         tail = new_tail;
 
-        if mem::discriminant(&num) != curr_d {
-            return err!(
-                agent,
-                LangError::InvalidArgument {
-                    given: num.clone().into(),
-                    expected: std::borrow::Cow::Owned(format!("discriminant {:?}", curr_d))
-                }
-            );
+        if num.numeric_cmp(&curr) == Some(ordering) {
+            curr = num;
         }
-        curr /= num;
     }
     Ok(curr.into())
 }
 
+fn number_error(e: NumberError, operands: Sexp) -> LangError {
+    match e {
+        NumberError::Overflow => LangError::Overflow(operands),
+        NumberError::DivisionByZero => LangError::DivisionByZero(operands),
+    }
+}
+
+/// Map the names of builtin functions to BuiltIns over them.
+macro_rules! builtins {
+    [$($x:expr),*] => {
+        {
+            let mut m = HashMap::new();
+            $(m.insert(stringify!($x), BuiltIn::new(stringify!($x), $x));)*
+            m
+        }
+    };
+    [$($n:tt : $x:expr),+ ,] => {
+        builtins![$($n : $x),*]
+    };
+}
+use builtins;
 
 /// Autogen function taking args: Vec<Sexp> from one taking specific subtypes.
 macro_rules! wrap_builtin {
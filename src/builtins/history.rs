@@ -0,0 +1,84 @@
+//! Builtins querying the Agent's history env. See agent::history.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use super::{builtins, wrap_builtin};
+use crate::agent::history::{self, HistoryEntry};
+use crate::agent::lang_error::LangError;
+use crate::agent::Agent;
+use crate::error::Error;
+use crate::primitive::prelude::*;
+use crate::sexp::Sexp;
+
+
+pub(super) fn generate_builtin_map() -> HashMap<&'static str, BuiltIn> {
+    builtins![now, history_entries, history_entry, history_touches]
+}
+
+wrap_builtin!(now_() => now);
+wrap_builtin!(history_entries_(Number) => history_entries);
+wrap_builtin!(history_entry_(Node) => history_entry);
+wrap_builtin!(history_touches_(Node, Primitive) => history_touches);
+
+/// Seconds since the UNIX epoch, as recorded in history entries.
+fn now_(_agent: &mut Agent) -> Result<Number, Error> {
+    Ok(Number::U64(history::now()))
+}
+
+/// Entry nodes recorded at or after time, oldest first.
+fn history_entries_(since: Number, agent: &mut Agent) -> Result<Sexp, Error> {
+    let since = match u64::try_from(since.clone()) {
+        Ok(since) => since,
+        Err(_) => {
+            return err!(
+                agent,
+                LangError::InvalidArgument {
+                    given: since.into(),
+                    expected: "nonnegative integer time".into(),
+                }
+            );
+        }
+    };
+    let entries = HistoryEntry::since(agent, since)?
+        .into_iter()
+        .map(|entry| Sexp::from(entry.node))
+        .collect::<Vec<_>>();
+    Ok(entries.into())
+}
+
+fn history_entry_(node: Node, agent: &mut Agent) -> Result<SymSexpTable, Error> {
+    Ok(entry(node, agent)?.to_table())
+}
+
+/// Whether the entry's input or outcome involves the node, which may be
+/// given by (quoted) symbol.
+fn history_touches_(entry_node: Node, target: Primitive, agent: &mut Agent) -> Result<bool, Error> {
+    let node = match target {
+        Primitive::Node(node) => node,
+        Primitive::Symbol(symbol) => agent.resolve_name(&symbol)?,
+        _ => {
+            return err!(
+                agent,
+                LangError::InvalidArgument {
+                    given: target.into(),
+                    expected: "Node or Symbol".into(),
+                }
+            );
+        }
+    };
+    Ok(entry(entry_node, agent)?.touches(agent, node))
+}
+
+fn entry(node: Node, agent: &Agent) -> Result<HistoryEntry, Error> {
+    match HistoryEntry::get(agent, node)? {
+        Some(entry) => Ok(entry),
+        None => err!(
+            agent,
+            LangError::InvalidArgument {
+                given: node.into(),
+                expected: "history entry".into(),
+            }
+        ),
+    }
+}
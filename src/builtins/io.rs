@@ -0,0 +1,94 @@
+//! Builtins writing to & reading from the Agent's io. See agent::agent_io.
+
+use std::collections::HashMap;
+
+use super::{builtins, wrap_builtin};
+use crate::agent::{Agent, AgentIo};
+use crate::error::Error;
+use crate::parser::Parser;
+use crate::primitive::prelude::*;
+use crate::sexp::Sexp;
+use crate::std_error::StdError;
+use crate::stream::Transform;
+use crate::token::{Token, Tokenizer};
+
+
+pub(super) fn generate_builtin_map() -> HashMap<&'static str, BuiltIn> {
+    builtins![print, println, read_line, read]
+}
+
+wrap_builtin!(print_(Sexp) => print);
+wrap_builtin!(println_(Sexp) => println);
+wrap_builtin!(read_line_() => read_line);
+wrap_builtin!(read_() => read);
+
+fn print_(arg: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    let result = agent.io().write(|mut w| agent.write_sexp(&mut w, &arg));
+    if let Err(err) = result {
+        return err!(agent, StdError::Io(err));
+    }
+    Ok(Sexp::default())
+}
+
+fn println_(arg: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    let result = agent.io().write(|mut w| {
+        agent.write_sexp(&mut w, &arg)?;
+        writeln!(w)
+    });
+    if let Err(err) = result {
+        return err!(agent, StdError::Io(err));
+    }
+    Ok(Sexp::default())
+}
+
+/// Next line of input as a String, or nil at the end of input.
+fn read_line_(agent: &mut Agent) -> Result<Sexp, Error> {
+    match agent.io().read_line() {
+        Ok(Some(line)) => Ok(LangString::new(line).into()),
+        Ok(None) => Ok(Sexp::default()),
+        Err(err) => err!(agent, StdError::Io(err)),
+    }
+}
+
+/// Next form of input, unevaluated, or nil at the end of input.
+///
+/// Forms following the one read on its last line are kept for the next read,
+/// though not for read-line.
+fn read_(agent: &mut Agent) -> Result<Sexp, Error> {
+    let io = agent.io().clone();
+    let form = {
+        let mut reader = io.form_reader(agent.symbol_grammar());
+        let (tokenizer, parser) = &mut *reader;
+        read_form(agent, &io, tokenizer, parser)
+    };
+    // Start afresh after errors & at the end of input.
+    if !matches!(form, Ok(Some(_))) {
+        io.clear_form_reader();
+    }
+    Ok(form?.unwrap_or_default())
+}
+
+fn read_form(
+    agent: &mut Agent,
+    io: &AgentIo,
+    tokenizer: &mut Tokenizer<()>,
+    parser: &mut Parser,
+) -> Result<Option<Sexp>, Error> {
+    loop {
+        while let Some(token) = <dyn Transform<String, Token>>::output(tokenizer) {
+            parser.input(token)?;
+            if let Some(sexp) = parser.output() {
+                return sexp.map(Some);
+            }
+        }
+        let line = match io.read_line() {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(err) => return err!(agent, StdError::Io(err)),
+        };
+        <dyn Transform<String, Token>>::input(tokenizer, Ok(line))?;
+    }
+    tokenizer.finish().map_err(Error::no_cont)?;
+    parser.finish().map_err(Error::no_cont)?;
+    Ok(None)
+}
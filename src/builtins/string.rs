@@ -0,0 +1,238 @@
+//! String manipulation builtins.
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use super::{bounded_index, builtins, wrap_builtin};
+use crate::agent::lang_error::LangError;
+use crate::agent::Agent;
+use crate::error::Error;
+use crate::primitive::prelude::*;
+use crate::sexp::{ConsList, HeapSexp, Sexp};
+
+
+pub(super) fn generate_builtin_map() -> HashMap<&'static str, BuiltIn> {
+    builtins![
+        string_append,
+        string_len,
+        string_ref,
+        string_find,
+        string_split,
+        string_join,
+        substring,
+        string_upcase,
+        string_downcase,
+        string_trim,
+        number_to_string,
+        string_to_number,
+        symbol_to_string,
+        string_to_symbol,
+        char_to_integer,
+        integer_to_char,
+        regex_match,
+        regex_replace
+    ]
+}
+
+wrap_builtin!(string_len_(LangString) => string_len);
+wrap_builtin!(string_ref_(LangString, Number) => string_ref);
+wrap_builtin!(string_find_(LangString, LangString) => string_find);
+wrap_builtin!(string_split_(LangString, LangString) => string_split);
+wrap_builtin!(string_join_(HeapSexp, LangString) => string_join);
+wrap_builtin!(substring_(LangString, Number, Number) => substring);
+wrap_builtin!(string_upcase_(LangString) => string_upcase);
+wrap_builtin!(string_downcase_(LangString) => string_downcase);
+wrap_builtin!(string_trim_(LangString) => string_trim);
+wrap_builtin!(number_to_string_(Number) => number_to_string);
+wrap_builtin!(string_to_number_(LangString) => string_to_number);
+wrap_builtin!(symbol_to_string_(Symbol) => symbol_to_string);
+wrap_builtin!(string_to_symbol_(LangString) => string_to_symbol);
+wrap_builtin!(char_to_integer_(char) => char_to_integer);
+wrap_builtin!(integer_to_char_(Number) => integer_to_char);
+wrap_builtin!(regex_match_(LangString, LangString) => regex_match);
+wrap_builtin!(regex_replace_(LangString, LangString, LangString) => regex_replace);
+
+fn string_append(args: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    let mut res = String::new();
+    for (arg, proper) in args.into_iter() {
+        if !proper {
+            return err!(agent, LangError::InvalidSexp(*arg));
+        }
+        res.push_str(lang_string(*arg, agent)?.as_str());
+    }
+    Ok(res.into())
+}
+
+// Lengths and indices are in chars rather than bytes.
+fn string_len_(s: LangString, _agent: &mut Agent) -> Result<Number, Error> {
+    Ok(Number::USize(s.as_str().chars().count()))
+}
+
+fn string_ref_(s: LangString, index: Number, agent: &mut Agent) -> Result<char, Error> {
+    let i = bounded_index(index, s.as_str().chars().count(), agent)?;
+    Ok(s.as_str().chars().nth(i).unwrap())
+}
+
+fn string_find_(s: LangString, pattern: LangString, _agent: &mut Agent) -> Result<Sexp, Error> {
+    Ok(match s.as_str().find(pattern.as_str()) {
+        Some(byte_index) => Number::USize(s.as_str()[..byte_index].chars().count()).into(),
+        None => false.into(),
+    })
+}
+
+fn string_split_(s: LangString, sep: LangString, _agent: &mut Agent) -> Result<Sexp, Error> {
+    let mut res = ConsList::new();
+    if sep.as_str().is_empty() {
+        for c in s.as_str().chars() {
+            res.append(Sexp::from(c.to_string()));
+        }
+    } else {
+        for part in s.as_str().split(sep.as_str()) {
+            res.append(Sexp::from(part));
+        }
+    }
+    Ok(res.release())
+}
+
+fn string_join_(list: HeapSexp, sep: LangString, agent: &mut Agent) -> Result<LangString, Error> {
+    let mut parts = vec![];
+    for (elem, proper) in list.into_iter() {
+        if !proper {
+            return err!(agent, LangError::InvalidSexp(*elem));
+        }
+        parts.push(String::from(lang_string(*elem, agent)?));
+    }
+    Ok(parts.join(sep.as_str()).into())
+}
+
+fn substring_(
+    (s, start, end): (LangString, Number, Number),
+    agent: &mut Agent,
+) -> Result<Sexp, Error> {
+    let len = s.as_str().chars().count();
+    let end = bounded_index(end, len + 1, agent)?;
+    let start = bounded_index(start, end + 1, agent)?;
+    let sub = s
+        .as_str()
+        .chars()
+        .skip(start)
+        .take(end - start)
+        .collect::<String>();
+    Ok(sub.into())
+}
+
+fn string_upcase_(s: LangString, _agent: &mut Agent) -> Result<LangString, Error> {
+    Ok(s.as_str().to_uppercase().into())
+}
+
+fn string_downcase_(s: LangString, _agent: &mut Agent) -> Result<LangString, Error> {
+    Ok(s.as_str().to_lowercase().into())
+}
+
+fn string_trim_(s: LangString, _agent: &mut Agent) -> Result<LangString, Error> {
+    Ok(s.as_str().trim().into())
+}
+
+fn number_to_string_(num: Number, _agent: &mut Agent) -> Result<LangString, Error> {
+    Ok(num.to_string().into())
+}
+
+fn string_to_number_(s: LangString, _agent: &mut Agent) -> Result<Sexp, Error> {
+    Ok(match s.as_str().parse::<Number>() {
+        Ok(num) => num.into(),
+        Err(_) => false.into(),
+    })
+}
+
+fn symbol_to_string_(symbol: Symbol, _agent: &mut Agent) -> Result<LangString, Error> {
+    Ok(symbol.as_str().into())
+}
+
+fn string_to_symbol_(s: LangString, agent: &mut Agent) -> Result<Symbol, Error> {
+    match s.as_str().to_symbol_with(policy_base, agent.symbol_grammar()) {
+        Ok(symbol) => Ok(symbol),
+        Err(_) => err!(
+            agent,
+            LangError::InvalidArgument {
+                given: s.into(),
+                expected: "valid symbol".into(),
+            }
+        ),
+    }
+}
+
+fn char_to_integer_(c: char, _agent: &mut Agent) -> Result<Number, Error> {
+    Ok(Number::U32(c as u32))
+}
+
+fn integer_to_char_(n: Number, agent: &mut Agent) -> Result<char, Error> {
+    match u32::try_from(n.clone()).ok().and_then(char::from_u32) {
+        Some(c) => Ok(c),
+        None => err!(
+            agent,
+            LangError::InvalidArgument {
+                given: n.into(),
+                expected: "unicode scalar value".into(),
+            }
+        ),
+    }
+}
+
+/// Returns the list of capture groups of the first match, or #f if there is
+/// no match. Groups which did not participate in the match are '().
+fn regex_match_(pattern: LangString, s: LangString, agent: &mut Agent) -> Result<Sexp, Error> {
+    let re = compile_regex(pattern, agent)?;
+    Ok(match re.captures(s.as_str()) {
+        Some(captures) => {
+            let mut res = ConsList::new();
+            for group in captures.iter() {
+                match group {
+                    Some(m) => res.append(Sexp::from(m.as_str())),
+                    None => res.append(Sexp::default()),
+                }
+            }
+            res.release()
+        }
+        None => false.into(),
+    })
+}
+
+/// Replaces all matches of pattern in s; replacement may refer to capture
+/// groups as $1, $name, etc.
+fn regex_replace_(
+    (pattern, s, replacement): (LangString, LangString, LangString),
+    agent: &mut Agent,
+) -> Result<Sexp, Error> {
+    let re = compile_regex(pattern, agent)?;
+    Ok(re
+        .replace_all(s.as_str(), replacement.as_str())
+        .into_owned()
+        .into())
+}
+
+fn compile_regex(pattern: LangString, agent: &Agent) -> Result<Regex, Error> {
+    match Regex::new(pattern.as_str()) {
+        Ok(re) => Ok(re),
+        Err(_) => err!(
+            agent,
+            LangError::InvalidArgument {
+                given: pattern.into(),
+                expected: "valid regex".into(),
+            }
+        ),
+    }
+}
+
+fn lang_string(sexp: Sexp, agent: &Agent) -> Result<LangString, Error> {
+    match LangString::try_from(sexp) {
+        Ok(s) => Ok(s),
+        Err(given) => err!(
+            agent,
+            LangError::InvalidArgument {
+                given,
+                expected: "LangString".into(),
+            }
+        ),
+    }
+}
@@ -0,0 +1,166 @@
+//! Table builtins, shared across table kinds.
+//!
+//! Construction and mutation are special forms (e.g. table-insert!), since
+//! they need unevaluated keys or the Node holding the table respectively.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+use super::{builtins, wrap_builtin};
+use crate::agent::lang_error::LangError;
+use crate::agent::Agent;
+use crate::env::LocalNode;
+use crate::error::Error;
+use crate::primitive::prelude::*;
+use crate::sexp::{ConsList, Sexp};
+
+
+pub(super) fn generate_builtin_map() -> HashMap<&'static str, BuiltIn> {
+    builtins![
+        table_lookup,
+        table_contains,
+        table_keys,
+        table_len,
+        table_to_list
+    ]
+}
+
+wrap_builtin!(table_lookup_(Sexp, Sexp) => table_lookup);
+wrap_builtin!(table_contains_(Sexp, Sexp) => table_contains);
+wrap_builtin!(table_keys_(Sexp) => table_keys);
+wrap_builtin!(table_len_(Sexp) => table_len);
+wrap_builtin!(table_to_list_(Sexp) => table_to_list);
+
+/// Returns the value of key, or '() if absent.
+fn table_lookup_(table: Sexp, key: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    let val = match table {
+        Sexp::Primitive(Primitive::SymNodeTable(table)) => {
+            table.lookup(&symbol_key(key, agent)?).map(Sexp::from)
+        }
+        Sexp::Primitive(Primitive::SymSexpTable(table)) => table.lookup(&symbol_key(key, agent)?),
+        Sexp::Primitive(Primitive::LocalNodeTable(table)) => table
+            .lookup(&node_key(key, table.env(), agent)?)
+            .map(|v| Node::new(table.env(), v).into()),
+        _ => return not_table(table, agent),
+    };
+    Ok(val.unwrap_or_default())
+}
+
+fn table_contains_(table: Sexp, key: Sexp, agent: &mut Agent) -> Result<bool, Error> {
+    match table {
+        Sexp::Primitive(Primitive::SymNodeTable(table)) => {
+            Ok(table.contains_key(&symbol_key(key, agent)?))
+        }
+        Sexp::Primitive(Primitive::SymSexpTable(table)) => {
+            Ok(table.contains_key(&symbol_key(key, agent)?))
+        }
+        Sexp::Primitive(Primitive::LocalNodeTable(table)) => {
+            Ok(table.contains_key(&node_key(key, table.env(), agent)?))
+        }
+        _ => not_table(table, agent),
+    }
+}
+
+fn table_keys_(table: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    let mut keys = ConsList::new();
+    match table {
+        Sexp::Primitive(Primitive::SymNodeTable(table)) => {
+            for k in table.as_map().keys() {
+                keys.append(k.clone());
+            }
+        }
+        Sexp::Primitive(Primitive::SymSexpTable(table)) => {
+            for k in table.as_map().keys() {
+                keys.append(k.clone());
+            }
+        }
+        Sexp::Primitive(Primitive::LocalNodeTable(table)) => {
+            for k in table.as_map().keys() {
+                keys.append(Node::new(table.env(), *k));
+            }
+        }
+        _ => return not_table(table, agent),
+    }
+    Ok(keys.release())
+}
+
+fn table_len_(table: Sexp, agent: &mut Agent) -> Result<Number, Error> {
+    let len = match &table {
+        Sexp::Primitive(Primitive::SymNodeTable(table)) => table.as_map().len(),
+        Sexp::Primitive(Primitive::SymSexpTable(table)) => table.as_map().len(),
+        Sexp::Primitive(Primitive::LocalNodeTable(table)) => table.as_map().len(),
+        _ => return not_table(table, agent),
+    };
+    Ok(Number::USize(len))
+}
+
+/// List of (key value) entries, ordered by key.
+fn table_to_list_(table: Sexp, agent: &mut Agent) -> Result<Sexp, Error> {
+    let mut entries = ConsList::new();
+    match table {
+        Sexp::Primitive(Primitive::SymNodeTable(table)) => {
+            for (k, v) in table.as_map() {
+                entries.append(list!(k.clone(), *v));
+            }
+        }
+        Sexp::Primitive(Primitive::SymSexpTable(table)) => {
+            for (k, v) in table.as_map() {
+                entries.append(list!(k.clone(), v.clone()));
+            }
+        }
+        Sexp::Primitive(Primitive::LocalNodeTable(table)) => {
+            for (k, v) in table.as_map() {
+                entries.append(list!(
+                    Node::new(table.env(), *k),
+                    Node::new(table.env(), *v)
+                ));
+            }
+        }
+        _ => return not_table(table, agent),
+    }
+    Ok(entries.release())
+}
+
+fn symbol_key(key: Sexp, agent: &Agent) -> Result<Symbol, Error> {
+    match Symbol::try_from(key) {
+        Ok(symbol) => Ok(symbol),
+        Err(given) => err!(
+            agent,
+            LangError::InvalidArgument {
+                given,
+                expected: "Symbol".into(),
+            }
+        ),
+    }
+}
+
+/// Local part of key, which must be a Node in env.
+fn node_key(key: Sexp, env: LocalNode, agent: &Agent) -> Result<LocalNode, Error> {
+    match Node::try_from(key) {
+        Ok(node) if node.env() == env => Ok(node.local()),
+        Ok(node) => err!(
+            agent,
+            LangError::InvalidArgument {
+                given: node.into(),
+                expected: format!("Node in env {}", env).into(),
+            }
+        ),
+        Err(given) => err!(
+            agent,
+            LangError::InvalidArgument {
+                given,
+                expected: "Node".into(),
+            }
+        ),
+    }
+}
+
+fn not_table<T>(given: Sexp, agent: &Agent) -> Result<T, Error> {
+    err!(
+        agent,
+        LangError::InvalidArgument {
+            given,
+            expected: "table".into(),
+        }
+    )
+}
@@ -0,0 +1,88 @@
+//! Syntax checking of Amlang text, without needing an Agent.
+//!
+//! Unlike a Tokenizer & Parser pipeline, which stops at the first error,
+//! checking resynchronizes at top-level forms to report every error at once.
+
+use std::fmt;
+use std::path::Path;
+
+use crate::error::Error;
+use crate::parser::Parser;
+use crate::primitive::symbol_policies::SymbolPolicy;
+use crate::sexp::Sexp;
+use crate::stream::Transform;
+use crate::token::{Token, Tokenizer};
+
+
+/// Syntax error found by check, with 0-indexed line and col.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub col: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line + 1, self.col + 1, self.message)
+    }
+}
+
+
+/// All syntax errors in input, in order of position.
+pub fn check<S: AsRef<str>, SymbolInfo>(
+    input: S,
+    policy: SymbolPolicy<SymbolInfo>,
+) -> Vec<Diagnostic> {
+    let mut tokenizer = Tokenizer::new(policy).recovering();
+    let mut parser = Parser::new().recovering();
+    let mut diagnostics = vec![];
+
+    // A recovering Tokenizer only fails at finish.
+    let _ = tokenizer.tokenize(input.as_ref());
+    if let Err(err) = tokenizer.finish() {
+        diagnostics.push(Diagnostic::from_error(err.line(), err.col(), &err));
+    }
+    while let Some(Ok(token)) = <dyn Transform<&str, Token>>::output(&mut tokenizer) {
+        // Likewise a recovering Parser; discard Sexps as we go.
+        let _ = parser.input(Ok(token));
+        while <dyn Transform<Token, Sexp>>::output(&mut parser).is_some() {}
+    }
+    if let Err(err) = parser.finish() {
+        diagnostics.push(Diagnostic::from_error(err.line(), err.col(), &err));
+    }
+
+    for err in tokenizer.take_errors() {
+        diagnostics.push(Diagnostic::from_error(err.line(), err.col(), &err));
+    }
+    for err in parser.take_errors() {
+        diagnostics.push(Diagnostic::from_error(err.line(), err.col(), &err));
+    }
+    diagnostics.sort_by_key(|d| (d.line, d.col));
+    diagnostics
+}
+
+/// All syntax errors in the file at path, in order of position.
+pub fn check_file<P: AsRef<Path>, SymbolInfo>(
+    path: P,
+    policy: SymbolPolicy<SymbolInfo>,
+) -> Result<Vec<Diagnostic>, Error> {
+    let input = std::fs::read_to_string(path)?;
+    Ok(check(input, policy))
+}
+
+
+impl Diagnostic {
+    fn from_error<E: fmt::Display>(line: usize, col: usize, err: &E) -> Self {
+        Self {
+            line,
+            col,
+            message: err.to_string(),
+        }
+    }
+}
+
+
+#[cfg(test)]
+#[path = "./check_test.rs"]
+mod check_test;
@@ -0,0 +1,83 @@
+use super::*;
+
+use crate::primitive::symbol_policies::{policy_base, policy_env_serde};
+
+fn positions(input: &str) -> Vec<(usize, usize)> {
+    check(input, policy_base)
+        .into_iter()
+        .map(|d| (d.line, d.col))
+        .collect()
+}
+
+#[test]
+fn clean() {
+    assert_eq!(check("(a (b . c))\n'[d \"e\"] ; f", policy_base), vec![]);
+}
+
+#[test]
+fn all_top_level_errors() {
+    let input = "(a . b c)\n(d e))\n(f (g . (h)))\n(i . j)";
+    let diagnostics = check(input, policy_base);
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|d| (d.line, d.col))
+            .collect::<Vec<_>>(),
+        vec![(0, 7), (1, 5), (2, 8)]
+    );
+    assert_eq!(
+        diagnostics[0].to_string(),
+        "1:8: period must precede the last list element"
+    );
+    assert_eq!(diagnostics[1].to_string(), "2:6: no open form to close");
+}
+
+#[test]
+fn skips_rest_of_failed_form() {
+    // Only the first error of a form is reported.
+    assert_eq!(positions("(a (b . c d) . e f)\n(g)"), vec![(0, 10)]);
+}
+
+#[test]
+fn unclosed_forms() {
+    // A paren at the start of a line starts a new top-level form.
+    let input = "(a (b c)\n  (d)\n(e [f\n'";
+    let diagnostics = check(input, policy_base);
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|d| (d.line, d.col))
+            .collect::<Vec<_>>(),
+        vec![(0, 0), (2, 0)]
+    );
+    assert_eq!(diagnostics[0].message, "form is never closed");
+
+    assert_eq!(
+        check("(a)\n'", policy_base)[0].to_string(),
+        "2:1: quote is not followed by anything to quote"
+    );
+}
+
+#[test]
+fn tokenize_errors() {
+    let input = "(a ^b c)\n(d \"e\n f)";
+    let diagnostics = check(input, policy_base);
+    assert_eq!(
+        diagnostics
+            .iter()
+            .map(|d| (d.line, d.col))
+            .collect::<Vec<_>>(),
+        vec![(0, 3), (1, 0), (1, 3)]
+    );
+    assert!(diagnostics[0].message.starts_with("invalid symbol"));
+    assert_eq!(diagnostics[2].message, "string is never closed");
+}
+
+#[test]
+fn envs() {
+    let envs = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("envs");
+    for name in ["lang.env", "error.env", "meta.env"] {
+        let diagnostics = check_file(envs.join(name), policy_env_serde).unwrap();
+        assert_eq!(diagnostics, vec![], "{}", name);
+    }
+}
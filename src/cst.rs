@@ -0,0 +1,180 @@
+//! Concrete syntax tree of Amlang text.
+//!
+//! Unlike Sexps, Csts keep comments, blank lines, and the exact source text
+//! of primitives, so text can be rewritten (e.g. by the formatter) without
+//! losing anything but insignificant whitespace.
+
+use crate::check::{check, Diagnostic};
+use crate::primitive::symbol_policies::policy_env_serde;
+use crate::stream::Transform;
+use crate::token::{Token, TokenKind, Tokenizer};
+
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cst {
+    pub kind: CstKind,
+    // Whether separated from what precedes it by at least one blank line.
+    pub blank_before: bool,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CstKind {
+    /// Source text of a primitive.
+    Atom(String),
+    /// Text includes the leading ;. Trailing comments share a line with
+    /// whatever precedes them.
+    Comment {
+        text: String,
+        trailing: bool,
+    },
+    Period,
+    List {
+        vector: bool,
+        elems: Vec<Cst>,
+    },
+    /// Prefix is one of ' ` , ,@
+    Quoted {
+        prefix: String,
+        datum: Box<Cst>,
+    },
+}
+
+// Partially built node, awaiting its close or quoted datum.
+struct Frame {
+    open: Open,
+    blank_before: bool,
+    elems: Vec<Cst>,
+}
+
+enum Open {
+    Root,
+    List(bool), // vector
+    Quote(String),
+}
+
+
+impl Cst {
+    /// Parse text into top-level Csts, failing with every syntax error
+    /// if it doesn't parse.
+    ///
+    /// Accepts all symbols valid in .env files, which is a superset of
+    /// those in Amlang source.
+    pub fn parse<S: AsRef<str>>(input: S) -> Result<Vec<Cst>, Vec<Diagnostic>> {
+        let input = input.as_ref();
+        let diagnostics = check(input, policy_env_serde);
+        if !diagnostics.is_empty() {
+            return Err(diagnostics);
+        }
+
+        let lines = input.split('\n').collect::<Vec<_>>();
+        let mut tokenizer = Tokenizer::new(policy_env_serde);
+        // Checked above.
+        tokenizer.tokenize(input).unwrap();
+
+        let mut stack = vec![Frame {
+            open: Open::Root,
+            blank_before: false,
+            elems: vec![],
+        }];
+        let mut last_line: Option<usize> = None;
+        while let Some(Ok(token)) = <dyn Transform<&str, Token>>::output(&mut tokenizer) {
+            let blank_before = last_line.is_some_and(|line| token.line > line + 1);
+            let trailing = last_line == Some(token.line);
+            last_line = Some(token.end.0);
+
+            let node = |kind| Cst { kind, blank_before };
+            match token.token {
+                TokenKind::LeftParen | TokenKind::LeftBracket => stack.push(Frame {
+                    open: Open::List(token.token == TokenKind::LeftBracket),
+                    blank_before,
+                    elems: vec![],
+                }),
+                TokenKind::RightParen | TokenKind::RightBracket => {
+                    let frame = stack.pop().unwrap();
+                    let vector = matches!(frame.open, Open::List(true));
+                    let list = Cst {
+                        kind: CstKind::List {
+                            vector,
+                            elems: frame.elems,
+                        },
+                        blank_before: frame.blank_before,
+                    };
+                    push_node(&mut stack, list);
+                }
+                TokenKind::Quote
+                | TokenKind::Quasiquote
+                | TokenKind::Unquote
+                | TokenKind::UnquoteSplicing => stack.push(Frame {
+                    open: Open::Quote(source_text(&lines, &token)),
+                    blank_before,
+                    elems: vec![],
+                }),
+                // Comments may fall between a quote and its datum, in which
+                // case they precede the quote.
+                TokenKind::Comment(text) => {
+                    let comment = node(CstKind::Comment {
+                        text: text.trim_end().to_string(),
+                        trailing,
+                    });
+                    let i = stack
+                        .iter()
+                        .rposition(|frame| !matches!(frame.open, Open::Quote(_)))
+                        .unwrap();
+                    stack[i].elems.push(comment);
+                }
+                TokenKind::Period => stack.last_mut().unwrap().elems.push(node(CstKind::Period)),
+                TokenKind::Primitive(_) => {
+                    push_node(&mut stack, node(CstKind::Atom(source_text(&lines, &token))));
+                }
+            }
+        }
+        Ok(stack.pop().unwrap().elems)
+    }
+
+    pub fn is_comment(&self) -> bool {
+        matches!(self.kind, CstKind::Comment { .. })
+    }
+}
+
+
+// Add node to the innermost list (or root), wrapping it in any pending quotes.
+fn push_node(stack: &mut Vec<Frame>, mut node: Cst) {
+    while let Some(Frame {
+        open: Open::Quote(_),
+        ..
+    }) = stack.last()
+    {
+        let frame = stack.pop().unwrap();
+        if let Open::Quote(prefix) = frame.open {
+            node = Cst {
+                kind: CstKind::Quoted {
+                    prefix,
+                    datum: Box::new(node),
+                },
+                blank_before: frame.blank_before,
+            };
+        }
+    }
+    stack.last_mut().unwrap().elems.push(node);
+}
+
+fn source_text(lines: &[&str], token: &Token) -> String {
+    let (end_line, end_col) = token.end;
+    if token.line == end_line {
+        return lines[token.line][token.col..end_col].to_string();
+    }
+
+    let mut text = lines[token.line][token.col..].to_string();
+    for line in &lines[token.line + 1..end_line] {
+        text.push('\n');
+        text.push_str(line);
+    }
+    text.push('\n');
+    text.push_str(&lines[end_line][..end_col]);
+    text
+}
+
+
+#[cfg(test)]
+#[path = "./cst_test.rs"]
+mod cst_test;
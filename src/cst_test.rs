@@ -0,0 +1,55 @@
+use super::*;
+
+fn atom(s: &str) -> Cst {
+    Cst {
+        kind: CstKind::Atom(s.to_string()),
+        blank_before: false,
+    }
+}
+
+#[test]
+fn source_text() {
+    let forms = Cst::parse("(1.50 4u8 \"a\\tb\")").unwrap();
+    assert_eq!(
+        forms,
+        vec![Cst {
+            kind: CstKind::List {
+                vector: false,
+                elems: vec![atom("1.50"), atom("4u8"), atom("\"a\\tb\"")],
+            },
+            blank_before: false,
+        }]
+    );
+
+    let forms = Cst::parse("\"multi\n line\"").unwrap();
+    assert_eq!(forms, vec![atom("\"multi\n line\"")]);
+}
+
+#[test]
+fn comments_and_blanks() {
+    let forms = Cst::parse("a ; one\n\n\n; two\n' ; three\nb").unwrap();
+    assert_eq!(forms.len(), 5);
+    assert_eq!(
+        forms[1].kind,
+        CstKind::Comment {
+            text: "; one".to_string(),
+            trailing: true,
+        }
+    );
+    assert!(forms[2].blank_before);
+    // Comments between a quote and its datum precede the quote.
+    assert_eq!(
+        forms[3].kind,
+        CstKind::Comment {
+            text: "; three".to_string(),
+            trailing: true,
+        }
+    );
+    assert_eq!(
+        forms[4].kind,
+        CstKind::Quoted {
+            prefix: "'".to_string(),
+            datum: Box::new(atom("b")),
+        }
+    );
+}
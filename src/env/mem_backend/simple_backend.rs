@@ -75,9 +75,6 @@ impl MemBackend for SimpleBackend {
     }
 
     fn designator_mut(&mut self, context: LocalNode) -> &mut Designator {
-        if !self.designators.contains_key(&context) {
-            self.designators.insert(context, Designator::default());
-        }
-        self.designators.get_mut(&context).unwrap()
+        self.designators.entry(context).or_default()
     }
 }
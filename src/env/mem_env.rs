@@ -146,7 +146,7 @@ impl<Backend: MemBackend> Environment for MemEnv<Backend> {
     fn match_all(&self) -> TripleSet {
         // TODO(feat) Watch out if backends can ever have gaps here.
         let elements = (0..self.backend.triple_count())
-            .map(|x| index_to_triple_id(x))
+            .map(index_to_triple_id)
             .collect();
         TripleSet::new(self, elements)
     }
@@ -1,13 +1,26 @@
+use std::cell::UnsafeCell;
 use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
 
 use super::local_node::LocalNode;
 use super::EnvObject;
 
 
-#[derive(Clone, Debug)]
+type EnvMap = BTreeMap<LocalNode, Box<EnvObject>>;
+
+/// Envs listed in a base (meta) env.
+///
+/// Clones share the env map, so that an env inserted through one, e.g. by an
+/// Agent creating a module env, is found through all of them without cloning
+/// the env itself.
+///
+/// SAFETY: As with RawOverlay, clients must ensure read-write exclusion,
+/// which is trivial for serial execution.
+#[derive(Clone)]
 pub struct MetaEnv {
     base: Box<EnvObject>,
-    envs: BTreeMap<LocalNode, Box<EnvObject>>,
+    envs: Rc<UnsafeCell<EnvMap>>,
 }
 
 impl MetaEnv {
@@ -19,23 +32,33 @@ impl MetaEnv {
     }
 
     pub fn insert_env(&mut self, node: LocalNode, env: Box<EnvObject>) {
-        self.envs.insert(node, env);
+        self.envs_mut().insert(node, env);
     }
 
     // For EnvManager usage in e.g. migrations & defrags.
     // Clients are responsible for ensuring semantic safety of this operation.
     pub unsafe fn replace_env(&mut self, node: LocalNode, env: Box<EnvObject>) {
-        if let Some(entry) = self.envs.get_mut(&node) {
+        if let Some(entry) = self.envs_mut().get_mut(&node) {
             *entry = env;
         }
     }
 
+    /// Remove the env designated by node, leaving node itself in place.
+    ///
+    /// # Safety
+    ///
+    /// Nothing may still refer into the env, such as Nodes held by Agents,
+    /// their continuations, or other envs.
+    pub unsafe fn remove_env(&mut self, node: LocalNode) -> Option<Box<EnvObject>> {
+        self.envs_mut().remove(&node)
+    }
+
     pub fn env(&self, node: LocalNode) -> Option<&Box<EnvObject>> {
-        self.envs.get(&node)
+        self.envs().get(&node)
     }
 
     pub fn env_mut(&mut self, node: LocalNode) -> Option<&mut Box<EnvObject>> {
-        self.envs.get_mut(&node)
+        self.envs_mut().get_mut(&node)
     }
 
     pub fn base(&self) -> &Box<EnvObject> {
@@ -45,4 +68,21 @@ impl MetaEnv {
     pub fn base_mut(&mut self) -> &mut Box<EnvObject> {
         &mut self.base
     }
+
+    fn envs(&self) -> &EnvMap {
+        unsafe { &*self.envs.get() }
+    }
+
+    fn envs_mut(&mut self) -> &mut EnvMap {
+        unsafe { &mut *self.envs.get() }
+    }
+}
+
+impl fmt::Debug for MetaEnv {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MetaEnv")
+            .field("base", &self.base)
+            .field("envs", self.envs())
+            .finish()
+    }
 }
@@ -4,13 +4,15 @@
 //! Errors to be mapped to semantic content within Environments, even if not
 //! part of the base implementation.
 
+use std::any::Any;
 use std::fmt;
 
 use serde::{de, ser};
 
 use crate::agent::agent_frames::ExecFrame;
+use crate::agent::Agent;
 use crate::continuation::Continuation;
-use crate::sexp::{Cons, Sexp};
+use crate::sexp::Sexp;
 
 
 /// Creates a stateful Error wrapped in Err.
@@ -104,10 +106,29 @@ impl Error {
 }
 
 
-pub trait ErrorKind: fmt::Debug /* fmt::Display auto-impled below */ {
+pub trait ErrorKind: fmt::Debug + fmt::Display + KindEq {
     // Cannot use serde since we use ErrorKind as a trait object,
     // although could use erased-serde.
-    fn reify(&self) -> Sexp;
+    /// Reify into a structure built from the nodes of the Agent's
+    /// ErrorContext.
+    fn reify(&self, agent: &Agent) -> Sexp;
+}
+
+/// Structural comparison of ErrorKinds, which may be of differing types.
+/// Implemented for all PartialEq types.
+pub trait KindEq {
+    fn as_any(&self) -> &dyn Any;
+    fn kind_eq(&self, other: &dyn Any) -> bool;
+}
+
+impl<K: PartialEq + 'static> KindEq for K {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn kind_eq(&self, other: &dyn Any) -> bool {
+        other.downcast_ref::<K>() == Some(self)
+    }
 }
 
 #[derive(Debug)]
@@ -117,30 +138,60 @@ enum GenericError {
 }
 
 impl ErrorKind for GenericError {
-    // TODO(func) Model within env rather than fall back on strings.
-    fn reify(&self) -> Sexp {
+    fn reify(&self, agent: &Agent) -> Sexp {
+        let context = agent.error_context();
+        let class = *context.generic_error();
         match self {
-            Self::AdHoc(name, body) => {
-                Cons::new(Sexp::from(name.clone()), Cons::new(body.clone(), None)).into()
-            }
-            Self::Nested(parent, child) => {
-                Cons::new(parent.reify(), Cons::new(child.reify(), None)).into()
-            }
+            Self::AdHoc(name, body) => context.reify_error(
+                class,
+                *context.ad_hoc(),
+                vec![
+                    (*context.name(), name.clone().into()),
+                    (*context.body(), body.clone()),
+                ],
+            ),
+            Self::Nested(parent, child) => context.reify_error(
+                class,
+                *context.nested(),
+                vec![
+                    (*context.parent(), parent.reify(agent)),
+                    (*context.child(), child.reify(agent)),
+                ],
+            ),
         }
     }
 }
 
 
-impl PartialEq for Error {
-    /// Compare kind.
+impl PartialEq for GenericError {
     fn eq(&self, other: &Self) -> bool {
-        self.kind().reify() == other.kind().reify()
+        match (self, other) {
+            (Self::AdHoc(name, body), Self::AdHoc(other_name, other_body)) => {
+                name == other_name && body == other_body
+            }
+            (Self::Nested(parent, child), Self::Nested(other_parent, other_child)) => {
+                (**parent).kind_eq((**other_parent).as_any())
+                    && (**child).kind_eq((**other_child).as_any())
+            }
+            _ => false,
+        }
     }
 }
 
-impl fmt::Display for dyn ErrorKind + '_ {
+impl fmt::Display for GenericError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.reify())
+        match self {
+            Self::AdHoc(name, body) => write!(f, "{}: {}", name, body),
+            Self::Nested(parent, child) => write!(f, "{}: {}", parent, child),
+        }
+    }
+}
+
+
+impl PartialEq for Error {
+    /// Compare kind.
+    fn eq(&self, other: &Self) -> bool {
+        self.kind().kind_eq(other.kind().as_any())
     }
 }
 
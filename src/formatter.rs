@@ -0,0 +1,365 @@
+//! Pretty-printer of Amlang source and .env files.
+//!
+//! Forms are kept on one line when they fit. Otherwise, special forms
+//! taking a body (lambda, let, ...) keep their leading args on the first
+//! line and indent their body, while other lists align their args under
+//! the first one. Comments and (collapsed) blank lines are preserved.
+
+use crate::check::Diagnostic;
+use crate::cst::{Cst, CstKind};
+
+
+/// Special forms with a body, and how many args precede it.
+const BODY_FORMS: [(&str, usize); 10] = [
+    ("lambda", 1),
+    ("fexpr", 1),
+    ("defmacro", 2),
+    ("let", 1),
+    ("letrec", 1),
+    ("def", 1),
+    ("match", 1),
+    ("progn", 0),
+    ("and", 0),
+    ("or", 0),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FormatMode {
+    Source,
+    /// .env files are read a line at a time, so top-level forms are never
+    /// broken: comments within them are hoisted onto lines of their own
+    /// before them, and newlines within strings are escaped. As with the
+    /// serializer, top-level atoms are indented by a space (aligning them
+    /// with structured lines) and the file ends with a blank line.
+    Env,
+}
+
+#[derive(Clone, Debug)]
+pub struct FormatOptions {
+    pub mode: FormatMode,
+    pub max_width: usize,
+    pub indent: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            mode: FormatMode::Source,
+            max_width: 80,
+            indent: 2,
+        }
+    }
+}
+
+impl FormatOptions {
+    pub fn env() -> Self {
+        Self {
+            mode: FormatMode::Env,
+            ..Default::default()
+        }
+    }
+}
+
+
+/// Format text, failing with every syntax error if it doesn't parse.
+pub fn format<S: AsRef<str>>(input: S, options: &FormatOptions) -> Result<String, Vec<Diagnostic>> {
+    Ok(format_cst(&Cst::parse(input)?, options))
+}
+
+pub fn format_cst(forms: &[Cst], options: &FormatOptions) -> String {
+    let formatter = Formatter { options };
+    let mut out = String::new();
+    for (i, form) in forms.iter().enumerate() {
+        if let CstKind::Comment {
+            trailing: true,
+            text,
+        } = &form.kind
+        {
+            if i > 0 {
+                out.pop();
+                out.push(' ');
+                out.push_str(text);
+                out.push('\n');
+                continue;
+            }
+        }
+
+        if i > 0 && form.blank_before {
+            out.push('\n');
+        }
+        let line = match (options.mode, &form.kind) {
+            (FormatMode::Env, CstKind::Atom(atom)) => format!(" {}", atom.replace('\n', "\\n")),
+            (FormatMode::Env, _) => {
+                let mut comments = vec![];
+                let line = formatter.env_line(form, &mut comments).unwrap_or_default();
+                for comment in comments {
+                    out.push_str(comment);
+                    out.push('\n');
+                }
+                line
+            }
+            (FormatMode::Source, _) => formatter.render(form, 0, false),
+        };
+        out.push_str(&line);
+        out.push('\n');
+    }
+    if options.mode == FormatMode::Env && !out.is_empty() {
+        out.push('\n');
+    }
+    out
+}
+
+
+struct Formatter<'a> {
+    options: &'a FormatOptions,
+}
+
+impl<'a> Formatter<'a> {
+    // Single-line rendering, if possible (i.e. no comments or multi-line
+    // strings).
+    fn flat(&self, cst: &Cst) -> Option<String> {
+        match &cst.kind {
+            CstKind::Atom(atom) => {
+                if atom.contains('\n') {
+                    None
+                } else {
+                    Some(atom.clone())
+                }
+            }
+            CstKind::Comment { .. } => None,
+            CstKind::Period => Some(".".to_string()),
+            CstKind::Quoted { prefix, datum } => Some(format!("{}{}", prefix, self.flat(datum)?)),
+            CstKind::List { vector, elems } => {
+                let mut parts = Vec::with_capacity(elems.len());
+                for elem in elems {
+                    parts.push(self.flat(elem)?);
+                }
+                let (open, close) = delimiters(*vector);
+                Some(format!("{}{}{}", open, parts.join(" "), close))
+            }
+        }
+    }
+
+    // Single-line rendering regardless of comments, which are collected in
+    // comments rather than rendered, and multi-line strings, whose newlines
+    // are escaped. None for comments themselves.
+    fn env_line<'c>(&self, cst: &'c Cst, comments: &mut Vec<&'c str>) -> Option<String> {
+        match &cst.kind {
+            CstKind::Atom(atom) => Some(atom.replace('\n', "\\n")),
+            CstKind::Comment { text, .. } => {
+                comments.push(text);
+                None
+            }
+            CstKind::Period => Some(".".to_string()),
+            CstKind::Quoted { prefix, datum } => {
+                Some(format!("{}{}", prefix, self.env_line(datum, comments)?))
+            }
+            CstKind::List { vector, elems } => {
+                let parts = elems
+                    .iter()
+                    .filter_map(|elem| self.env_line(elem, comments))
+                    .collect::<Vec<_>>();
+                let (open, close) = delimiters(*vector);
+                Some(format!("{}{}{}", open, parts.join(" "), close))
+            }
+        }
+    }
+
+    // Rendering starting at col, with any later lines fully indented.
+    //
+    // Lists within data (i.e. quoted) are filled rather than laid out as code.
+    fn render(&self, cst: &Cst, col: usize, data: bool) -> String {
+        if let Some(flat) = self.flat(cst) {
+            if col + flat.chars().count() <= self.options.max_width {
+                return flat;
+            }
+        }
+
+        match &cst.kind {
+            CstKind::Atom(atom) => atom.clone(),
+            CstKind::Comment { text, .. } => text.clone(),
+            CstKind::Period => ".".to_string(),
+            CstKind::Quoted { prefix, datum } => {
+                let data = prefix == "'" || prefix == "`";
+                format!("{}{}", prefix, self.render(datum, col + prefix.len(), data))
+            }
+            CstKind::List { vector, elems } => self.render_list(*vector, elems, col, data),
+        }
+    }
+
+    fn render_list(&self, vector: bool, elems: &[Cst], col: usize, data: bool) -> String {
+        let (open, close) = delimiters(vector);
+        let units = units(elems);
+        if units.is_empty() {
+            return format!("{}{}", open, close);
+        }
+        if data {
+            return self.fill_list(open, close, &units, col);
+        }
+
+        // Number of units after the head kept on the first line, and the
+        // indentation of the rest.
+        let head = match (vector, &units[0]) {
+            (
+                false,
+                Unit::Elem(Cst {
+                    kind: CstKind::Atom(atom),
+                    ..
+                }),
+            ) => Some(atom.as_str()),
+            _ => None,
+        };
+        let (inline, rest_col) = match head {
+            Some(head) => match BODY_FORMS.iter().find(|(name, _)| *name == head) {
+                Some((_, args)) => (*args, col + self.options.indent),
+                None => {
+                    let aligned = col + 1 + head.chars().count() + 1;
+                    if units.len() > 1
+                        && !units[1].is_comment()
+                        && aligned * 2 <= self.options.max_width
+                    {
+                        (1, aligned)
+                    } else {
+                        (0, col + 1)
+                    }
+                }
+            },
+            None => (0, col + 1),
+        };
+
+        let mut out = String::from(open);
+        for (i, unit) in units.iter().enumerate() {
+            let on_first_line = i <= inline && !units[..i].iter().any(|u| u.is_comment());
+            if i > 0 {
+                if (unit.is_trailing_comment()) || (on_first_line && !unit.is_comment()) {
+                    out.push(' ');
+                } else {
+                    out.push('\n');
+                    if unit.blank_before() {
+                        out.push('\n');
+                    }
+                    out.push_str(&" ".repeat(rest_col));
+                }
+            }
+            let unit_col = current_col(&out, col);
+            out.push_str(&unit.render(self, unit_col, false));
+        }
+        self.close_list(out, close, &units, col)
+    }
+
+    // Pack as many units on each line as fit, aligned just inside open.
+    fn fill_list(&self, open: &str, close: &str, units: &[Unit], col: usize) -> String {
+        let mut out = String::from(open);
+        for (i, unit) in units.iter().enumerate() {
+            if i > 0 {
+                let after_comment = units[i - 1].is_comment();
+                let fits = unit.flat(self).is_some_and(|flat| {
+                    current_col(&out, col) + 1 + flat.chars().count() <= self.options.max_width
+                });
+                if unit.is_trailing_comment() || (fits && !after_comment && !unit.is_comment()) {
+                    out.push(' ');
+                } else {
+                    out.push('\n');
+                    if unit.blank_before() {
+                        out.push('\n');
+                    }
+                    out.push_str(&" ".repeat(col + 1));
+                }
+            }
+            let unit_col = current_col(&out, col);
+            out.push_str(&unit.render(self, unit_col, true));
+        }
+        self.close_list(out, close, units, col)
+    }
+
+    fn close_list(&self, mut out: String, close: &str, units: &[Unit], col: usize) -> String {
+        // A comment runs to the end of its line.
+        if units.last().unwrap().is_comment() {
+            out.push('\n');
+            out.push_str(&" ".repeat(col));
+        }
+        out.push_str(close);
+        out
+    }
+}
+
+
+// Elements of a list, with any period joined to the tail following it.
+enum Unit<'a> {
+    Elem(&'a Cst),
+    Tail(&'a Cst),
+}
+
+impl<'a> Unit<'a> {
+    fn is_comment(&self) -> bool {
+        matches!(self, Unit::Elem(cst) if cst.is_comment())
+    }
+
+    fn is_trailing_comment(&self) -> bool {
+        matches!(
+            self,
+            Unit::Elem(Cst {
+                kind: CstKind::Comment { trailing: true, .. },
+                ..
+            })
+        )
+    }
+
+    fn blank_before(&self) -> bool {
+        match self {
+            Unit::Elem(cst) | Unit::Tail(cst) => cst.blank_before,
+        }
+    }
+
+    fn flat(&self, formatter: &Formatter) -> Option<String> {
+        match self {
+            Unit::Elem(cst) => formatter.flat(cst),
+            Unit::Tail(cst) => Some(format!(". {}", formatter.flat(cst)?)),
+        }
+    }
+
+    fn render(&self, formatter: &Formatter, col: usize, data: bool) -> String {
+        match self {
+            Unit::Elem(cst) => formatter.render(cst, col, data),
+            Unit::Tail(cst) => format!(". {}", formatter.render(cst, col + 2, data)),
+        }
+    }
+}
+
+fn units(elems: &[Cst]) -> Vec<Unit<'_>> {
+    let mut units = Vec::with_capacity(elems.len());
+    let mut period = false;
+    for elem in elems {
+        match &elem.kind {
+            CstKind::Period => period = true,
+            CstKind::Comment { .. } => units.push(Unit::Elem(elem)),
+            _ if period => {
+                period = false;
+                units.push(Unit::Tail(elem));
+            }
+            _ => units.push(Unit::Elem(elem)),
+        }
+    }
+    units
+}
+
+fn delimiters(vector: bool) -> (&'static str, &'static str) {
+    if vector {
+        ("[", "]")
+    } else {
+        ("(", ")")
+    }
+}
+
+// Column just past s, given it starts at col.
+fn current_col(s: &str, col: usize) -> usize {
+    match s.rfind('\n') {
+        Some(i) => s[i + 1..].chars().count(),
+        None => col + s.chars().count(),
+    }
+}
+
+
+#[cfg(test)]
+#[path = "./formatter_test.rs"]
+mod formatter_test;
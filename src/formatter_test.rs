@@ -0,0 +1,102 @@
+use super::*;
+
+const SAMPLE: &str = "; Sample script.
+(def   fact (lambda (n) (if (< n 2) 1 (* n (fact (- n 1))))))   ; recursive
+
+
+(def long-list '(alpha beta gamma delta epsilon zeta eta theta iota kappa lambda mu))
+(let ((a 1)
+      ; the second binding
+      (b [2 \"two\\nlines\" 3]))
+  (+ a b))
+`(a ,b ,@c . d)
+";
+
+fn width(max_width: usize) -> FormatOptions {
+    FormatOptions {
+        max_width,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn fits() {
+    assert_eq!(
+        format("(a   b\n (c . d))  '[e  \"f g\"]", &width(80)).unwrap(),
+        "(a b (c . d))\n'[e \"f g\"]\n"
+    );
+}
+
+#[test]
+fn breaks() {
+    let expected = "; Sample script.
+(def fact
+  (lambda (n)
+    (if (< n 2) 1 (* n (fact (- n 1)))))) ; recursive
+
+(def long-list
+  '(alpha beta gamma delta epsilon zeta
+    eta theta iota kappa lambda mu))
+(let ((a 1)
+      ; the second binding
+      (b [2 \"two\\nlines\" 3]))
+  (+ a b))
+`(a ,b ,@c . d)
+";
+    assert_eq!(format(SAMPLE, &width(40)).unwrap(), expected);
+}
+
+#[test]
+fn aligned_args() {
+    assert_eq!(
+        format("(foo (bar baz) (qux quux) corge)", &width(16)).unwrap(),
+        "(foo (bar baz)\n     (qux quux)\n     corge)\n"
+    );
+    // Comments force breaks, and closes follow comments on their own line.
+    assert_eq!(
+        format("(foo a ; first\n b\n\n c ; last\n)", &width(80)).unwrap(),
+        "(foo a ; first\n     b\n\n     c ; last\n)\n"
+    );
+}
+
+#[test]
+fn idempotent() {
+    for max_width in [10, 20, 40, 80] {
+        let once = format(SAMPLE, &width(max_width)).unwrap();
+        assert_eq!(format(&once, &width(max_width)).unwrap(), once);
+    }
+}
+
+#[test]
+fn env_mode() {
+    let input = "(header (version . \"0.0.5\"))\n\n(section nodes)\n   a\n(b (__builtin b))";
+    assert_eq!(
+        format(input, &FormatOptions::env()).unwrap(),
+        "(header (version . \"0.0.5\"))\n\n(section nodes)\n a\n(b (__builtin b))\n\n"
+    );
+
+    // Forms which don't fit on a line in Source mode are still kept whole.
+    let input = " ^1\n(^2 (lambda (a) ; c\n a))\n(^3 \"x\ny\")";
+    assert_eq!(
+        format(input, &FormatOptions::env()).unwrap(),
+        " ^1\n; c\n(^2 (lambda (a) a))\n(^3 \"x\\ny\")\n\n"
+    );
+
+    // Serialized envs are already formatted.
+    let envs = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("envs");
+    for name in ["lang.env", "error.env", "meta.env"] {
+        let input = std::fs::read_to_string(envs.join(name)).unwrap();
+        assert_eq!(
+            format(&input, &FormatOptions::env()).unwrap(),
+            input,
+            "{}",
+            name
+        );
+    }
+}
+
+#[test]
+fn syntax_errors() {
+    let diagnostics = format("(a b))\n(c", &width(80)).unwrap_err();
+    assert_eq!(diagnostics.len(), 2);
+}
@@ -1,8 +1,9 @@
 use log::info;
 use std::env::set_current_dir;
-use std::fs::{copy, read_dir, remove_file};
+use std::fs::{read_dir, remove_file, write};
 use std::path::{Path, PathBuf};
 
+use crate::agent::env_sources::{embedded_env, META_PATH};
 use crate::error::Error;
 
 
@@ -16,11 +17,16 @@ pub mod stream;
 pub mod agent;
 
 pub mod builtins;
+pub mod check;
 pub mod continuation;
+pub mod cst;
 pub mod env;
+pub mod formatter;
 pub mod introspect;
 pub mod parser;
 pub mod primitive;
+pub mod runtime;
+pub mod span;
 pub mod std_error;
 pub mod token;
 pub mod version;
@@ -30,6 +36,7 @@ pub mod prelude {
     pub use crate::env::{NodeSet, TripleSet};
     pub use crate::error::{Error, ErrorKind};
     pub use crate::primitive::prelude::*;
+    pub use crate::runtime::Runtime;
     pub use crate::sexp::{Cons, ConsList, HeapSexp, Sexp};
     pub use crate::std_error::StdError;
     pub use crate::InitOptions;
@@ -45,8 +52,11 @@ pub mod prelude {
 ///
 /// IsolatedRun will use a different, project-specific path for
 /// reading/writing envs. Some Amlang envs will be copied over for
-/// bootstrapping purposes. Note that the lang.env is currently still
-/// the direct one from upstream Amlang.
+/// bootstrapping purposes. Note that the lang.env and error.env are
+/// still those of upstream Amlang, as embedded in the crate.
+///
+/// To read lang.env and error.env from disk as well, bootstrap RootRuns
+/// from EnvSources::root_run().
 pub enum InitOptions {
     IsolatedRun(PathBuf, bool), // env_path, reset_state
     RootRun,
@@ -57,11 +67,11 @@ pub enum InitOptions {
 /// Note that this function does *not* setup logging, clients should
 /// take care of that prior to calling this function. See:
 ///   https://github.com/rust-lang/log#in-executables.
+///
+/// As this sets the current dir of the process, embedders running several
+/// Amlang instances should instead use Runtime, bootstrapping from
+/// EnvSources.
 pub fn init(options: InitOptions) -> Result<(), Error> {
-    let amlang_root = Path::new(env!("CARGO_MANIFEST_DIR"))
-        .canonicalize()
-        .unwrap();
-
     match options {
         InitOptions::IsolatedRun(env_path, should_reset_state) => {
             // Need to set dir to properly read relative paths in envs.
@@ -78,14 +88,14 @@ pub fn init(options: InitOptions) -> Result<(), Error> {
                     return Err(Error::from(err).wrap_adhoc("InitErr", "Resetting state failed"));
                 }
             }
-            let meta_path = "meta.env";
-            if !Path::new(meta_path).exists() {
-                if let Err(err) = copy_meta(&amlang_root, &env_path) {
+            if !Path::new(META_PATH).exists() {
+                if let Err(err) = copy_meta(&env_path) {
                     return Err(err.wrap_adhoc("InitErr", "Copying meta.env failed"));
                 }
             }
         }
         InitOptions::RootRun => {
+            let amlang_root = Path::new(env!("CARGO_MANIFEST_DIR"));
             if let Err(err) = set_current_dir(amlang_root.join("envs")) {
                 return Err(Error::from(err).wrap_adhoc("InitErr", "Setting to amlang dir failed"));
             }
@@ -96,7 +106,7 @@ pub fn init(options: InitOptions) -> Result<(), Error> {
 }
 
 
-fn reset_state(env_path: &Path) -> Result<(), Error> {
+pub(crate) fn reset_state(env_path: &Path) -> Result<(), Error> {
     for entry in read_dir(env_path)? {
         let entry = entry?;
         let path = entry.path();
@@ -110,10 +120,9 @@ fn reset_state(env_path: &Path) -> Result<(), Error> {
     Ok(())
 }
 
-fn copy_meta(amlang_root: &Path, env_path: &Path) -> Result<(), Error> {
-    info!("No meta env; copying from envs/.");
-    let amlang_meta = amlang_root.join("envs/meta.env");
-    let target_meta = env_path.join("meta.env");
-    copy(amlang_meta, target_meta.clone())?;
+fn copy_meta(env_path: &Path) -> Result<(), Error> {
+    info!("No meta env; copying embedded one.");
+    let target_meta = env_path.join(META_PATH);
+    write(target_meta, embedded_env(META_PATH).unwrap())?;
     Ok(())
 }
@@ -1,11 +1,14 @@
 //! Module for parsing Amlang tokens into an AST.
 use std::collections::VecDeque;
+use std::fmt;
 
+use crate::agent::Agent;
 use crate::continuation::Continuation;
 use crate::error::{Error, ErrorKind};
 use crate::primitive::symbol_policies::policy_base;
-use crate::primitive::ToSymbol;
+use crate::primitive::{Number, ToSymbol, Vector};
 use crate::sexp::{ConsList, Sexp};
+use crate::span::{SharedSourceMap, SourceId, Span, SpanTree};
 use crate::stream::Transform;
 use crate::token::{Token, TokenKind};
 
@@ -20,9 +23,21 @@ pub struct Parser {
     // for the parser).
     state: Continuation<(ParserState, usize)>, // All but root are in quotes.
     current: Vec<ConsList>,
+    // Lengths of current at which [ opened a Vector rather than a list.
+    open_vectors: Vec<usize>,
+    // Where each of current was opened, along with spans of its elements.
+    current_spans: Vec<((usize, usize), Vec<SpanTree>)>,
     max_current_len: usize,
 
     sexps: VecDeque<Sexp>,
+    // SourceMap to record a SpanTree per Sexp in, if any.
+    source: Option<(SharedSourceMap, SourceId)>,
+
+    // Whether to collect errors and resynchronize rather than fail.
+    recover: bool,
+    errors: Vec<ParseError>,
+    // Unclosed parens/brackets of a form being skipped after an error.
+    skip_depth: Option<usize>,
 }
 
 #[derive(Debug)]
@@ -37,12 +52,117 @@ impl Parser {
         Self {
             state: Continuation::new((Base, 0)),
             current: Default::default(),
+            open_vectors: Default::default(),
+            current_spans: Default::default(),
             max_current_len: MAX_DEPTH,
             sexps: Default::default(),
+            source: None,
+            recover: false,
+            errors: Default::default(),
+            skip_depth: None,
+        }
+    }
+
+    /// Collect errors rather than failing, to be retrieved through
+    /// take_errors.
+    ///
+    /// After an error, the rest of the enclosing top-level form is skipped.
+    /// Parens at the start of a line are taken to start a new top-level
+    /// form, so a missing close paren doesn't swallow the rest of the input.
+    pub fn recovering(mut self) -> Self {
+        self.recover = true;
+        self
+    }
+
+    pub fn take_errors(&mut self) -> Vec<ParseError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Signal end of input, failing if a form was left open.
+    pub fn finish(&mut self) -> Result<(), ParseError> {
+        let res = match self.unclosed() {
+            Some(err) => Err(err),
+            None => Ok(()),
+        };
+        self.reset();
+        res
+    }
+
+    fn recover_token(&mut self, token: Token) {
+        let opens = matches!(token.token, TokenKind::LeftParen | TokenKind::LeftBracket);
+        let closes = matches!(token.token, TokenKind::RightParen | TokenKind::RightBracket);
+        if opens && token.col == 0 {
+            self.skip_depth = None;
+            if let Some(err) = self.unclosed() {
+                self.errors.push(err);
+                self.reset();
+            }
+        }
+
+        if let Some(depth) = self.skip_depth {
+            let depth = if opens {
+                depth + 1
+            } else if closes {
+                depth.saturating_sub(1)
+            } else {
+                depth
+            };
+            self.skip_depth = if depth > 0 { Some(depth) } else { None };
+            return;
+        }
+
+        // Parens left open by the failing token, which are yet to be closed.
+        let depth = self.current.len() - (self.state.depth() - 1) + opens as usize;
+        if let Err(err) = self.parse_token(token) {
+            self.errors.push(err);
+            self.reset();
+            if depth > 0 {
+                self.skip_depth = Some(depth);
+            }
         }
     }
 
+    // Error for the outermost paren/bracket left open, or else quote.
+    fn unclosed(&self) -> Option<ParseError> {
+        // Lengths of current at which quotes opened; the root state has 0.
+        let quotes = self.state.iter().map(|(_, len)| *len).collect::<Vec<_>>();
+        let i = (0..self.current.len())
+            .find(|i| !quotes.contains(&(i + 1)))
+            .or_else(|| self.current.len().checked_sub(1))?;
+        let (line, col) = self.current_spans[i].0;
+        let (reason, kind) = if quotes.contains(&(i + 1)) {
+            (TrailingQuote, TokenKind::Quote)
+        } else if self.open_vectors.contains(&(i + 1)) {
+            (Unclosed, TokenKind::LeftBracket)
+        } else {
+            (Unclosed, TokenKind::LeftParen)
+        };
+        Some(ParseError {
+            reason,
+            token: Token {
+                token: kind,
+                line,
+                col,
+                end: (line, col + 1),
+            },
+        })
+    }
+
+    fn reset(&mut self) {
+        self.state = Continuation::new((Base, 0));
+        self.current.clear();
+        self.open_vectors.clear();
+        self.current_spans.clear();
+    }
+
+    /// Record spans of parsed Sexps in map, as part of source.
+    pub fn with_source(mut self, map: SharedSourceMap, source: SourceId) -> Self {
+        self.source = Some((map, source));
+        self
+    }
+
     fn parse_token(&mut self, token: Token) -> Result<(), ParseError> {
+        let span = self.span(&token);
         match token.token {
             TokenKind::LeftParen => match self.curr_state() {
                 Base => {
@@ -52,7 +172,7 @@ impl Parser {
                             token,
                         });
                     }
-                    self.current.push(ConsList::new());
+                    self.open(&token);
                 }
                 _ => {
                     return Err(ParseError {
@@ -61,21 +181,48 @@ impl Parser {
                     });
                 }
             },
+            TokenKind::LeftBracket => match self.curr_state() {
+                Base => {
+                    if self.current.len() >= self.max_current_len {
+                        return Err(ParseError {
+                            reason: DepthOverflow,
+                            token,
+                        });
+                    }
+                    self.open(&token);
+                    self.open_vectors.push(self.current.len());
+                }
+                _ => {
+                    return Err(ParseError {
+                        reason: UnmatchedOpen,
+                        token,
+                    });
+                }
+            },
+            TokenKind::RightBracket => {
+                if !self.in_vector() {
+                    return Err(ParseError {
+                        reason: UnmatchedClose,
+                        token,
+                    });
+                }
+                self.close_vector(token.end)?;
+            }
             TokenKind::RightParen => match self.curr_state() {
                 Base => {
-                    if self.current.len() == 0 {
+                    if self.current.is_empty() || self.in_vector() {
                         return Err(ParseError {
                             reason: UnmatchedClose,
                             token,
                         });
                     }
-                    self.close(None)?;
+                    self.close(None, token.end)?;
                 }
                 ImproperClose(_) => {
                     let mut state = Base;
                     std::mem::swap(&mut self.state.top_mut().0, &mut state);
                     if let ImproperClose(sexp) = state {
-                        self.close(Some(sexp))?;
+                        self.close(Some(sexp), token.end)?;
                     }
                 }
                 _ => {
@@ -87,9 +234,11 @@ impl Parser {
             },
             TokenKind::Primitive(primitive) => match self.curr_state() {
                 Base => {
-                    self.append(primitive.into())?;
+                    self.append(primitive.into(), SpanTree::leaf(span))?;
                 }
                 ImproperTail => {
+                    let tree = SpanTree::leaf(span);
+                    self.current_spans.last_mut().unwrap().1.push(tree);
                     self.state.top_mut().0 = ImproperClose(primitive.into());
                 }
                 ImproperClose(_) => {
@@ -99,20 +248,32 @@ impl Parser {
                             token: TokenKind::Primitive(primitive),
                             line: token.line,
                             col: token.col,
+                            end: token.end,
                         },
                     });
                 }
             },
-            TokenKind::Quote => {
-                self.current.push(ConsList::new());
+            TokenKind::Quote
+            | TokenKind::Quasiquote
+            | TokenKind::Unquote
+            | TokenKind::UnquoteSplicing => {
+                let name = match token.token {
+                    TokenKind::Quote => "quote",
+                    TokenKind::Quasiquote => "quasiquote",
+                    TokenKind::Unquote => "unquote",
+                    _ => "unquote-splicing",
+                };
+                self.open(&token);
                 let len = self.current.len();
 
-                self.current[len - 1].append("quote".to_symbol_or_panic(policy_base));
+                self.current[len - 1].append(name.to_symbol_or_panic(policy_base));
+                let tree = SpanTree::leaf(self.span(&token));
+                self.current_spans[len - 1].1.push(tree);
                 self.state.push((Base, len));
             }
             TokenKind::Period => match self.curr_state() {
                 Base => {
-                    if self.current.len() == 0 {
+                    if self.current.is_empty() || self.in_vector() {
                         return Err(ParseError {
                             reason: IsolatedPeriod,
                             token,
@@ -135,18 +296,23 @@ impl Parser {
         Ok(())
     }
 
-    fn append(&mut self, sexp: Sexp) -> Result<(), ParseError> {
+    fn append(&mut self, sexp: Sexp, tree: SpanTree) -> Result<(), ParseError> {
         let len = self.current.len();
+        let end = (tree.span.end_line, tree.span.end_col);
         if len == 0 {
             self.sexps.push_back(sexp);
+            if let Some((map, _)) = &self.source {
+                map.borrow_mut().push_parsed(tree);
+            }
             return Ok(());
         } else {
             self.current[len - 1].append(sexp);
+            self.current_spans[len - 1].1.push(tree);
         }
 
         if self.state.top().1 == self.current.len() {
             match self.state.pop() {
-                Some((Base, _)) => self.close(None),
+                Some((Base, _)) => self.close(None, end),
                 Some((ImproperTail, _)) => {
                     return Err(ParseError {
                         reason: NotPenultimatePeriod,
@@ -154,24 +320,77 @@ impl Parser {
                             token: TokenKind::Period,
                             line: 0,
                             col: 0,
+                            end: (0, 0),
                         },
                     });
                 }
-                Some((ImproperClose(sexp), _)) => self.close(Some(sexp)),
-                state @ _ => panic!("{:?}", state),
+                Some((ImproperClose(sexp), _)) => self.close(Some(sexp), end),
+                state => panic!("{:?}", state),
             }?
         }
         Ok(())
     }
 
-    fn close(&mut self, tail: Option<Sexp>) -> Result<(), ParseError> {
+    fn open(&mut self, token: &Token) {
+        self.current.push(ConsList::new());
+        self.current_spans.push(((token.line, token.col), vec![]));
+    }
+
+    fn close(&mut self, tail: Option<Sexp>, end: (usize, usize)) -> Result<(), ParseError> {
         let last = self.current.pop().unwrap();
         let sexp = match tail {
             Some(tail) => last.release_with_tail(Some(tail.into())),
             None => last.release(),
         };
 
-        self.append(sexp)
+        let tree = self.close_spans(end);
+        self.append(sexp, tree)
+    }
+
+    fn close_vector(&mut self, end: (usize, usize)) -> Result<(), ParseError> {
+        self.open_vectors.pop();
+        let last = self.current.pop().unwrap();
+        let elements = last
+            .release()
+            .into_iter()
+            .map(|(elem, _)| *elem)
+            .collect::<Vec<_>>();
+
+        let tree = self.close_spans(end);
+        self.append(Vector::from(elements).into(), tree)
+    }
+
+    fn close_spans(&mut self, end: (usize, usize)) -> SpanTree {
+        let ((line, col), children) = self.current_spans.pop().unwrap();
+        SpanTree {
+            span: Span {
+                source: self.source_id(),
+                line,
+                col,
+                end_line: end.0,
+                end_col: end.1,
+            },
+            children,
+        }
+    }
+
+    fn span(&self, token: &Token) -> Span {
+        Span {
+            source: self.source_id(),
+            line: token.line,
+            col: token.col,
+            end_line: token.end.0,
+            end_col: token.end.1,
+        }
+    }
+
+    fn source_id(&self) -> SourceId {
+        self.source.as_ref().map_or(0, |(_, id)| *id)
+    }
+
+    // Whether the innermost open structure was started with [.
+    fn in_vector(&self) -> bool {
+        self.open_vectors.last() == Some(&self.current.len())
     }
 
     fn curr_state(&self) -> &ParserState {
@@ -182,7 +401,9 @@ impl Parser {
 
 impl Transform<Token, Sexp> for Parser {
     fn input(&mut self, input: Result<Token, Error>) -> Result<bool, Error> {
-        if let Err(err) = self.parse_token(input?) {
+        if self.recover {
+            self.recover_token(input?);
+        } else if let Err(err) = self.parse_token(input?) {
             return Err(Error::no_cont(err));
         }
         Ok(self.sexps.len() > 0)
@@ -194,29 +415,68 @@ impl Transform<Token, Sexp> for Parser {
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum ParseErrorReason {
     DepthOverflow,
     TrailingQuote,
     UnmatchedOpen,
     UnmatchedClose,
+    // Form still open at end of input (or resynchronization).
+    Unclosed,
     IsolatedPeriod,
     NotPenultimatePeriod,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct ParseError {
     reason: ParseErrorReason,
     token: Token,
 }
 
+impl ParseError {
+    pub fn line(&self) -> usize {
+        self.token.line
+    }
+
+    pub fn col(&self) -> usize {
+        self.token.col
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let explanation = match self.reason {
+            DepthOverflow => format!("nesting exceeds max depth of {}", MAX_DEPTH),
+            TrailingQuote => "quote is not followed by anything to quote".to_string(),
+            UnmatchedOpen => "form cannot open after a period".to_string(),
+            Unclosed => "form is never closed".to_string(),
+            UnmatchedClose => "no open form to close".to_string(),
+            IsolatedPeriod => "period must follow a list element".to_string(),
+            NotPenultimatePeriod => "period must precede the last list element".to_string(),
+        };
+        write!(f, "{}", explanation)
+    }
+}
+
 impl ErrorKind for ParseError {
-    // TODO(func) Model within env rather than fall back on strings.
-    fn reify(&self) -> Sexp {
-        list!(
-            "ParseError",
-            format!("{:?}", self.reason),
-            self.token.to_string()
+    fn reify(&self, agent: &Agent) -> Sexp {
+        let context = agent.error_context();
+        let kind = match self.reason {
+            DepthOverflow => context.depth_overflow(),
+            TrailingQuote => context.trailing_quote(),
+            UnmatchedOpen | Unclosed => context.unmatched_open(),
+            UnmatchedClose => context.unmatched_close(),
+            IsolatedPeriod => context.isolated_period(),
+            NotPenultimatePeriod => context.not_penultimate_period(),
+        };
+        context.reify_error(
+            *context.parse_error(),
+            *kind,
+            vec![
+                (*context.token(), self.token.to_string().into()),
+                (*context.line(), Number::USize(self.token.line).into()),
+                (*context.col(), Number::USize(self.token.col).into()),
+            ],
         )
     }
 }
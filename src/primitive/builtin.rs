@@ -46,7 +46,7 @@ impl PartialEq for BuiltIn {
 
 impl fmt::Debug for BuiltIn {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "[BUILTIN_{} @ {:p}]", self.name, &self.fun)
+        write!(f, "[BUILTIN_{} @ {:p}]", self.name, self.fun)
     }
 }
 
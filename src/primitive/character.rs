@@ -0,0 +1,44 @@
+//! Literal forms of char primitives.
+//!
+//! Chars are written as #\ followed by the char itself (#\a), its name
+//! (#\space) or an escape as in strings (#\u{1b}, #\x41).
+
+use super::LangString;
+
+
+const NAMES: [(&str, char); 5] = [
+    ("nul", '\0'),
+    ("tab", '\t'),
+    ("newline", '\n'),
+    ("return", '\r'),
+    ("space", ' '),
+];
+
+pub fn to_literal(c: char) -> String {
+    if let Some((name, _)) = NAMES.iter().find(|(_, named)| *named == c) {
+        format!("#\\{}", name)
+    } else if c.is_control() || c.is_whitespace() {
+        format!("#\\u{{{:x}}}", c as u32)
+    } else {
+        format!("#\\{}", c)
+    }
+}
+
+/// Parse what follows the #\ of a char literal.
+pub fn from_literal(s: &str) -> Option<char> {
+    let mut chars = s.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(c);
+    }
+    if let Some((_, c)) = NAMES.iter().find(|(name, _)| *name == s) {
+        return Some(*c);
+    }
+    if s.starts_with('x') || s.starts_with('u') {
+        if let Some((c, len)) = LangString::unescape(s) {
+            if len == s.len() {
+                return Some(c);
+            }
+        }
+    }
+    None
+}
@@ -15,6 +15,7 @@ use crate::sexp::{HeapSexp, Sexp};
 mod try_from_helper;
 
 pub mod builtin;
+pub mod character;
 pub mod node;
 pub mod number;
 pub mod path;
@@ -38,7 +39,7 @@ pub mod prelude {
     pub use super::string::LangString;
     pub use super::symbol::{Symbol, ToSymbol};
     pub use super::symbol_policies::{
-        policy_admin, policy_base, policy_uuid, AdminSymbolInfo, SymbolPolicy,
+        policy_admin, policy_base, policy_uuid, AdminSymbolInfo, SymbolGrammar, SymbolPolicy,
     };
     pub use super::table::{LocalNodeTable, SymNodeTable, SymSexpTable, Table};
     pub use super::vector::Vector;
@@ -49,9 +50,11 @@ pub use prelude::*;
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum Primitive {
+    Bool(bool),
     Number(Number),
     Symbol(Symbol),
     LangString(LangString),
+    Char(char),
     BuiltIn(BuiltIn),
     Node(Node),
     LangPath(LangPath),
@@ -71,8 +74,12 @@ impl Primitive {
                 m.insert("apply", "Procedure");
                 m.insert("lambda", "Procedure");
                 m.insert("fexpr", "Procedure");
+                m.insert("macro", "Procedure");
                 m.insert("progn", "Procedure");
                 m.insert("if", "Procedure");
+                m.insert(SymNodeTable::DISCRIMINATOR, "SymNodeTable");
+                m.insert(SymSexpTable::DISCRIMINATOR, "SymSexpTable");
+                m.insert(LocalNodeTable::DISCRIMINATOR, "LocalNodeTable");
                 m
             };
         }
@@ -84,18 +91,20 @@ impl Primitive {
 impl fmt::Display for Primitive {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            Primitive::Bool(b) => write!(f, "{}", if *b { "#t" } else { "#f" }),
             Primitive::Number(num) => write!(f, "{}", num),
             Primitive::Symbol(s) => write!(f, "{}", s),
             Primitive::LangString(s) => write!(f, "{}", s),
+            Primitive::Char(c) => write!(f, "{}", character::to_literal(*c)),
             Primitive::BuiltIn(b) => write!(f, "{}", b),
             Primitive::Node(node) => write!(f, "{}", node),
             Primitive::LangPath(path) => write!(f, "{}", path),
 
-            Primitive::SymNodeTable(table) => write!(f, "{:?}", table),
-            Primitive::SymSexpTable(table) => write!(f, "{:?}", table),
-            Primitive::LocalNodeTable(table) => write!(f, "{:?}", table),
+            Primitive::SymNodeTable(table) => write!(f, "{}", table.to_form()),
+            Primitive::SymSexpTable(table) => write!(f, "{}", table.to_form()),
+            Primitive::LocalNodeTable(table) => write!(f, "{}", table.to_form()),
             Primitive::Procedure(proc) => write!(f, "{:?}", proc),
-            Primitive::Vector(vector) => write!(f, "{:?}", vector),
+            Primitive::Vector(vector) => write!(f, "{}", vector),
         }
     }
 }
@@ -110,6 +119,7 @@ impl PartialEq for Primitive {
             let other_d = mem::discriminant(&*other);
             if self_d == other_d {
                 match (&*self, &*other) {
+                    (Primitive::Bool(this), Primitive::Bool(that)) => this == that,
                     (&Primitive::Number(ref this), &Primitive::Number(ref that)) => {
                         (*this) == (*that)
                     }
@@ -119,6 +129,7 @@ impl PartialEq for Primitive {
                     (&Primitive::LangString(ref this), &Primitive::LangString(ref that)) => {
                         (*this) == (*that)
                     }
+                    (Primitive::Char(this), Primitive::Char(that)) => this == that,
                     (&Primitive::BuiltIn(ref this), &Primitive::BuiltIn(ref that)) => {
                         (*this) == (*that)
                     }
@@ -204,6 +215,52 @@ macro_rules! primitive_from {
     () => {};
 }
 
+impl From<bool> for Primitive {
+    fn from(elem: bool) -> Self {
+        Primitive::Bool(elem)
+    }
+}
+impl From<bool> for Sexp {
+    fn from(elem: bool) -> Self {
+        Sexp::Primitive(Primitive::Bool(elem))
+    }
+}
+impl From<bool> for HeapSexp {
+    fn from(elem: bool) -> Self {
+        Self::new(Sexp::Primitive(Primitive::Bool(elem)))
+    }
+}
+
+impl_try_from!(Bool;
+               Primitive         ->  bool,
+               Sexp              ->  bool,
+               HeapSexp          ->  bool,
+               ref Sexp          ->  ref bool,
+);
+
+impl From<char> for Primitive {
+    fn from(elem: char) -> Self {
+        Primitive::Char(elem)
+    }
+}
+impl From<char> for Sexp {
+    fn from(elem: char) -> Self {
+        Sexp::Primitive(Primitive::Char(elem))
+    }
+}
+impl From<char> for HeapSexp {
+    fn from(elem: char) -> Self {
+        Self::new(Sexp::Primitive(Primitive::Char(elem)))
+    }
+}
+
+impl_try_from!(Char;
+               Primitive         ->  char,
+               Sexp              ->  char,
+               HeapSexp          ->  char,
+               ref Sexp          ->  ref char,
+);
+
 primitive_from!(
     Number,
     Symbol,
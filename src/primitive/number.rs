@@ -1,8 +1,12 @@
 //! Representation of Amlang numbers.
 
+use std::cmp::Ordering;
 use std::convert::TryFrom;
-use std::{fmt, mem, ops, str};
+use std::{fmt, str};
 
+use num_integer::Integer;
+use num_rational::BigRational;
+use num_traits::{Float, Signed, ToPrimitive, Zero};
 use serde::{Deserialize, Serialize};
 
 use self::Number::*;
@@ -46,10 +50,12 @@ macro_rules! generate_number {
         ($($ivariant:ident : $itype:ident),+$(,)?),
         ($($fvariant:ident : $ftype:ident),+$(,)?),
     ) => {
-        #[derive(Clone, Copy, Serialize, Deserialize)]
+        #[derive(Clone, Serialize, Deserialize)]
         pub enum Number {
             $($variant($type),)+
             GenericInt(i128),
+            BigInt(num_bigint::BigInt),
+            Rational(BigRational),
         }
 
         impl Number {
@@ -64,66 +70,51 @@ macro_rules! generate_number {
                     f @ Self::F32(_) | f @ Self::F64(_) => f,
                     $(Self::$ivariant(i) => GenericInt(i as i128),)+
                     g @ GenericInt(_) => g,
+                    b @ Self::BigInt(_) => b,
+                    r @ Rational(_) => r,
                 }
             }
 
-            fn pre_op(&mut self, other: Self) -> Self {
-                if let Some((_, other)) = self.matching_types(other) {
-                    return other;
-                } else {
-                    panic!("Mismatching number types");
+            /// Value of an integer which fits in an i128.
+            fn as_i128(&self) -> Option<i128> {
+                match self {
+                    $(Self::$ivariant(i) => Some(*i as i128),)+
+                    GenericInt(i) => Some(*i),
+                    _ => None,
                 }
             }
-        }
 
-        impl ops::AddAssign for Number {
-            fn add_assign(&mut self, other: Self) {
-                let other = self.pre_op(other);
-                match (self, &other) {
-                    $(
-                        ($variant(this), &$variant(ref that)) => *this += that,
-                    )+
-                    (GenericInt(this), &GenericInt(ref that)) => *this += that,
-                    _ => panic!()
+            /// Signedness and width of fixed-width integers.
+            fn fixed_width(&self) -> Option<(bool, u32)> {
+                match self {
+                    $(Self::$ivariant(_) => Some(($itype::MIN != 0, $itype::BITS)),)+
+                    _ => None,
                 }
             }
-        }
 
-        impl ops::SubAssign for Number {
-            fn sub_assign(&mut self, other: Self) {
-                let other = self.pre_op(other);
-                match (self, &other) {
-                    $(
-                        ($variant(this), &$variant(ref that)) => *this -= that,
-                    )+
-                    (GenericInt(this), &GenericInt(ref that)) => *this -= that,
-                    _ => panic!()
+            /// Create an integer of the same variant as self, if i fits.
+            fn with_int(&self, i: i128) -> Option<Number> {
+                match self {
+                    $(Self::$ivariant(_) => $itype::try_from(i).ok().map(Self::$ivariant),)+
+                    GenericInt(_) => Some(GenericInt(i)),
+                    _ => None,
                 }
             }
-        }
 
-        impl ops::MulAssign for Number {
-            fn mul_assign(&mut self, other: Self) {
-                let other = self.pre_op(other);
-                match (self, &other) {
-                    $(
-                        ($variant(this), &$variant(ref that)) => *this *= that,
-                    )+
-                    (GenericInt(this), &GenericInt(ref that)) => *this *= that,
-                    _ => panic!()
+            fn to_f64(&self) -> f64 {
+                match self {
+                    $(Self::$variant(v) => *v as f64,)+
+                    GenericInt(i) => *i as f64,
+                    Self::BigInt(i) => i.to_f64().unwrap_or(f64::NAN),
+                    Rational(r) => r.to_f64().unwrap_or(f64::NAN),
                 }
             }
-        }
 
-        impl ops::DivAssign for Number {
-            fn div_assign(&mut self, other: Self) {
-                let other = self.pre_op(other);
-                match (self, &other) {
-                    $(
-                        ($variant(this), &$variant(ref that)) => *this /= that,
-                    )+
-                    (GenericInt(this), &GenericInt(ref that)) => *this /= that,
-                    _ => panic!()
+            fn to_rational(&self) -> Option<BigRational> {
+                match self {
+                    Self::BigInt(i) => Some(BigRational::from_integer(i.clone())),
+                    Rational(r) => Some(r.clone()),
+                    _ => self.as_i128().map(|i| BigRational::from_integer(i.into())),
                 }
             }
         }
@@ -166,10 +157,9 @@ macro_rules! generate_number {
 
                 fn try_from(value: Sexp) -> Result<Self, Self::Error> {
                     let num = Number::try_from(value)?;
-                    if let Ok(val) = $type::try_from(num) {
-                        Ok(val)
-                    } else {
-                        Err(num.into())
+                    match $type::try_from(num) {
+                        Ok(val) => Ok(val),
+                        Err(num) => Err(num.into()),
                     }
                 }
             }
@@ -193,74 +183,294 @@ macro_rules! generate_number {
             }
         )+
 
-        impl Number {
-            fn matching_types(&mut self, other: Number) -> Option<(&mut Number, Number)> {
-                let self_d = mem::discriminant(&*self);
-                let other_d = mem::discriminant(&other);
-                if self_d == other_d {
-                    Some((self, other))
-                } else {
-                    match (&*self, &other) {
-                        $((Number::GenericInt(ref this), Number::$ivariant(ref _that)) => {
-                            if let Ok(i) = $itype::try_from(*this) {
-                                *self = Number::$ivariant(i);
-                                Some((self, other))
-                            } else {
-                                None
-                            }
-                        })+
-                        $((Number::$ivariant(ref _this), Number::GenericInt(ref that)) => {
-                            if let Ok(i) = $itype::try_from(*that) {
-                                Some((self, Number::$ivariant(i)))
-                            } else {
-                                None
-                            }
-                         })+
-                         _ => None
-                    }
-                }
-            }
-        }
-
         impl PartialEq for Number {
             #[inline]
             fn eq(&self, other: &Number) -> bool {
-                let self_d = mem::discriminant(&*self);
-                let other_d = mem::discriminant(&*other);
-                if self_d == other_d {
-                    match (&*self, &*other) {
-                        $((&Number::$variant(ref this), &Number::$variant(ref that)) => {
-                            (*this) == (*that)
-                        })+
-                        (Number::GenericInt(ref this), Number::GenericInt(ref that)) => {
-                            (*this) == (*that)
+                match (&*self, &*other) {
+                    $((&Number::$variant(ref this), &Number::$variant(ref that)) => {
+                        (*this) == (*that)
+                    })+
+                    (Number::GenericInt(ref this), Number::GenericInt(ref that)) => {
+                        (*this) == (*that)
+                    }
+                    (Number::BigInt(ref this), Number::BigInt(ref that)) => {
+                        (*this) == (*that)
+                    }
+                    (Number::Rational(ref this), Number::Rational(ref that)) => {
+                        (*this) == (*that)
+                    }
+                    $((Number::GenericInt(ref this), Number::$ivariant(ref that)) => {
+                        if let Ok(i) = $itype::try_from(*this) {
+                            i == (*that)
+                        } else {
+                            false
                         }
-                        _ => {
-                            panic!();
+                    })+
+                    $((Number::$ivariant(ref this), Number::GenericInt(ref that)) => {
+                        if let Ok(i) = $itype::try_from(*that) {
+                            i == (*this)
+                        } else {
+                            false
                         }
+                    })+
+                    // Other mixed variants, including fixed-width ints of
+                    // differing widths. Construction through From keeps
+                    // BigInts outside i128 & Rationals non-integral.
+                    _ => false,
+                }
+            }
+        }
+    };
+}
+
+/// Failure of a checked arithmetic operation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NumberError {
+    Overflow,
+    DivisionByZero,
+}
+
+#[derive(Clone, Copy)]
+enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+}
+
+/// Operands of a binary operation, promoted to a common representation.
+enum Promoted {
+    /// Integers of the same variant as the template.
+    Int(i128, i128, Number),
+    Big(num_bigint::BigInt, num_bigint::BigInt),
+    Rational(BigRational, BigRational),
+    F32(f32, f32),
+    F64(f64, f64),
+}
+
+impl Number {
+    pub fn checked_add(&self, other: &Number) -> Result<Number, NumberError> {
+        self.arithmetic(other, Op::Add)
+    }
+
+    pub fn checked_sub(&self, other: &Number) -> Result<Number, NumberError> {
+        self.arithmetic(other, Op::Sub)
+    }
+
+    pub fn checked_mul(&self, other: &Number) -> Result<Number, NumberError> {
+        self.arithmetic(other, Op::Mul)
+    }
+
+    /// Fixed-width integers use truncating division, while generic
+    /// integers divide exactly into Rationals when needed.
+    pub fn checked_div(&self, other: &Number) -> Result<Number, NumberError> {
+        self.arithmetic(other, Op::Div)
+    }
+
+    /// Floored modulus; the result takes the sign of other.
+    pub fn checked_mod(&self, other: &Number) -> Result<Number, NumberError> {
+        self.arithmetic(other, Op::Mod)
+    }
+
+    /// Literal which parses back into the same variant, unlike Display.
+    pub fn to_literal(&self) -> String {
+        match self {
+            GenericInt(_) | Self::BigInt(_) | Rational(_) => self.to_string(),
+            _ => format!("{:?}", self),
+        }
+    }
+
+    pub fn checked_abs(&self) -> Result<Number, NumberError> {
+        match self {
+            F32(f) => Ok(F32(f.abs())),
+            F64(f) => Ok(F64(f.abs())),
+            GenericInt(i) => Ok(i.checked_abs().map_or_else(
+                || Number::from(num_bigint::BigInt::from(*i).abs()),
+                GenericInt,
+            )),
+            Self::BigInt(i) => Ok(Number::from(i.abs())),
+            Rational(r) => Ok(Rational(r.abs())),
+            _ => self
+                .with_int(self.as_i128().unwrap().abs())
+                .ok_or(NumberError::Overflow),
+        }
+    }
+
+    /// Compare numeric values irrespective of representation.
+    ///
+    /// Unlike PartialEq, (1u8).numeric_cmp(1.0) is Some(Equal).
+    pub fn numeric_cmp(&self, other: &Number) -> Option<Ordering> {
+        if let (Some(a), Some(b)) = (self.as_i128(), other.as_i128()) {
+            return Some(a.cmp(&b));
+        }
+        if self.is_float() || other.is_float() {
+            return self.to_f64().partial_cmp(&other.to_f64());
+        }
+        self.to_rational()?.partial_cmp(&other.to_rational()?)
+    }
+
+    fn is_float(&self) -> bool {
+        matches!(self, F32(_) | F64(_))
+    }
+
+    /// Promote operands along the numeric tower:
+    ///   fixed-width ints -> GenericInt -> BigInt -> Rational -> F32 -> F64
+    ///
+    /// GenericInts adopt the variant of fixed-width operands, while mixed
+    /// fixed-width operands promote to the narrowest variant covering both.
+    fn promote(&self, other: &Number) -> Result<Promoted, NumberError> {
+        if matches!(self, F64(_)) || matches!(other, F64(_)) {
+            return Ok(Promoted::F64(self.to_f64(), other.to_f64()));
+        }
+        if self.is_float() || other.is_float() {
+            return Ok(Promoted::F32(self.to_f64() as f32, other.to_f64() as f32));
+        }
+        if matches!(self, Rational(_)) || matches!(other, Rational(_)) {
+            return Ok(Promoted::Rational(
+                self.to_rational().unwrap(),
+                other.to_rational().unwrap(),
+            ));
+        }
+        if matches!(self, Self::BigInt(_)) || matches!(other, Self::BigInt(_)) {
+            return Ok(Promoted::Big(
+                self.to_rational().unwrap().to_integer(),
+                other.to_rational().unwrap().to_integer(),
+            ));
+        }
+
+        let (a, b) = (self.as_i128().unwrap(), other.as_i128().unwrap());
+        let template = match (self.fixed_width(), other.fixed_width()) {
+            (None, None) => GenericInt(0),
+            (Some(_), None) => self.clone(),
+            (None, Some(_)) => other.clone(),
+            (Some((self_signed, self_bits)), Some((other_signed, other_bits))) => {
+                if self_signed == other_signed {
+                    if other_bits > self_bits {
+                        other.clone()
+                    } else {
+                        self.clone()
                     }
                 } else {
-                    match (&*self, &*other) {
-                        $((Number::GenericInt(ref this), Number::$ivariant(ref that)) => {
-                            if let Ok(i) = $itype::try_from(*this) {
-                                i == (*that)
-                            } else {
-                                false
-                            }
-                        })+
-                        $((Number::$ivariant(ref this), Number::GenericInt(ref that)) => {
-                            if let Ok(i) = $itype::try_from(*that) {
-                                i == (*this)
-                            } else {
-                                false
-                            }
-                        })+
-                        _ => false
+                    let (signed, signed_bits, unsigned_bits) = if self_signed {
+                        (self, self_bits, other_bits)
+                    } else {
+                        (other, other_bits, self_bits)
+                    };
+                    if signed_bits > unsigned_bits {
+                        signed.clone()
+                    } else {
+                        match unsigned_bits {
+                            8 => I16(0),
+                            16 => I32(0),
+                            _ => I64(0),
+                        }
                     }
                 }
             }
+        };
+        if template.with_int(a).is_none() || template.with_int(b).is_none() {
+            return Err(NumberError::Overflow);
         }
-    };
+        Ok(Promoted::Int(a, b, template))
+    }
+
+    fn arithmetic(&self, other: &Number, op: Op) -> Result<Number, NumberError> {
+        match self.promote(other)? {
+            Promoted::Int(a, b, template) => {
+                if matches!(op, Op::Div | Op::Mod) && b == 0 {
+                    return Err(NumberError::DivisionByZero);
+                }
+                let generic = matches!(template, GenericInt(_));
+                if generic && matches!(op, Op::Div) {
+                    return Ok(BigRational::new(a.into(), b.into()).into());
+                }
+
+                let result = match op {
+                    Op::Add => a.checked_add(b),
+                    Op::Sub => a.checked_sub(b),
+                    Op::Mul => a.checked_mul(b),
+                    Op::Div => a.checked_div(b),
+                    Op::Mod => a.checked_rem(b).map(|r| {
+                        if r != 0 && (r < 0) != (b < 0) {
+                            r + b
+                        } else {
+                            r
+                        }
+                    }),
+                };
+                match result.and_then(|i| template.with_int(i)) {
+                    Some(num) => Ok(num),
+                    None if generic => Self::big_arithmetic(a.into(), b.into(), op),
+                    None => Err(NumberError::Overflow),
+                }
+            }
+            Promoted::Big(a, b) => Self::big_arithmetic(a, b, op),
+            Promoted::Rational(a, b) => {
+                if matches!(op, Op::Div | Op::Mod) && b.is_zero() {
+                    return Err(NumberError::DivisionByZero);
+                }
+                let result = match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div => a / b,
+                    Op::Mod => &a - &b * (&a / &b).floor(),
+                };
+                Ok(result.into())
+            }
+            Promoted::F32(a, b) => Ok(F32(Self::float_arithmetic(a, b, op))),
+            Promoted::F64(a, b) => Ok(F64(Self::float_arithmetic(a, b, op))),
+        }
+    }
+
+    fn big_arithmetic(
+        a: num_bigint::BigInt,
+        b: num_bigint::BigInt,
+        op: Op,
+    ) -> Result<Number, NumberError> {
+        if matches!(op, Op::Div | Op::Mod) && b.is_zero() {
+            return Err(NumberError::DivisionByZero);
+        }
+        Ok(match op {
+            Op::Add => (a + b).into(),
+            Op::Sub => (a - b).into(),
+            Op::Mul => (a * b).into(),
+            Op::Div => BigRational::new(a, b).into(),
+            Op::Mod => a.mod_floor(&b).into(),
+        })
+    }
+
+    fn float_arithmetic<F: Float>(a: F, b: F, op: Op) -> F {
+        match op {
+            Op::Add => a + b,
+            Op::Sub => a - b,
+            Op::Mul => a * b,
+            Op::Div => a / b,
+            Op::Mod => a - b * (a / b).floor(),
+        }
+    }
+}
+
+/// Prefers GenericInt when the value fits.
+impl From<num_bigint::BigInt> for Number {
+    fn from(i: num_bigint::BigInt) -> Self {
+        match i.to_i128() {
+            Some(i) => GenericInt(i),
+            None => Number::BigInt(i),
+        }
+    }
+}
+
+/// Prefers integer variants for whole values.
+impl From<BigRational> for Number {
+    fn from(r: BigRational) -> Self {
+        if r.is_integer() {
+            r.to_integer().into()
+        } else {
+            Rational(r)
+        }
+    }
 }
 
 #[derive(Debug)]
@@ -276,6 +486,29 @@ impl str::FromStr for Number {
             return Ok(GenericInt(int));
         }
 
+        if is_integer_literal(s) {
+            if let Ok(big) = s.parse::<num_bigint::BigInt>() {
+                return Ok(big.into());
+            }
+        }
+
+        if let Some((numer, denom)) = s.split_once('/') {
+            if is_integer_literal(numer) && denom.chars().all(|c| c.is_ascii_digit()) {
+                if let (Ok(numer), Ok(denom)) = (
+                    numer.parse::<num_bigint::BigInt>(),
+                    denom.parse::<num_bigint::BigInt>(),
+                ) {
+                    if !denom.is_zero() {
+                        return Ok(BigRational::new(numer, denom).into());
+                    }
+                }
+            }
+        }
+
+        if let Some(num) = parse_typed(s) {
+            return Ok(num);
+        }
+
         let float = s.parse::<f64>();
         if let Ok(f) = float {
             return Ok(F64(f));
@@ -285,6 +518,41 @@ impl str::FromStr for Number {
     }
 }
 
+// Parse fixed-width literals with a type suffix, as written by to_literal.
+fn parse_typed(s: &str) -> Option<Number> {
+    macro_rules! typed {
+        ($($suffix:literal => $variant:ident: $type:ty),+ $(,)?) => {
+            $(
+                if let Some(val) = s.strip_suffix($suffix) {
+                    if let Ok(val) = val.parse::<$type>() {
+                        return Some($variant(val));
+                    }
+                }
+            )+
+        };
+    }
+
+    typed!(
+        "i8" => I8: i8,
+        "i16" => I16: i16,
+        "i32" => I32: i32,
+        "i64" => I64: i64,
+        "isize" => ISize: isize,
+        "u8" => U8: u8,
+        "u16" => U16: u16,
+        "u32" => U32: u32,
+        "u64" => U64: u64,
+        "usize" => USize: usize,
+        "f32" => F32: f32,
+        "f64" => F64: f64,
+    );
+    None
+}
+
+fn is_integer_literal(s: &str) -> bool {
+    let digits = s.strip_prefix('-').unwrap_or(s);
+    !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit())
+}
 
 impl fmt::Display for Number {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -302,6 +570,8 @@ impl fmt::Display for Number {
             F32(val) => write!(f, "{}", val),
             F64(val) => write!(f, "{}", val),
             GenericInt(val) => write!(f, "{}", val),
+            BigInt(val) => write!(f, "{}", val),
+            Rational(val) => write!(f, "{}", val),
         }
     }
 }
@@ -322,6 +592,8 @@ impl fmt::Debug for Number {
             F32(val) => write!(f, "{}f32", val),
             F64(val) => write!(f, "{}f64", val),
             GenericInt(val) => write!(f, "{}_", val),
+            BigInt(val) => write!(f, "{}_", val),
+            Rational(val) => write!(f, "{}", val),
         }
     }
 }
@@ -338,3 +610,7 @@ impl_try_from!(Number;
 );
 
 use generate_number;
+
+#[cfg(test)]
+#[path = "./number_test.rs"]
+mod number_test;
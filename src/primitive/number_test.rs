@@ -0,0 +1,143 @@
+use std::mem;
+
+use super::*;
+
+fn parse(s: &str) -> Number {
+    s.parse().unwrap()
+}
+
+#[test]
+fn promotion() {
+    // GenericInts adopt fixed-width variants.
+    let res = Number::U8(200).checked_add(&Number::generic(50)).unwrap();
+    assert!(matches!(res, U8(250)));
+
+    // Mixed fixed-width ints promote to cover both.
+    let res = Number::U8(200).checked_add(&Number::I8(-1)).unwrap();
+    assert!(matches!(res, I16(199)));
+    let res = Number::I32(1).checked_add(&Number::I64(1)).unwrap();
+    assert!(matches!(res, I64(2)));
+
+    // Floats dominate.
+    let res = Number::generic(1).checked_add(&Number::F64(0.5)).unwrap();
+    assert!(matches!(res, F64(f) if f == 1.5));
+    let res = parse("1/2").checked_mul(&Number::F32(3.)).unwrap();
+    assert!(matches!(res, F32(f) if f == 1.5));
+}
+
+#[test]
+fn overflow() {
+    assert_eq!(
+        Number::U8(255).checked_add(&Number::U8(1)),
+        Err(NumberError::Overflow)
+    );
+    assert_eq!(Number::I8(-128).checked_abs(), Err(NumberError::Overflow));
+    assert_eq!(
+        Number::U8(1).checked_add(&Number::generic(256)),
+        Err(NumberError::Overflow)
+    );
+
+    // GenericInts overflow into BigInts and back.
+    let max = GenericInt(i128::MAX);
+    let big = max.checked_add(&Number::generic(1)).unwrap();
+    assert!(matches!(big, BigInt(_)));
+    assert_eq!(big.to_string(), "170141183460469231731687303715884105728");
+    assert_eq!(big.checked_sub(&Number::generic(1)).unwrap(), max);
+}
+
+#[test]
+fn division() {
+    assert_eq!(
+        Number::generic(1).checked_div(&Number::generic(0)),
+        Err(NumberError::DivisionByZero)
+    );
+    assert_eq!(
+        Number::I32(7).checked_div(&Number::I32(2)).unwrap(),
+        Number::I32(3)
+    );
+
+    // GenericInts divide exactly.
+    let half = Number::generic(1).checked_div(&Number::generic(2)).unwrap();
+    assert_eq!(half, parse("1/2"));
+    assert_eq!(half.checked_add(&parse("3/2")).unwrap(), Number::generic(2));
+}
+
+#[test]
+fn mixed_equality() {
+    assert_eq!(Number::generic(3), Number::I8(3));
+    assert_eq!(Number::U64(3), Number::generic(3));
+    assert_ne!(Number::I8(3), Number::I16(3));
+    assert_ne!(parse("1/2"), Number::F64(0.5));
+    assert_ne!(
+        parse("170141183460469231731687303715884105728"),
+        Number::generic(1)
+    );
+}
+
+#[test]
+fn modulo() {
+    let m = |a: i32, b: i32| Number::generic(a).checked_mod(&Number::generic(b)).unwrap();
+    assert_eq!(m(7, 3), Number::generic(1));
+    assert_eq!(m(-7, 3), Number::generic(2));
+    assert_eq!(m(7, -3), Number::generic(-2));
+    assert_eq!(
+        Number::generic(1).checked_mod(&Number::generic(0)),
+        Err(NumberError::DivisionByZero)
+    );
+}
+
+#[test]
+fn comparison() {
+    assert_eq!(
+        Number::U8(1).numeric_cmp(&Number::F64(1.)),
+        Some(Ordering::Equal)
+    );
+    assert_eq!(
+        parse("1/3").numeric_cmp(&parse("1/2")),
+        Some(Ordering::Less)
+    );
+    assert_eq!(
+        parse("100000000000000000000000000000000000000000").numeric_cmp(&Number::I8(-1)),
+        Some(Ordering::Greater)
+    );
+    assert_eq!(Number::F64(f64::NAN).numeric_cmp(&Number::F64(1.)), None);
+}
+
+#[test]
+fn parsing() {
+    assert!(matches!(parse("12"), GenericInt(12)));
+    assert!(matches!(parse("4/2"), GenericInt(2)));
+    assert_eq!(parse("-2/4").to_string(), "-1/2");
+    assert!(matches!(
+        parse("340282366920938463463374607431768211456"),
+        BigInt(_)
+    ));
+    assert!("1/0".parse::<Number>().is_err());
+    assert!("1_000".parse::<Number>().is_err());
+
+    assert!(matches!(parse("4u8"), U8(4)));
+    assert!(matches!(parse("-3isize"), ISize(-3)));
+    assert!(matches!(parse("2.5f32"), F32(f) if f == 2.5));
+    assert!(matches!(parse("2f64"), F64(f) if f == 2.));
+    assert!("256u8".parse::<Number>().is_err());
+    assert!("u8".parse::<Number>().is_err());
+}
+
+#[test]
+fn literals() {
+    for num in [
+        U8(4),
+        I64(-4),
+        USize(0),
+        F32(0.1),
+        F64(2.),
+        F64(f64::INFINITY),
+        GenericInt(7),
+        parse("340282366920938463463374607431768211456"),
+        parse("-1/3"),
+    ] {
+        let parsed = parse(&num.to_literal());
+        assert_eq!(mem::discriminant(&parsed), mem::discriminant(&num));
+        assert_eq!(parsed, num);
+    }
+}
@@ -17,6 +17,9 @@ pub enum Procedure {
     #[serde(rename = "fexpr")]
     InterpreterAbstraction(Vec<Node>, Node),
 
+    #[serde(rename = "macro")]
+    MacroAbstraction(Vec<Node>, Node),
+
     #[serde(rename = "progn")]
     Sequence(Vec<Node>),
 
@@ -15,18 +15,52 @@ impl LangString {
         Self(s.to_string())
     }
 
-    pub fn unescape_char(c: char) -> char {
-        match c {
+    /// Decode the escape sequence at the start of seq (i.e. following a `\`),
+    /// returning the char and the byte length of the sequence.
+    ///
+    /// Supports `\t`, `\r`, `\n`, `\0`, `\xNN` (up to 7F) and `\u{N..}` (up to
+    /// 6 hex digits); any other char escapes to itself (e.g. `\\` and `\"`).
+    pub fn unescape(seq: &str) -> Option<(char, usize)> {
+        fn hex(digits: &str) -> Option<u32> {
+            if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+                return None;
+            }
+            u32::from_str_radix(digits, 16).ok()
+        }
+
+        let c = seq.chars().next()?;
+        let decoded = match c {
             't' => '\t',
             'r' => '\r',
             'n' => '\n',
-            // TODO(func) decode escape_unicodes for symmetry with escape_char.
+            '0' => '\0',
+            'x' => {
+                let n = hex(seq.get(1..3)?).filter(|n| *n <= 0x7F)?;
+                return Some((char::from_u32(n)?, 3));
+            }
+            'u' => {
+                let digits = seq.strip_prefix("u{")?;
+                let end = digits.find('}').filter(|end| *end <= 6)?;
+                let c = char::from_u32(hex(&digits[..end])?)?;
+                return Some((c, end + 3));
+            }
             _ => c,
-        }
+        };
+        Some((decoded, c.len_utf8()))
     }
 
+    /// Escape only what is needed for unescape to restore c, leaving other
+    /// chars (including non-ASCII) as-is.
     pub fn escape_char(c: char) -> String {
-        c.escape_default().to_string()
+        match c {
+            '\t' => "\\t".to_string(),
+            '\r' => "\\r".to_string(),
+            '\n' => "\\n".to_string(),
+            '\0' => "\\0".to_string(),
+            '\\' | '"' => format!("\\{}", c),
+            _ if c.is_control() => format!("\\u{{{:x}}}", c as u32),
+            _ => c.to_string(),
+        }
     }
 
     pub fn as_str(&self) -> &str {
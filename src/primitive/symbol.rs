@@ -4,30 +4,44 @@ use std::fmt;
 
 use serde::{Deserialize, Serialize};
 
+use super::symbol_policies::SymbolGrammar;
 use super::Primitive;
 use crate::sexp::{HeapSexp, Sexp};
 
 
 /// String which can be used as an identifier (amlang designator).
 ///
-/// Currently this means only alphabetic characters and underscore.
+/// Which strings qualify is up to the SymbolPolicy used to create it, which
+/// are generally based on a SymbolGrammar.
 #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
 pub struct Symbol(String);
 
 pub trait ToSymbol {
+    /// Symbol under policy, as per the standard SymbolGrammar.
     fn to_symbol<Info, P>(&self, policy: P) -> Result<Symbol, SymbolError>
     where
-        P: Fn(&str) -> Result<Info, SymbolError>;
+        P: Fn(&str, &SymbolGrammar) -> Result<Info, SymbolError>,
+    {
+        self.to_symbol_with(policy, SymbolGrammar::standard())
+    }
+
+    fn to_symbol_with<Info, P>(
+        &self,
+        policy: P,
+        grammar: &SymbolGrammar,
+    ) -> Result<Symbol, SymbolError>
+    where
+        P: Fn(&str, &SymbolGrammar) -> Result<Info, SymbolError>;
 
     fn to_symbol_or_panic<Info, P>(&self, policy: P) -> Symbol
     where
-        P: Fn(&str) -> Result<Info, SymbolError>,
+        P: Fn(&str, &SymbolGrammar) -> Result<Info, SymbolError>,
     {
         self.to_symbol(policy).unwrap()
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum SymbolError {
     InvalidIdentifier(String),
     DunderPrefix(String),
@@ -36,17 +50,21 @@ pub enum SymbolError {
 }
 
 impl Symbol {
-    pub fn try_policy<S, Info, P>(sym: S, policy: P) -> Result<(Symbol, Info), SymbolError>
+    pub fn try_policy<S, Info, P>(
+        sym: S,
+        policy: P,
+        grammar: &SymbolGrammar,
+    ) -> Result<(Symbol, Info), SymbolError>
     where
         S: AsRef<str>,
-        P: Fn(&str) -> Result<Info, SymbolError>,
+        P: Fn(&str, &SymbolGrammar) -> Result<Info, SymbolError>,
     {
         let s = sym.as_ref();
         if s.len() == 0 {
             return Err(SymbolError::EmptyString);
         }
 
-        let info = policy(s)?;
+        let info = policy(s, grammar)?;
         Ok((Symbol(s.to_string()), info))
     }
 
@@ -57,21 +75,29 @@ impl Symbol {
 
 
 impl<S: AsRef<str>> ToSymbol for S {
-    fn to_symbol<Info, P>(&self, policy: P) -> Result<Symbol, SymbolError>
+    fn to_symbol_with<Info, P>(
+        &self,
+        policy: P,
+        grammar: &SymbolGrammar,
+    ) -> Result<Symbol, SymbolError>
     where
-        P: Fn(&str) -> Result<Info, SymbolError>,
+        P: Fn(&str, &SymbolGrammar) -> Result<Info, SymbolError>,
     {
-        let (sym, _info) = Symbol::try_policy(self, policy)?;
+        let (sym, _info) = Symbol::try_policy(self, policy, grammar)?;
         Ok(sym)
     }
 }
 
 impl ToSymbol for Symbol {
-    fn to_symbol<Info, P>(&self, policy: P) -> Result<Symbol, SymbolError>
+    fn to_symbol_with<Info, P>(
+        &self,
+        policy: P,
+        grammar: &SymbolGrammar,
+    ) -> Result<Symbol, SymbolError>
     where
-        P: Fn(&str) -> Result<Info, SymbolError>,
+        P: Fn(&str, &SymbolGrammar) -> Result<Info, SymbolError>,
     {
-        let (sym, _info) = Symbol::try_policy(self.as_str(), policy)?;
+        let (sym, _info) = Symbol::try_policy(self.as_str(), policy, grammar)?;
         Ok(sym)
     }
 }
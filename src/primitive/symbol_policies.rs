@@ -3,28 +3,77 @@ use regex::Regex;
 
 use crate::env::local_node::{LocalId, LocalNode};
 use crate::primitive::symbol::SymbolError;
+use crate::primitive::Number;
 
 
-/// De-facto specification of amlang identifier format.
-fn is_amlang_identifier(s: &str) -> bool {
-    match s {
-        "+" | "-" | "*" | "/" | "$" => true,
-        _ if s
-            .chars()
-            .all(|c| c.is_alphabetic() || c == '_' || c == '-' || c == '*' || c == '!') =>
-        {
-            true
+/// Data-driven specification of the amlang identifier format, which symbol
+/// policies check identifiers against, as does anything which needs to find
+/// identifiers in text (e.g. completion).
+///
+/// Configured per Tokenizer (see Tokenizer::with_grammar) and per Agent (see
+/// Agent::set_symbol_grammar), defaulting to SymbolGrammar::standard().
+///
+/// Loosely follows Scheme: identifiers start with a letter or special
+/// initial, followed by any mix of those, digits, and special subsequents.
+/// Strings which read as numbers are never identifiers.
+#[derive(Clone, Debug)]
+pub struct SymbolGrammar {
+    /// Chars besides letters allowed anywhere in an identifier.
+    pub special_initial: String,
+    /// Chars besides letters, digits, and special_initial allowed after the
+    /// first char.
+    pub special_subsequent: String,
+    /// Whether non-ASCII chars other than whitespace and control chars (e.g.
+    /// operators like ≤) are treated as letters.
+    pub allow_unicode: bool,
+}
+
+impl SymbolGrammar {
+    /// The default grammar, used where none is configured.
+    pub fn standard() -> &'static SymbolGrammar {
+        lazy_static! {
+            static ref STANDARD: SymbolGrammar = SymbolGrammar::default();
+        }
+        &STANDARD
+    }
+
+    pub fn is_initial(&self, c: char) -> bool {
+        c.is_alphabetic()
+            || self.special_initial.contains(c)
+            || (self.allow_unicode && !c.is_ascii() && !c.is_whitespace() && !c.is_control())
+    }
+
+    pub fn is_subsequent(&self, c: char) -> bool {
+        self.is_initial(c) || c.is_ascii_digit() || self.special_subsequent.contains(c)
+    }
+
+    pub fn is_identifier(&self, s: &str) -> bool {
+        let mut chars = s.chars();
+        match chars.next() {
+            Some(c) if self.is_initial(c) => {}
+            _ => return false,
+        }
+        chars.all(|c| self.is_subsequent(c)) && s.parse::<Number>().is_err()
+    }
+}
+
+impl Default for SymbolGrammar {
+    fn default() -> Self {
+        Self {
+            // ^ is reserved for node specs (e.g. ^3) in env files.
+            special_initial: "!$%&*/:<=>?_~+-".to_string(),
+            special_subsequent: ".@^".to_string(),
+            allow_unicode: true,
         }
-        _ => false,
     }
 }
 
 
-pub type SymbolPolicy<SymbolInfo> = fn(&str) -> Result<SymbolInfo, SymbolError>;
+pub type SymbolPolicy<SymbolInfo> = fn(&str, &SymbolGrammar) -> Result<SymbolInfo, SymbolError>;
 
 /// Only accepts non-dunder identifiers.
-pub fn policy_base(s: &str) -> Result<(), SymbolError> {
-    if is_amlang_identifier(s) {
+pub fn policy_base(s: &str, grammar: &SymbolGrammar) -> Result<(), SymbolError> {
+    if grammar.is_identifier(s) {
         if s.chars().take(2).collect::<String>() == "__" {
             Err(SymbolError::DunderPrefix(s.to_string()))
         } else {
@@ -35,7 +84,7 @@ pub fn policy_base(s: &str) -> Result<(), SymbolError> {
     }
 }
 
-pub fn policy_uuid(s: &str) -> Result<(), SymbolError> {
+pub fn policy_uuid(s: &str, _grammar: &SymbolGrammar) -> Result<(), SymbolError> {
     if s.chars().all(|c| c.is_ascii_hexdigit() || c == '-') {
         Ok(())
     } else {
@@ -53,8 +102,8 @@ pub enum AdminSymbolInfo {
 }
 
 /// Accepts all identifiers.
-pub fn policy_admin(s: &str) -> Result<AdminSymbolInfo, SymbolError> {
-    if is_amlang_identifier(s) {
+pub fn policy_admin(s: &str, grammar: &SymbolGrammar) -> Result<AdminSymbolInfo, SymbolError> {
+    if grammar.is_identifier(s) {
         Ok(AdminSymbolInfo::Identifier)
     } else {
         Err(SymbolError::InvalidIdentifier(s.to_string()))
@@ -62,9 +111,9 @@ pub fn policy_admin(s: &str) -> Result<AdminSymbolInfo, SymbolError> {
 }
 
 /// Accepts all identifiers, as well as local & global nodes/triples.
-pub fn policy_env_serde(s: &str) -> Result<AdminSymbolInfo, SymbolError> {
+pub fn policy_env_serde(s: &str, grammar: &SymbolGrammar) -> Result<AdminSymbolInfo, SymbolError> {
     match s {
-        _ if is_amlang_identifier(s) => Ok(AdminSymbolInfo::Identifier),
+        _ if grammar.is_identifier(s) => Ok(AdminSymbolInfo::Identifier),
         _ if s
             .chars()
             .all(|c| c.is_ascii_digit() || c == '^' || c == 't') =>
@@ -7,9 +7,10 @@ use std::convert::TryFrom;
 
 use serde::{Deserialize, Serialize};
 
-use super::{Node, Primitive, Symbol};
+use super::symbol_policies::policy_base;
+use super::{Node, Primitive, Symbol, ToSymbol};
 use crate::env::LocalNode;
-use crate::sexp::{HeapSexp, Sexp};
+use crate::sexp::{ConsList, HeapSexp, Sexp};
 
 
 pub type SymNodeTable = AmlangTable<Symbol, Node>;
@@ -53,6 +54,14 @@ pub trait Table<K: Ord, V: Clone> {
         self.as_map_mut().insert(k, v)
     }
 
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + Eq + ?Sized,
+    {
+        self.as_map_mut().remove(k)
+    }
+
     fn entry(&mut self, k: K) -> Entry<K, V> {
         self.as_map_mut().entry(k)
     }
@@ -77,13 +86,91 @@ impl<K: Ord, V> Default for AmlangTable<K, V> {
 }
 
 
+// Tables are written in the same form they are constructed with in Amlang:
+//   (table-sym-node (key node) ...)
+//   (table-sym-sexp (key value) ...)
+//   (table-lnode env-node (node node) ...)
+impl SymNodeTable {
+    pub const DISCRIMINATOR: &'static str = "table-sym-node";
+
+    pub fn to_form(&self) -> Sexp {
+        table_form(Self::DISCRIMINATOR, None, self.map.iter(), |k, v| {
+            list!(k.clone(), *v)
+        })
+    }
+}
+
+impl SymSexpTable {
+    pub const DISCRIMINATOR: &'static str = "table-sym-sexp";
+
+    /// Form where each value is first passed through wrap_value, allowing
+    /// callers to quote values as needed by the reader of the form.
+    pub fn to_form_with<F: Fn(&Sexp) -> Sexp>(&self, wrap_value: F) -> Sexp {
+        table_form(Self::DISCRIMINATOR, None, self.map.iter(), |k, v| {
+            list!(k.clone(), wrap_value(v))
+        })
+    }
+
+    pub fn to_form(&self) -> Sexp {
+        self.to_form_with(quote_value)
+    }
+}
+
 impl LocalNodeTable {
+    pub const DISCRIMINATOR: &'static str = "table-lnode";
+
     pub fn in_env(env: LocalNode) -> Self {
         Self {
             map: Default::default(),
             env,
         }
     }
+
+    pub fn env(&self) -> LocalNode {
+        self.env
+    }
+
+    pub fn to_form(&self) -> Sexp {
+        let env_node = Node::new(LocalNode::default(), self.env);
+        table_form(
+            Self::DISCRIMINATOR,
+            Some(env_node.into()),
+            self.map.iter(),
+            |k, v| list!(Node::new(self.env, *k), Node::new(self.env, *v)),
+        )
+    }
+}
+
+/// Quote values which do not evaluate to themselves in Amlang.
+pub fn quote_value(value: &Sexp) -> Sexp {
+    match value {
+        Sexp::Primitive(Primitive::Number(_))
+        | Sexp::Primitive(Primitive::LangString(_))
+        | Sexp::Primitive(Primitive::Char(_))
+        | Sexp::Primitive(Primitive::Bool(_)) => value.clone(),
+        _ => list!("quote".to_symbol_or_panic(policy_base), value.clone()),
+    }
+}
+
+fn table_form<'a, K: 'a, V: 'a, I, F>(
+    discriminator: &str,
+    header: Option<Sexp>,
+    entries: I,
+    entry_form: F,
+) -> Sexp
+where
+    I: Iterator<Item = (&'a K, &'a V)>,
+    F: Fn(&K, &V) -> Sexp,
+{
+    let mut form = ConsList::new();
+    form.append(discriminator.to_symbol_or_panic(policy_base));
+    if let Some(header) = header {
+        form.append(header);
+    }
+    for (k, v) in entries {
+        form.append(entry_form(k, v));
+    }
+    form.release()
 }
 
 impl Table<LocalNode, LocalNode> for LocalNodeTable {
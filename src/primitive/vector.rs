@@ -11,6 +11,43 @@ use crate::sexp::{HeapSexp, Sexp};
 pub struct Vector(Vec<Sexp>);
 
 
+impl Vector {
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&Sexp> {
+        self.0.get(index)
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut Sexp> {
+        self.0.get_mut(index)
+    }
+
+    pub fn as_slice(&self) -> &[Sexp] {
+        &self.0
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Sexp> {
+        self.0.iter()
+    }
+
+    pub fn into_vec(self) -> Vec<Sexp> {
+        self.0
+    }
+}
+
+impl From<Vec<Sexp>> for Vector {
+    fn from(elements: Vec<Sexp>) -> Self {
+        Self(elements)
+    }
+}
+
+
 impl fmt::Display for Vector {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "[")?;
@@ -0,0 +1,251 @@
+//! Ready-to-use Agent, bootstrapped from an env directory or from memory.
+//!
+//! Wraps EnvManager bootstrapping & Agent setup, so that embedders needn't
+//! know which envs Amlang uses internally:
+//!
+//!   let mut runtime = Runtime::builder().env_dir("/path/to/envs").build()?;
+//!   runtime.agent_mut().interpret(sexp)?;
+//!
+//! Envs are saved to the env dir when the Runtime is dropped. Without one,
+//! envs live in memory. Either way, upstream envs are those embedded in the
+//! crate, unless running from the source tree through root_run.
+//!
+//! Unlike init, this leaves the current dir alone, so independent Runtimes
+//! can coexist within a process.
+
+use log::warn;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use crate::agent::env_policy::{EnvPolicy, SimplePolicy};
+use crate::agent::env_sources::EnvSources;
+use crate::agent::{
+    Agent, AmlangContext, AmlangInterpreter, Context, EnvManager, HistoryContext, NullInterpreter,
+    VmInterpreter,
+};
+use crate::env::LocalNode;
+use crate::error::Error;
+use crate::primitive::Node;
+use crate::span::SourceMap;
+
+
+// Envs which are upstream Amlang's, rather than the runtime's.
+const SAVE_BLACKLIST: [&str; 2] = ["lang.env", "error.env"];
+
+/// Agent positioned in the working env, along with the EnvManager its envs
+/// came from.
+pub struct Runtime<Policy: EnvPolicy = SimplePolicy> {
+    // Only None once taken by into_parts.
+    parts: Option<(Agent, EnvManager<Policy>)>,
+    root: Option<PathBuf>,
+    save_on_drop: bool,
+}
+
+pub struct RuntimeBuilder<Policy: EnvPolicy = SimplePolicy> {
+    sources: Option<EnvSources>,
+    reset: bool,
+    interpreter: BaseInterpreter,
+    save_on_drop: bool,
+    policy: PhantomData<Policy>,
+}
+
+/// Interpreter at the base of the Agent's stack, below AmlangInterpreter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BaseInterpreter {
+    /// Execute Procedures compiled by AmlangInterpreter.
+    Amlang,
+    /// Leave compiled Procedures to be executed manually.
+    Null,
+}
+
+
+impl Runtime {
+    pub fn builder() -> RuntimeBuilder {
+        RuntimeBuilder::default()
+    }
+}
+
+impl<Policy: EnvPolicy> Runtime<Policy> {
+    pub fn agent(&self) -> &Agent {
+        &self.parts().0
+    }
+    pub fn agent_mut(&mut self) -> &mut Agent {
+        &mut self.parts_mut().0
+    }
+
+    pub fn manager(&self) -> &EnvManager<Policy> {
+        &self.parts().1
+    }
+    pub fn manager_mut(&mut self) -> &mut EnvManager<Policy> {
+        &mut self.parts_mut().1
+    }
+
+    /// Borrow Agent & EnvManager at once, e.g. for REPL commands.
+    pub fn split_mut(&mut self) -> (&mut Agent, &mut EnvManager<Policy>) {
+        let (agent, manager) = self.parts_mut();
+        (agent, manager)
+    }
+
+    /// Take Agent & EnvManager, without saving.
+    pub fn into_parts(mut self) -> (Agent, EnvManager<Policy>) {
+        self.parts.take().unwrap()
+    }
+
+    /// Directory envs are saved to, or None if bootstrapped from memory.
+    pub fn root(&self) -> Option<&Path> {
+        self.root.as_deref()
+    }
+
+    /// Serialize all envs but those of upstream Amlang to the root.
+    pub fn save(&mut self) -> Result<(), Error> {
+        let root = match &self.root {
+            Some(root) => root.clone(),
+            None => return Err(Error::adhoc("RuntimeErr", "No env root to save to")),
+        };
+        self.manager_mut()
+            .serialize_full(root, SAVE_BLACKLIST.into())?;
+        Ok(())
+    }
+
+    fn parts(&self) -> &(Agent, EnvManager<Policy>) {
+        self.parts.as_ref().unwrap()
+    }
+    fn parts_mut(&mut self) -> &mut (Agent, EnvManager<Policy>) {
+        self.parts.as_mut().unwrap()
+    }
+}
+
+impl<Policy: EnvPolicy> Drop for Runtime<Policy> {
+    fn drop(&mut self) {
+        // In-memory Runtimes have nowhere to save to.
+        if !self.save_on_drop || self.parts.is_none() || self.root.is_none() {
+            return;
+        }
+        if let Err(err) = self.save() {
+            warn!("Failed to save envs: {}", err);
+        }
+    }
+}
+
+
+impl<Policy: EnvPolicy> RuntimeBuilder<Policy> {
+    /// Use envs in dir, rather than keeping them in memory.
+    ///
+    /// Lacking a meta.env, dir is bootstrapped with Amlang's.
+    pub fn env_dir<P: AsRef<Path>>(self, dir: P) -> Self {
+        self.sources(EnvSources::at_root(dir))
+    }
+
+    /// Use upstream Amlang's envs/ directory in the source tree, reading
+    /// upstream envs from it as well. Meant for developing Amlang itself.
+    pub fn root_run(self) -> Self {
+        self.sources(EnvSources::root_run())
+    }
+
+    /// Bootstrap from sources, which default to EnvSources::in_memory().
+    /// Envs are saved to the root of sources, if any.
+    pub fn sources(mut self, sources: EnvSources) -> Self {
+        self.sources = Some(sources);
+        self
+    }
+
+    /// Clear the env dir of saved state before bootstrapping.
+    pub fn reset(mut self, reset: bool) -> Self {
+        self.reset = reset;
+        self
+    }
+
+    pub fn interpreter(mut self, interpreter: BaseInterpreter) -> Self {
+        self.interpreter = interpreter;
+        self
+    }
+
+    /// Save when the Runtime is dropped, as by default; see Runtime::save.
+    pub fn save_on_drop(mut self, save: bool) -> Self {
+        self.save_on_drop = save;
+        self
+    }
+
+    /// Bootstrap envs & prepare Agent.
+    pub fn build(self) -> Result<Runtime<Policy>, Error> {
+        let sources = match self.sources {
+            Some(sources) => sources,
+            None => EnvSources::in_memory(),
+        };
+        let root = sources.root().map(Path::to_path_buf);
+        // Resetting would clear upstream Amlang's own envs.
+        if self.reset && sources.upstream_root() {
+            return Err(Error::adhoc(
+                "RuntimeErr",
+                "Cannot reset upstream Amlang's envs",
+            ));
+        }
+        if let (Some(root), true) = (&root, self.reset) {
+            if let Err(err) = crate::reset_state(root) {
+                return Err(err.wrap_adhoc("RuntimeErr", "Resetting state failed"));
+            }
+        }
+
+        let mut manager = EnvManager::<Policy>::bootstrap_from(&sources)?;
+        let pre_agent = manager.agent_mut();
+        let find_env = |agent: &Agent, name: &str| match agent.find_env(name) {
+            Some(env) => Ok(env),
+            None => Err(Error::adhoc(
+                "RuntimeErr",
+                format!("{} missing from meta env", name),
+            )),
+        };
+        let lang_env = find_env(pre_agent, "lang.env")?;
+        let history_env = find_env(pre_agent, "history.env")?;
+        let impl_env = find_env(pre_agent, "impl.env")?;
+        let working_env = find_env(pre_agent, "working.env")?;
+
+        let amlang_context =
+            AmlangContext::load(Node::new(lang_env, LocalNode::default()), pre_agent)?;
+        let history_context =
+            HistoryContext::load(Node::new(history_env, LocalNode::default()), pre_agent)?;
+        pre_agent.set_history_context(history_context);
+
+        let mut agent = match self.interpreter {
+            BaseInterpreter::Amlang => pre_agent.fork(VmInterpreter::new(
+                history_env,
+                impl_env,
+                amlang_context.clone(),
+            )),
+            BaseInterpreter::Null => pre_agent.fork(NullInterpreter::default()),
+        };
+        agent.set_eval(move |frame| {
+            let mut interpreter = AmlangInterpreter::new(impl_env, amlang_context.clone());
+            if let Some(frame) = frame {
+                interpreter.eval_state.push(frame);
+            }
+            Ok(Box::new(interpreter))
+        })?;
+        agent.set_source_map(SourceMap::shared());
+
+        // Designate Amlang nodes first, then those of the working env.
+        agent
+            .designation_chain_mut()
+            .push_front(Node::new(lang_env, LocalNode::default()));
+        let pos = agent.jump_env(working_env);
+        agent.designation_chain_mut().push_back(pos);
+
+        Ok(Runtime {
+            parts: Some((agent, manager)),
+            root,
+            save_on_drop: self.save_on_drop,
+        })
+    }
+}
+
+impl<Policy: EnvPolicy> Default for RuntimeBuilder<Policy> {
+    fn default() -> Self {
+        Self {
+            sources: None,
+            reset: false,
+            interpreter: BaseInterpreter::Amlang,
+            save_on_drop: true,
+            policy: PhantomData,
+        }
+    }
+}
@@ -77,7 +77,7 @@ impl Sexp {
 
         let tail = match original {
             Sexp::Primitive(p) => list!(p),
-            tail @ _ => tail,
+            tail => tail,
         };
         if let Sexp::Cons(c) = self {
             c.set_cdr(tail.into());
@@ -160,9 +160,17 @@ impl Sexp {
     }
 
     pub fn parse_with<I>(s: &str, policy: SymbolPolicy<I>) -> Result<Self, Error> {
+        Self::parse_with_grammar(s, policy, SymbolGrammar::standard())
+    }
+
+    pub fn parse_with_grammar<I>(
+        s: &str,
+        policy: SymbolPolicy<I>,
+        grammar: &SymbolGrammar,
+    ) -> Result<Self, Error> {
         let input = StringReader::new(s);
         let mut stream = pull_transform!(input
-                       =>> Tokenizer::new(policy)
+                       =>> Tokenizer::new(policy).with_grammar(grammar.clone())
                        =>. Parser::new());
 
         match stream.next() {
@@ -172,6 +180,7 @@ impl Sexp {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn write<W, F, P, S>(
         &self,
         w: &mut W,
@@ -203,13 +212,11 @@ impl Sexp {
                         return write!(w, "(..)");
                     }
                 }
-                if let Ok(symbol) = <&Symbol>::try_from(val) {
-                    if symbol.as_str() == "quote" {
-                        outer_quote = true;
-                        write!(w, "'")?;
-                        pos += 1;
-                        continue;
-                    }
+                if let Some(prefix) = self.quote_prefix() {
+                    outer_quote = true;
+                    write!(w, "{}", prefix)?;
+                    pos += 1;
+                    continue;
                 }
                 write_paren(w, "(", depth)?;
             }
@@ -250,6 +257,26 @@ impl Sexp {
         Ok(())
     }
 
+    /// Abbreviation of (quote x) and the like, e.g. '. Other lists headed by
+    /// these symbols must be written out in full.
+    pub fn quote_prefix(&self) -> Option<&'static str> {
+        let (head, rest) = match self {
+            Sexp::Cons(cons) => (cons.car()?, cons.cdr()?),
+            _ => return None,
+        };
+        match rest {
+            Sexp::Cons(rest) if rest.car().is_some() && rest.cdr().is_none() => {}
+            _ => return None,
+        }
+        match <&Symbol>::try_from(head).ok()?.as_str() {
+            "quote" => Some("'"),
+            "quasiquote" => Some("`"),
+            "unquote" => Some(","),
+            "unquote-splicing" => Some(",@"),
+            _ => None,
+        }
+    }
+
     pub fn to_string_truncated(&self) -> String {
         // Any list longer than this will simply be suffixed with "..." after these
         // many elements.
@@ -1,3 +1,4 @@
+use crate::agent::lang_error::{ExpectedCount, LangError};
 use crate::primitive::{LangString, Node, Number, Symbol};
 use crate::sexp::{Cons, HeapSexp, Sexp};
 
@@ -69,9 +70,10 @@ fn vec_break_no_remainder() {
 fn wrong_type() {
     let original: Sexp = "(lambda (a b) ing)".parse().unwrap();
     if let Err(err) = break_sexp!(original => (Node, Sexp, Symbol)) {
-        let (_, kind, _) =
-            break_sexp!(err.kind().reify() => (LangString, LangString; remainder)).unwrap();
-        assert_eq!(kind, "InvalidArgument".into());
+        assert!(matches!(
+            err.kind().as_any().downcast_ref::<LangError>(),
+            Some(LangError::InvalidArgument { .. })
+        ));
     } else {
         panic!();
     }
@@ -81,9 +83,13 @@ fn wrong_type() {
 fn extra_arguments() {
     let original: Sexp = "(test ing 1 2)".parse().unwrap();
     if let Err(err) = break_sexp!(original => (Symbol, Symbol)) {
-        let (_, kind, _) =
-            break_sexp!(err.kind().reify() => (LangString, LangString; remainder)).unwrap();
-        assert_eq!(kind, "WrongArgumentCount".into());
+        assert_eq!(
+            err.kind().as_any().downcast_ref::<LangError>(),
+            Some(&LangError::WrongArgumentCount {
+                given: 4,
+                expected: ExpectedCount::Exactly(2),
+            })
+        );
     } else {
         panic!();
     }
@@ -93,9 +99,13 @@ fn extra_arguments() {
 fn missing_arguments() {
     let original: Sexp = "(test)".parse().unwrap();
     if let Err(err) = break_sexp!(original => (Symbol, Symbol, Symbol)) {
-        let (_, kind, _) =
-            break_sexp!(err.kind().reify() => (LangString, LangString; remainder)).unwrap();
-        assert_eq!(kind, "WrongArgumentCount".into());
+        assert_eq!(
+            err.kind().as_any().downcast_ref::<LangError>(),
+            Some(&LangError::WrongArgumentCount {
+                given: 1,
+                expected: ExpectedCount::Exactly(3),
+            })
+        );
     } else {
         panic!();
     }
@@ -126,7 +136,7 @@ fn multi_type_list() {
 fn simple_list_vars() {
     let a = Number::generic(1);
     let b = Number::generic(2);
-    let l = list!(a, b);
+    let l = list!(a.clone(), b.clone());
     let (aa, bb) = break_sexp!(l => (Number, Number)).unwrap();
     assert_eq!(a, aa);
     assert_eq!(b, bb);
@@ -137,7 +147,7 @@ fn nested_list_vars() {
     let a = Number::generic(1);
     let b = Number::generic(2);
     let c = Number::generic(3);
-    let l = list!(a, (b, c));
+    let l = list!(a.clone(), (b.clone(), c.clone()));
     let (aa, sub) = break_sexp!(l => (Number, HeapSexp)).unwrap();
     let (bb, cc) = break_sexp!(sub => (Number, Number)).unwrap();
     assert_eq!(a, aa);
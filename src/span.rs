@@ -0,0 +1,234 @@
+//! Optional side-table mapping parsed structures back to their source text.
+//!
+//! Sexps themselves carry no location info. Instead, a Tokenizer & Parser
+//! sharing a SourceMap record source lines and a SpanTree per parsed Sexp,
+//! which AmlangInterpreter then attaches to the Nodes it creates. Errors can
+//! then point back at the source of the Nodes in their continuation.
+
+use std::cell::RefCell;
+use std::collections::{BTreeMap, VecDeque};
+use std::rc::Rc;
+
+use crate::primitive::{Node, Primitive};
+use crate::sexp::Sexp;
+
+
+pub type SourceId = usize;
+pub type SharedSourceMap = Rc<RefCell<SourceMap>>;
+
+/// Lines a SourceMap retains by default; see SourceMap::with_line_limit.
+pub const DEFAULT_LINE_LIMIT: usize = 10_000;
+
+/// Region of a source, with 0-indexed lines and byte columns. The end is
+/// just past the last char.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Span {
+    pub source: SourceId,
+    pub line: usize,
+    pub col: usize,
+    pub end_line: usize,
+    pub end_col: usize,
+}
+
+/// Spans of a Sexp and its elements, mirroring its structure.
+///
+/// Children are the elements of a list (including any improper tail) or
+/// Vector, in order; primitives have none.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpanTree {
+    pub span: Span,
+    pub children: Vec<SpanTree>,
+}
+
+/// Lines of sources, along with spans into them.
+///
+/// So that long-running REPLs & loads don't grow it forever, only the most
+/// recent lines across all sources are retained, and spans into dropped
+/// lines are forgotten.
+#[derive(Debug)]
+pub struct SourceMap {
+    sources: Vec<Source>,
+    // Trees of parsed Sexps which have yet to be interpreted.
+    parsed: VecDeque<SpanTree>,
+    // Span of the last top-level Sexp interpreted.
+    root: Option<Span>,
+    nodes: BTreeMap<Node, Span>,
+
+    // Source of each retained line, oldest first.
+    line_order: VecDeque<SourceId>,
+    line_limit: usize,
+}
+
+#[derive(Debug)]
+struct Source {
+    name: String,
+    lines: VecDeque<String>,
+    // Lines dropped from the front of lines.
+    dropped: usize,
+}
+
+
+impl SpanTree {
+    pub fn leaf(span: Span) -> Self {
+        Self {
+            span,
+            children: vec![],
+        }
+    }
+
+    /// Whether this tree could have come from parsing sexp.
+    pub fn matches(&self, sexp: &Sexp) -> bool {
+        match sexp {
+            Sexp::Primitive(Primitive::Vector(vector)) => {
+                vector.len() == self.children.len()
+                    && vector
+                        .iter()
+                        .zip(self.children.iter())
+                        .all(|(elem, child)| child.matches(elem))
+            }
+            Sexp::Primitive(_) => self.children.is_empty(),
+            Sexp::Cons(_) => {
+                let mut children = self.children.iter();
+                for (elem, _) in sexp.iter() {
+                    match children.next() {
+                        Some(child) if child.matches(elem) => {}
+                        _ => return false,
+                    }
+                }
+                children.next().is_none()
+            }
+        }
+    }
+}
+
+impl SourceMap {
+    pub fn shared() -> SharedSourceMap {
+        Rc::new(RefCell::new(Self::default()))
+    }
+
+    /// Retain at most limit lines, dropping the oldest beyond that.
+    pub fn with_line_limit(mut self, limit: usize) -> Self {
+        self.line_limit = limit.max(1);
+        self
+    }
+
+    pub fn add_source<S: Into<String>>(&mut self, name: S) -> SourceId {
+        self.sources.push(Source {
+            name: name.into(),
+            lines: VecDeque::new(),
+            dropped: 0,
+        });
+        self.sources.len() - 1
+    }
+
+    pub fn push_line<S: Into<String>>(&mut self, source: SourceId, line: S) {
+        self.sources[source].lines.push_back(line.into());
+        self.line_order.push_back(source);
+        if self.line_order.len() > self.line_limit {
+            self.drop_lines();
+        }
+    }
+
+    // Drop the oldest quarter of lines, so that spans are only pruned once
+    // per batch of lines.
+    fn drop_lines(&mut self) {
+        let keep = self.line_limit - self.line_limit / 4;
+        while self.line_order.len() > keep {
+            let source = &mut self.sources[self.line_order.pop_front().unwrap()];
+            source.lines.pop_front();
+            source.dropped += 1;
+        }
+
+        let sources = &self.sources;
+        let retained = |span: &Span| span.line >= sources[span.source].dropped;
+        self.nodes.retain(|_, span| retained(span));
+        self.parsed.retain(|tree| retained(&tree.span));
+        if self.root.as_ref().is_some_and(|root| !retained(root)) {
+            self.root = None;
+        }
+    }
+
+    pub fn push_parsed(&mut self, tree: SpanTree) {
+        self.parsed.push_back(tree);
+    }
+
+    /// Take the tree of the next parsed Sexp, provided it matches sexp.
+    ///
+    /// Sexps not from the Parser (e.g. through eval) leave it in place.
+    pub fn pop_parsed(&mut self, sexp: &Sexp) -> Option<SpanTree> {
+        match self.parsed.front() {
+            Some(tree) if tree.matches(sexp) => self.parsed.pop_front(),
+            _ => None,
+        }
+    }
+
+    pub fn set_root(&mut self, span: Option<Span>) {
+        self.root = span;
+    }
+
+    pub fn take_root(&mut self) -> Option<Span> {
+        self.root.take()
+    }
+
+    pub fn insert_node(&mut self, node: Node, span: Span) {
+        self.nodes.insert(node, span);
+    }
+
+    pub fn node_span(&self, node: Node) -> Option<Span> {
+        self.nodes.get(&node).copied()
+    }
+
+    /// Excerpt of the source with span underlined by carets, or None if the
+    /// source text isn't available. Spans covering multiple lines only
+    /// underline their first line.
+    pub fn render(&self, span: &Span) -> Option<String> {
+        let source = self.sources.get(span.source)?;
+        let line = source.lines.get(span.line.checked_sub(source.dropped)?)?;
+        let end = if span.end_line == span.line {
+            span.end_col
+        } else {
+            line.len()
+        };
+        let indent = line.get(..span.col)?.chars().count();
+        let width = line.get(span.col..end)?.chars().count().max(1);
+
+        let number = (span.line + 1).to_string();
+        let pad = " ".repeat(number.len());
+        Some(format!(
+            "{}--> {}:{}:{}\n{} |\n{} | {}\n{} | {}{}{}",
+            pad,
+            source.name,
+            span.line + 1,
+            indent + 1,
+            pad,
+            number,
+            line,
+            pad,
+            " ".repeat(indent),
+            "^".repeat(width),
+            if span.end_line == span.line {
+                ""
+            } else {
+                " ..."
+            },
+        ))
+    }
+}
+
+impl Default for SourceMap {
+    fn default() -> Self {
+        Self {
+            sources: Default::default(),
+            parsed: Default::default(),
+            root: None,
+            nodes: Default::default(),
+            line_order: Default::default(),
+            line_limit: DEFAULT_LINE_LIMIT,
+        }
+    }
+}
+
+
+#[cfg(test)]
+#[path = "./span_test.rs"]
+mod span_test;
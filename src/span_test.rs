@@ -0,0 +1,131 @@
+use super::*;
+
+use crate::env::LocalNode;
+use crate::parser::Parser;
+use crate::primitive::symbol_policies::policy_base;
+use crate::stream::input::StringReader;
+use crate::token::Tokenizer;
+
+fn parse(input: &str) -> (SharedSourceMap, Vec<Sexp>) {
+    let map = SourceMap::shared();
+    let source = map.borrow_mut().add_source("test");
+    let sexps = pull_transform!(?unwrap
+                                StringReader::new(input)
+                                =>> Tokenizer::new(policy_base).with_source(map.clone(), source)
+                                =>. Parser::new().with_source(map.clone(), source))
+    .map(|s| s.unwrap())
+    .collect::<Vec<_>>();
+    (map, sexps)
+}
+
+fn span(line: usize, col: usize, end_line: usize, end_col: usize) -> Span {
+    Span {
+        source: 0,
+        line,
+        col,
+        end_line,
+        end_col,
+    }
+}
+
+#[test]
+fn nested() {
+    let (map, sexps) = parse("(a (bc \"d\"))");
+    assert_eq!(sexps.len(), 1);
+    let tree = map.borrow_mut().pop_parsed(&sexps[0]).unwrap();
+
+    assert_eq!(tree.span, span(0, 0, 0, 12));
+    assert_eq!(tree.children.len(), 2);
+    assert_eq!(tree.children[0], SpanTree::leaf(span(0, 1, 0, 2)));
+    let inner = &tree.children[1];
+    assert_eq!(inner.span, span(0, 3, 0, 11));
+    assert_eq!(inner.children[0], SpanTree::leaf(span(0, 4, 0, 6)));
+    assert_eq!(inner.children[1], SpanTree::leaf(span(0, 7, 0, 10)));
+}
+
+#[test]
+fn multiline() {
+    let (map, sexps) = parse("(a\n  b) [c\n d]");
+    assert_eq!(sexps.len(), 2);
+    let list = map.borrow_mut().pop_parsed(&sexps[0]).unwrap();
+    let vector = map.borrow_mut().pop_parsed(&sexps[1]).unwrap();
+
+    assert_eq!(list.span, span(0, 0, 1, 4));
+    assert_eq!(list.children[1], SpanTree::leaf(span(1, 2, 1, 3)));
+    assert_eq!(vector.span, span(1, 5, 2, 3));
+    assert_eq!(vector.children[1], SpanTree::leaf(span(2, 1, 2, 2)));
+}
+
+#[test]
+fn quotes_and_tails() {
+    let (map, sexps) = parse("'(a . b) ,@c");
+    let quoted = map.borrow_mut().pop_parsed(&sexps[0]).unwrap();
+    let spliced = map.borrow_mut().pop_parsed(&sexps[1]).unwrap();
+
+    // Quote symbol spans the quote char; the form spans through its arg.
+    assert_eq!(quoted.span, span(0, 0, 0, 8));
+    assert_eq!(quoted.children[0], SpanTree::leaf(span(0, 0, 0, 1)));
+    let pair = &quoted.children[1];
+    assert_eq!(pair.span, span(0, 1, 0, 8));
+    assert_eq!(pair.children.len(), 2);
+    assert_eq!(pair.children[1], SpanTree::leaf(span(0, 6, 0, 7)));
+
+    assert_eq!(spliced.span, span(0, 9, 0, 12));
+    assert_eq!(spliced.children[0], SpanTree::leaf(span(0, 9, 0, 11)));
+}
+
+#[test]
+fn pop_parsed_mismatch() {
+    let (map, sexps) = parse("(a b) c");
+    // Sexps which didn't come from the Parser leave the queue untouched.
+    assert_eq!(map.borrow_mut().pop_parsed(&sexps[1]), None);
+    assert!(map.borrow_mut().pop_parsed(&sexps[0]).is_some());
+    assert!(map.borrow_mut().pop_parsed(&sexps[1]).is_some());
+    assert_eq!(map.borrow_mut().pop_parsed(&sexps[0]), None);
+}
+
+#[test]
+fn render() {
+    let (map, sexps) = parse("(a\n  (λ b) c)");
+    let tree = map.borrow_mut().pop_parsed(&sexps[0]).unwrap();
+    let map = map.borrow();
+
+    assert_eq!(
+        map.render(&tree.children[1].span).unwrap(),
+        " --> test:2:3\n  |\n2 |   (λ b) c)\n  |   ^^^^^"
+    );
+    assert_eq!(
+        map.render(&tree.span).unwrap(),
+        " --> test:1:1\n  |\n1 | (a\n  | ^^ ..."
+    );
+    assert_eq!(map.render(&span(5, 0, 5, 1)), None);
+}
+
+#[test]
+fn line_limit() {
+    let mut map = SourceMap::default().with_line_limit(4);
+    let first = map.add_source("first");
+    map.push_line(first, "(a)");
+    let node = Node::new(LocalNode::new(1), LocalNode::new(2));
+    map.insert_node(node, span(0, 0, 0, 3));
+    assert!(map.render(&span(0, 0, 0, 3)).is_some());
+
+    let second = map.add_source("second");
+    for i in 0..4 {
+        map.push_line(second, format!("(b {})", i));
+    }
+    // The oldest lines are dropped, along with spans into them.
+    assert_eq!(map.render(&span(0, 0, 0, 3)), None);
+    assert_eq!(map.node_span(node), None);
+    let last = Span {
+        source: second,
+        line: 3,
+        col: 0,
+        end_line: 3,
+        end_col: 5,
+    };
+    assert_eq!(
+        map.render(&last).unwrap(),
+        " --> second:4:1\n  |\n4 | (b 3)\n  | ^^^^^"
+    );
+}
@@ -1,3 +1,6 @@
+use std::fmt;
+
+use crate::agent::Agent;
 use crate::error::{Error, ErrorKind};
 use crate::sexp::Sexp;
 
@@ -8,16 +11,33 @@ pub enum StdError {
     Io(std::io::Error),
 }
 
+impl PartialEq for StdError {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            // io::Errors aren't PartialEq.
+            (Self::Io(a), Self::Io(b)) => a.kind() == b.kind() && a.to_string() == b.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for StdError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
 impl ErrorKind for StdError {
-    // TODO(func) Model within env rather than fall back on strings.
-    fn reify(&self) -> Sexp {
-        let mut inner = match self {
-            Self::Io(err) => {
-                list!("IoError".to_string(), err.to_string())
-            }
-        };
-        inner.push_front("StdError".to_string());
-        inner
+    fn reify(&self, agent: &Agent) -> Sexp {
+        let context = agent.error_context();
+        match self {
+            Self::Io(err) => context.reify_error(
+                *context.std_error(),
+                *context.io_error(),
+                vec![(*context.message(), err.to_string().into())],
+            ),
+        }
     }
 }
 
@@ -1,5 +1,5 @@
 use std::fs::{File, OpenOptions};
-use std::io::{BufRead, BufReader, ErrorKind, Lines};
+use std::io::{stdin, BufRead, BufReader, ErrorKind, IsTerminal, Lines, StdinLock};
 use std::os::unix::fs::OpenOptionsExt;
 use std::path::{Path, PathBuf};
 
@@ -41,11 +41,7 @@ impl FileReader {
 impl Iterator for FileReader {
     type Item = Result<String, Error>;
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(res) = self.reader.next() {
-            Some(res.map_err(|e| e.into()))
-        } else {
-            None
-        }
+        self.reader.next().map(|res| res.map_err(|e| e.into()))
     }
 }
 
@@ -120,3 +116,35 @@ impl Iterator for StringReader {
         None
     }
 }
+
+
+/// Reads lines from stdin, e.g. when piped a script.
+pub struct StdinReader {
+    reader: Lines<StdinLock<'static>>,
+}
+
+impl StdinReader {
+    pub fn new() -> Self {
+        Self {
+            reader: stdin().lock().lines(),
+        }
+    }
+
+    /// Whether stdin is a terminal, rather than e.g. a pipe or file.
+    pub fn is_tty() -> bool {
+        stdin().is_terminal()
+    }
+}
+
+impl Default for StdinReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Iterator for StdinReader {
+    type Item = Result<String, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader.next().map(|res| res.map_err(|e| e.into()))
+    }
+}
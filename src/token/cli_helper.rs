@@ -1,34 +1,57 @@
+use colored::*;
 use rustyline::completion::{Candidate, Completer};
 use rustyline::highlight::Highlighter;
 use rustyline::hint::{Hint, Hinter};
 use rustyline::line_buffer::LineBuffer;
-use rustyline::validate::Validator;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
 use rustyline::{Context, Helper};
+use std::borrow::Cow;
 use std::cell::RefCell;
 use std::collections::BTreeMap;
+use std::convert::TryFrom;
 
-use crate::agent::Agent;
-use crate::primitive::Symbol;
+use super::cli_stream::ReplCommand;
+use super::token::{Token, TokenKind};
+use super::tokenizer::Tokenizer;
+use crate::agent::print_settings::paren_color;
+use crate::agent::{Agent, AmlangInterpreter};
+use crate::primitive::symbol_policies::{policy_base, SymbolGrammar};
+use crate::primitive::{Primitive, Procedure, Symbol, ToSymbol};
+use crate::stream::Transform;
 
 
 // Rustyline Helper for CliStream.
+//
+// Highlights & validates input, and if given an Agent, completes symbols
+// and hints at the params of procedures.
 pub struct CliHelper {
-    agent: RefCell<Agent>,
+    agent: Option<RefCell<Agent>>,
 }
 
 pub struct CliCandidate {
     symbol: Symbol,
 }
 
+pub struct CliHint {
+    display: String,
+}
+
 impl CliHelper {
     pub fn new(agent: Agent) -> Self {
         Self {
-            agent: RefCell::new(agent),
+            agent: Some(RefCell::new(agent)),
         }
     }
 
+    pub fn without_agent() -> Self {
+        Self { agent: None }
+    }
+
     fn designation_prefix(&self, prefix: &str) -> Vec<Symbol> {
-        let agent = self.agent.borrow_mut();
+        let agent = match &self.agent {
+            Some(agent) => agent.borrow_mut(),
+            None => return vec![],
+        };
         let mut res = Vec::<Symbol>::new();
         for node in agent.designation_chain() {
             let pairs: BTreeMap<_, _> = agent
@@ -47,14 +70,22 @@ impl CliHelper {
         res
     }
 
+    // Grammar of the Agent's identifiers, if given one.
+    fn grammar(&self) -> SymbolGrammar {
+        match &self.agent {
+            Some(agent) => agent.borrow().symbol_grammar().clone(),
+            None => SymbolGrammar::standard().clone(),
+        }
+    }
+
     fn word_bounds<'a>(&self, line: &'a str, pos: usize) -> (usize, usize) {
         let mut start: usize = 0;
         let mut end: usize = line.len();
+        let grammar = self.grammar();
         for (i, c) in line.char_indices() {
-            // TODO(flex) Use symbol policy here.
-            if !c.is_alphabetic() && c != '_' && c != '+' && c != '-' && c != '*' && c != '/' {
+            if !grammar.is_subsequent(c) {
                 if i < pos {
-                    start = i + 1;
+                    start = i + c.len_utf8();
                 } else {
                     end = i;
                     break;
@@ -95,12 +126,123 @@ impl Completer for CliHelper {
 }
 
 
-impl Helper for CliHelper {}
 impl Hinter for CliHelper {
-    type Hint = CliCandidate;
+    type Hint = CliHint;
+
+    // Params of the procedure named just before the cursor, if any.
+    fn hint(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> Option<Self::Hint> {
+        let agent = self.agent.as_ref()?.borrow();
+        let (start, end) = self.word_bounds(line, pos);
+        if start == end || pos != end || !line[..start].ends_with('(') {
+            return None;
+        }
+
+        let symbol = (&line[start..end]).to_symbol(policy_base).ok()?;
+        let node = agent.resolve_name(&symbol).ok()?;
+        let proc = Procedure::try_from(agent.concretize(node).ok()?).ok()?;
+        let names = AmlangInterpreter::param_names(&agent, &proc)?;
+        if names.is_empty() {
+            return None;
+        }
+        Some(CliHint {
+            display: names.iter().map(|name| format!(" {}", name)).collect(),
+        })
+    }
+}
+
+
+impl Highlighter for CliHelper {
+    fn highlight<'l>(&self, line: &'l str, _pos: usize) -> Cow<'l, str> {
+        Cow::Owned(highlight(line, self.grammar()))
+    }
+
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        Cow::Owned(hint.dimmed().to_string())
+    }
+
+    fn highlight_char(&self, _line: &str, _pos: usize) -> bool {
+        true
+    }
 }
-impl Highlighter for CliHelper {}
-impl Validator for CliHelper {}
+
+/// Color input by token, with parens colored by depth as when printed.
+fn highlight(input: &str, grammar: SymbolGrammar) -> String {
+    let mut tokenizer = Tokenizer::new(policy_base)
+        .with_grammar(grammar)
+        .recovering();
+    // Invalid tokens are simply left uncolored.
+    let _ = tokenizer.tokenize(input);
+    let open_string = tokenizer.finish().err();
+
+    let mut line_starts = vec![0];
+    line_starts.extend(input.match_indices('\n').map(|(i, _)| i + 1));
+    let offset = |line: usize, col: usize| line_starts[line] + col;
+
+    let mut res = String::with_capacity(input.len());
+    let mut last = 0;
+    let mut depth: usize = 0;
+    while let Some(Ok(token)) = <dyn Transform<&str, Token>>::output(&mut tokenizer) {
+        let start = offset(token.line, token.col);
+        let end = offset(token.end.0, token.end.1);
+        let text = &input[start..end];
+        let styled = match &token.token {
+            TokenKind::LeftParen | TokenKind::LeftBracket => {
+                let (r, g, b) = paren_color(depth);
+                depth += 1;
+                text.truecolor(r, g, b)
+            }
+            TokenKind::RightParen | TokenKind::RightBracket => {
+                depth = depth.saturating_sub(1);
+                let (r, g, b) = paren_color(depth);
+                text.truecolor(r, g, b)
+            }
+            TokenKind::Comment(_) => text.dimmed(),
+            TokenKind::Primitive(Primitive::Symbol(_)) => text.cyan(),
+            TokenKind::Primitive(Primitive::Number(_)) => text.yellow(),
+            TokenKind::Primitive(Primitive::LangString(_) | Primitive::Char(_)) => text.green(),
+            TokenKind::Primitive(_) => text.magenta(),
+            _ => text.normal(),
+        };
+        res.push_str(&input[last..start]);
+        res.push_str(&styled.to_string());
+        last = end;
+    }
+    if let Some(err) = open_string {
+        let start = offset(err.line(), err.col());
+        res.push_str(&input[last..start]);
+        res.push_str(&input[start..].green().to_string());
+        last = input.len();
+    }
+    res.push_str(&input[last..]);
+    res
+}
+
+
+// Let Enter continue input onto a new line while any list or string is open,
+// unless it's a REPL command.
+impl Validator for CliHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        Ok(if is_complete(ctx.input(), self.grammar()) {
+            ValidationResult::Valid(None)
+        } else {
+            ValidationResult::Incomplete
+        })
+    }
+}
+
+fn is_complete(input: &str, grammar: SymbolGrammar) -> bool {
+    if ReplCommand::parse(input).is_some() {
+        return true;
+    }
+    let mut tokenizer = Tokenizer::new(policy_base)
+        .with_grammar(grammar)
+        .recovering();
+    let _ = tokenizer.tokenize(input);
+    tokenizer.depth() == 0
+}
+
+
+impl Helper for CliHelper {}
 
 
 impl Candidate for CliCandidate {
@@ -113,12 +255,18 @@ impl Candidate for CliCandidate {
     }
 }
 
-impl Hint for CliCandidate {
+impl Hint for CliHint {
     fn display(&self) -> &str {
-        self.symbol.as_str()
+        &self.display
     }
 
+    // Hints are only informational.
     fn completion(&self) -> Option<&str> {
-        Some(self.symbol.as_str())
+        None
     }
 }
+
+
+#[cfg(test)]
+#[path = "./cli_helper_test.rs"]
+mod cli_helper_test;
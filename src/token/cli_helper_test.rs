@@ -0,0 +1,62 @@
+use super::*;
+
+// Strip color codes from highlighted text.
+fn plain(highlighted: &str) -> String {
+    let mut res = String::new();
+    let mut chars = highlighted.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|c| c.is_ascii_alphabetic());
+        } else {
+            res.push(c);
+        }
+    }
+    res
+}
+
+#[test]
+fn highlighting() {
+    colored::control::set_override(true);
+
+    for input in [
+        "(def x [1 \"two\" #\\3]) ; comment",
+        "(a\n  (b \"multi\nline\"))",
+        "(unclosed \"string",
+        "(invalid 2a)",
+    ] {
+        let highlighted = highlight(input, SymbolGrammar::default());
+        assert_ne!(highlighted, input);
+        assert_eq!(plain(&highlighted), input);
+    }
+
+    let (r, g, b) = paren_color(1);
+    assert!(
+        highlight("((a))", SymbolGrammar::default()).contains(&"(".truecolor(r, g, b).to_string())
+    );
+}
+
+#[test]
+fn completeness() {
+    for complete in [
+        "",
+        "(a b)",
+        "[1 (2)]",
+        "\"a\nb\"",
+        "(a)) ; (",
+        "'a",
+        ":load \"a",
+    ] {
+        assert!(
+            is_complete(complete, SymbolGrammar::default()),
+            "{}",
+            complete
+        );
+    }
+    for incomplete in ["(a", "[1 (2]", "(a\n (b", "\"a", "(a \"b)\"", "'"] {
+        assert!(
+            !is_complete(incomplete, SymbolGrammar::default()),
+            "{}",
+            incomplete
+        );
+    }
+}
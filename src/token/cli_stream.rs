@@ -1,97 +1,155 @@
+use log::warn;
 use rustyline::error::ReadlineError;
 use rustyline::Editor;
+use std::collections::{BTreeSet, VecDeque};
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
 
 use super::cli_helper::CliHelper;
 use super::token::Token;
 use super::tokenizer::Tokenizer;
-use crate::agent::Agent;
+use crate::agent::env_policy::EnvPolicy;
+use crate::agent::script;
+use crate::agent::{Agent, EnvManager, HistoryEntry, HistorySession, LangError};
+use crate::env::LocalNode;
 use crate::error::Error;
-use crate::primitive::symbol_policies::policy_base;
+use crate::primitive::prelude::*;
+use crate::primitive::symbol_policies::policy_env_serde;
+use crate::sexp::Sexp;
+use crate::span::{SharedSourceMap, SourceId};
 use crate::stream::Transform;
 
 
-pub struct CliStream {
+pub struct CliStream<SymbolInfo = ()> {
     editor: Editor<CliHelper>,
-    tokenizer: Tokenizer<()>,
-
-    curr_expr: String,
+    tokenizer: Tokenizer<SymbolInfo>,
+    symbol_policy: SymbolPolicy<SymbolInfo>,
+    grammar: SymbolGrammar,
+    source: Option<(SharedSourceMap, SourceId)>,
 }
 
 impl CliStream {
     // TODO(func) Use Node abstracting relevant Agent state so we can
     // follow the state of a different Agent (or use that to have the
     // Agents directly share state).
+    //
+    // Identifiers are read as per the Agent's SymbolGrammar.
     pub fn with_helper(agent: Agent) -> Self {
-        Self::new(Some(CliHelper::new(agent)))
+        let grammar = agent.symbol_grammar().clone();
+        Self::new(CliHelper::new(agent), grammar)
     }
 
+    /// Without an Agent, symbols aren't completed or hinted.
     pub fn no_helper() -> Self {
-        Self::new(None)
+        Self::new(
+            CliHelper::without_agent(),
+            SymbolGrammar::standard().clone(),
+        )
     }
 
-    fn new(helper: Option<CliHelper>) -> Self {
+    fn new(helper: CliHelper, grammar: SymbolGrammar) -> Self {
         let mut editor = Editor::<CliHelper>::new();
-        editor.set_helper(helper);
+        editor.set_helper(Some(helper));
 
         Self {
             editor,
-            tokenizer: Tokenizer::new(policy_base),
+            tokenizer: Tokenizer::new(policy_base).with_grammar(grammar.clone()),
+            symbol_policy: policy_base,
+            grammar,
+            source: None,
+        }
+    }
+}
+
+impl<SymbolInfo> CliStream<SymbolInfo> {
+    /// Tokenize input under symbol_policy rather than policy_base.
+    pub fn with_policy<T>(self, symbol_policy: SymbolPolicy<T>) -> CliStream<T> {
+        let mut res = CliStream {
+            editor: self.editor,
+            tokenizer: Tokenizer::new(symbol_policy).with_grammar(self.grammar.clone()),
+            symbol_policy,
+            grammar: self.grammar,
+            source: None,
+        };
+        if let Some((map, source)) = self.source {
+            res = res.with_source(map, source);
+        }
+        res
+    }
 
-            curr_expr: String::default(),
+    /// Admin mode, which accepts all identifiers as well as raw references
+    /// to nodes & triples, as written in serialized envs (e.g. ^17 or
+    /// ^4^t2). Agents only resolve the latter once enabled through
+    /// Agent::set_node_references.
+    pub fn admin(self) -> CliStream<AdminSymbolInfo> {
+        self.with_policy(policy_env_serde)
+    }
+
+    /// Seed the line editor's history with inputs, oldest first.
+    pub fn with_history<S: AsRef<str>, I: IntoIterator<Item = S>>(mut self, inputs: I) -> Self {
+        for input in inputs {
+            self.editor.add_history_entry(input.as_ref());
         }
+        self
+    }
+
+    /// Record input lines in map, as part of source.
+    pub fn with_source(mut self, map: SharedSourceMap, source: SourceId) -> Self {
+        self.tokenizer = Tokenizer::new(self.symbol_policy)
+            .with_grammar(self.grammar.clone())
+            .with_source(map.clone(), source);
+        self.source = Some((map, source));
+        self
+    }
+
+    fn print_history(&self) {
+        for (i, entry) in self.editor.history().iter().enumerate() {
+            println!("{:>4}  {}", i, entry);
+        }
+        println!();
     }
 }
 
 
-impl Iterator for CliStream {
-    type Item = Result<Token, Error>;
+/// What CliStream reads: Amlang input, or a command to be run out-of-band
+/// rather than parsed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CliInput<T> {
+    Amlang(T),
+    Command(ReplCommand),
+}
+
+impl<SymbolInfo> Iterator for CliStream<SymbolInfo> {
+    type Item = Result<CliInput<Token>, Error>;
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
             if let Some(token) = <dyn Transform<String, Token>>::output(&mut self.tokenizer) {
-                return Some(token);
+                return Some(token.map(CliInput::Amlang));
             }
 
-            let line = if self.tokenizer.depth() == 0 {
-                self.editor.add_history_entry(self.curr_expr.as_str());
-                self.curr_expr = String::default();
-                self.editor.readline("> ")
-            } else {
-                self.editor
-                    .readline(&format!("..{}", "  ".repeat(self.tokenizer.depth())))
-            };
-
-            match line {
-                Ok(line) => {
-                    let l = self.curr_expr.len();
-                    // Insert whitespace only if we don't already have any and
-                    // we haven't just opened or are about to close a list.
-                    if l > 0
-                        && (|c: char| !c.is_whitespace() && c != '(')(
-                            self.curr_expr.as_str().chars().next_back().unwrap(),
-                        )
-                    {
-                        if let Some(')') = line.as_str().chars().next() {
-                        } else {
-                            self.curr_expr += " ";
+            // CliHelper continues input onto new lines until it's complete.
+            match self.editor.readline("> ") {
+                Ok(input) => {
+                    self.editor.add_history_entry(input.as_str());
+                    match ReplCommand::parse(&input) {
+                        Some(Ok(ReplCommand::History)) => self.print_history(),
+                        Some(Ok(command)) => return Some(Ok(CliInput::Command(command))),
+                        Some(Err(usage)) => {
+                            println!("{}", usage);
+                            println!();
+                        }
+                        None => {
+                            if let Err(err) = self.tokenizer.tokenize(&input) {
+                                println!("{:?}", err);
+                                println!("");
+                                self.tokenizer.clear();
+                            }
                         }
-                    }
-                    self.curr_expr += &line;
-                    // TODO(func) Make generic over policy.
-                    if let Err(err) = self.tokenizer.tokenize(&line) {
-                        println!("{:?}", err);
-                        println!("");
-                        self.tokenizer.clear();
-                        continue;
                     }
                 }
                 Err(ReadlineError::Interrupted) => {
                     println!("^C");
-                    if self.tokenizer.depth() > 0 {
-                        self.tokenizer.clear();
-                        // Enable ^C to cancel an expression mid-parse.
-                        return None;
-                    }
                     continue;
                 }
                 Err(ReadlineError::Eof) => {
@@ -109,3 +167,243 @@ impl Iterator for CliStream {
         }
     }
 }
+
+
+/// Colon-prefixed commands, read by CliStream in place of Amlang input.
+///
+/// CliStream handles :history itself, printing its line history, and passes
+/// the rest downstream as CliInput::Command, to be run by ReplCommands.
+///
+/// Commands take precedence over Amlang input: since symbols may start with
+/// a colon, a line which does so is read as a command nonetheless (e.g. the
+/// symbol :a can be evaluated through (progn :a) instead).
+#[derive(Clone, Debug, PartialEq)]
+pub enum ReplCommand {
+    Save,
+    Envs,
+    Env(String),
+    Dchain,
+    Describe(Symbol),
+    History,
+    Trace(bool),
+    Load(PathBuf),
+}
+
+impl ReplCommand {
+    pub const USAGE: &'static str = "commands: :save, :envs, :env <name>, :dchain, \
+                                     :describe <symbol>, :history, :trace on|off, :load <file>";
+
+    /// Parse line as a command if it starts with a colon, failing with a
+    /// usage message if the command is unknown or malformed.
+    pub fn parse(line: &str) -> Option<Result<Self, String>> {
+        let line = line.trim().strip_prefix(':')?;
+        let (name, arg) = match line.split_once(char::is_whitespace) {
+            Some((name, arg)) => (name, Some(arg.trim())),
+            None => (line, None),
+        };
+        let usage = |args: &str| Err(format!("usage: :{} {}", name, args));
+
+        Some(match (name, arg) {
+            ("save", None) => Ok(Self::Save),
+            ("envs", None) => Ok(Self::Envs),
+            ("env", Some(env)) => Ok(Self::Env(env.to_string())),
+            ("env", None) => usage("<name>"),
+            ("dchain", None) => Ok(Self::Dchain),
+            // Allow raw node references regardless of CliStream's policy.
+            ("describe", Some(symbol)) => match symbol.to_symbol(policy_env_serde) {
+                Ok(symbol) => Ok(Self::Describe(symbol)),
+                Err(_) => usage("<symbol>"),
+            },
+            ("describe", None) => usage("<symbol>"),
+            ("history", None) => Ok(Self::History),
+            ("trace", Some("on")) => Ok(Self::Trace(true)),
+            ("trace", Some("off")) => Ok(Self::Trace(false)),
+            ("trace", _) => usage("on|off"),
+            ("load", Some(path)) => Ok(Self::Load(path.into())),
+            ("load", None) => usage("<file>"),
+            ("save" | "envs" | "dchain" | "history", Some(_)) => {
+                Err(format!("usage: :{} (takes no arguments)", name))
+            }
+            _ => Err(format!("unknown command :{}; {}", name, Self::USAGE)),
+        })
+    }
+}
+
+
+/// Passes Amlang input through transform and commands around it, so that
+/// e.g. a Parser can follow CliStream.
+pub struct Bypass<T> {
+    transform: T,
+    commands: VecDeque<ReplCommand>,
+}
+
+impl<T> Bypass<T> {
+    pub fn new(transform: T) -> Self {
+        Self {
+            transform,
+            commands: Default::default(),
+        }
+    }
+}
+
+impl<I, O, T: Transform<I, O>> Transform<CliInput<I>, CliInput<O>> for Bypass<T> {
+    fn input(&mut self, input: Result<CliInput<I>, Error>) -> Result<bool, Error> {
+        match input {
+            Ok(CliInput::Amlang(input)) => self.transform.input(Ok(input)),
+            Ok(CliInput::Command(command)) => {
+                self.commands.push_back(command);
+                Ok(true)
+            }
+            Err(err) => self.transform.input(Err(err)),
+        }
+    }
+
+    fn output(&mut self) -> Option<Result<CliInput<O>, Error>> {
+        // Commands follow whatever the transform made of prior input.
+        if let Some(output) = self.transform.output() {
+            return Some(output.map(CliInput::Amlang));
+        }
+        Some(Ok(CliInput::Command(self.commands.pop_front()?)))
+    }
+}
+
+// Inputs of history.env returned by :history, most recent last.
+const HISTORY_ENTRIES: usize = 20;
+
+/// Runs the commands which CliStream passes downstream, for an Agent forked
+/// from manager's.
+pub struct ReplCommands<'m, Policy: EnvPolicy> {
+    manager: &'m mut EnvManager<Policy>,
+    save_path: PathBuf,
+    save_blacklist: BTreeSet<String>,
+    trace: bool,
+    history: Option<HistorySession>,
+}
+
+impl<'m, Policy: EnvPolicy> ReplCommands<'m, Policy> {
+    /// :save will serialize all envs to save_path, other than those whose
+    /// paths are in save_blacklist.
+    pub fn new<P: AsRef<Path>>(
+        manager: &'m mut EnvManager<Policy>,
+        save_path: P,
+        save_blacklist: BTreeSet<&str>,
+    ) -> Self {
+        Self {
+            manager,
+            save_path: save_path.as_ref().to_path_buf(),
+            save_blacklist: save_blacklist.into_iter().map(String::from).collect(),
+            trace: true,
+            history: None,
+        }
+    }
+
+    /// Record everything interpreted through eval as part of session.
+    pub fn with_history(mut self, session: HistorySession) -> Self {
+        self.history = Some(session);
+        self
+    }
+
+    /// Whether errors should be traced, as set by :trace.
+    pub fn trace(&self) -> bool {
+        self.trace
+    }
+
+    /// Interpret sexp, recording it in history if so configured.
+    pub fn eval(&mut self, agent: &mut Agent, sexp: Sexp) -> Result<Sexp, Error> {
+        let history = match &mut self.history {
+            Some(history) => history,
+            None => return agent.interpret(sexp),
+        };
+
+        let input = history.define_input(agent, sexp.clone())?;
+        let result = agent.interpret(sexp);
+        if let Err(err) = history.record(agent, input, &result) {
+            warn!("Failed to record history entry: {}", err);
+        }
+        result
+    }
+
+    pub fn run(&mut self, agent: &mut Agent, command: ReplCommand) -> Result<Sexp, Error> {
+        match command {
+            ReplCommand::Save => {
+                let blacklist = self.save_blacklist.iter().map(String::as_str).collect();
+                self.manager.serialize_full(&self.save_path, blacklist)?;
+                Ok(LangPath::from(self.save_path.as_path()).into())
+            }
+            ReplCommand::Envs => {
+                let envs = agent
+                    .env_paths()
+                    .into_iter()
+                    .map(|(env, path)| list!(Node::new(LocalNode::default(), env), path))
+                    .collect::<Vec<_>>();
+                Ok(envs.into())
+            }
+            ReplCommand::Env(name) => match agent.find_env(&name) {
+                Some(env) => Ok(agent.jump_env(env).into()),
+                None => err!(
+                    agent,
+                    LangError::InvalidArgument {
+                        given: name.into(),
+                        expected: "env listed in meta env".into(),
+                    }
+                ),
+            },
+            ReplCommand::Dchain => {
+                let dchain = agent
+                    .designation_chain()
+                    .iter()
+                    .map(|node| Sexp::from(*node))
+                    .collect::<Vec<_>>();
+                Ok(dchain.into())
+            }
+            ReplCommand::Describe(symbol) => {
+                let designation = agent.designate(symbol.clone().into())?;
+                let env = Node::try_from(designation)
+                    .ok()
+                    .and_then(|node| Some((node, agent.access_env(node.env())?)));
+                let (node, env) = match env {
+                    Some(found) => found,
+                    None => {
+                        return err!(
+                            agent,
+                            LangError::InvalidArgument {
+                                given: symbol.into(),
+                                expected: "symbol designating a node in a loaded env".into(),
+                            }
+                        )
+                    }
+                };
+                let structure = agent.concretize(node)?;
+                let triples = agent
+                    .ask_any(node)?
+                    .triples()
+                    .map(|triple| {
+                        list!(
+                            Node::new(node.env(), env.triple_subject(triple)),
+                            Node::new(node.env(), env.triple_predicate(triple)),
+                            Node::new(node.env(), env.triple_object(triple)),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                Ok(list!(node, structure, triples))
+            }
+            ReplCommand::History => {
+                let inputs = HistoryEntry::last(agent, HISTORY_ENTRIES)?
+                    .into_iter()
+                    .map(|entry| entry.input)
+                    .collect::<Vec<_>>();
+                Ok(inputs.into())
+            }
+            ReplCommand::Trace(on) => {
+                self.trace = on;
+                Ok(on.into())
+            }
+            ReplCommand::Load(path) => script::run_file(agent, &path),
+        }
+    }
+}
+
+
+#[cfg(test)]
+#[path = "./cli_stream_test.rs"]
+mod cli_stream_test;
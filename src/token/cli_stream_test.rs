@@ -0,0 +1,86 @@
+use super::*;
+
+use crate::parser::Parser;
+use crate::stream::Transform;
+
+fn symbol(s: &str) -> Symbol {
+    s.to_symbol_or_panic(policy_base)
+}
+
+#[test]
+fn parse_commands() {
+    for (line, expected) in [
+        (":save", ReplCommand::Save),
+        ("  :envs  ", ReplCommand::Envs),
+        (
+            ":env working.env",
+            ReplCommand::Env("working.env".to_string()),
+        ),
+        (":dchain", ReplCommand::Dchain),
+        (":describe car", ReplCommand::Describe(symbol("car"))),
+        (
+            ":describe ^4^t2",
+            ReplCommand::Describe("^4^t2".to_symbol_or_panic(policy_env_serde)),
+        ),
+        (":history", ReplCommand::History),
+        (":trace on", ReplCommand::Trace(true)),
+        (":trace off", ReplCommand::Trace(false)),
+        (
+            ":load dir/some file.am",
+            ReplCommand::Load("dir/some file.am".into()),
+        ),
+    ] {
+        assert_eq!(ReplCommand::parse(line), Some(Ok(expected)), "{}", line);
+    }
+
+    for line in ["(car '(1))", "a:b", ""] {
+        assert_eq!(ReplCommand::parse(line), None, "{}", line);
+    }
+    for line in [
+        ":",
+        ":unknown",
+        ":env",
+        ":describe",
+        ":describe (a)",
+        ":describe ^1^",
+        ":trace",
+        ":trace maybe",
+        ":load",
+        ":save now",
+    ] {
+        assert!(matches!(ReplCommand::parse(line), Some(Err(_))), "{}", line);
+    }
+}
+
+#[test]
+fn command_precedence() {
+    // Symbols may start with a colon, but lines which do are commands.
+    let mut tokenizer = Tokenizer::new(policy_base);
+    assert!(tokenizer.tokenize(":a").is_ok());
+    assert!(matches!(ReplCommand::parse(":a"), Some(Err(_))));
+    assert_eq!(ReplCommand::parse("(progn :a)"), None);
+}
+
+#[test]
+fn bypass() {
+    let mut bypass = Bypass::new(Parser::new());
+    let mut tokenizer = Tokenizer::new(policy_base);
+    tokenizer.tokenize("(car 1)").unwrap();
+    while let Some(token) = <dyn Transform<String, Token>>::output(&mut tokenizer) {
+        bypass.input(token.map(CliInput::Amlang)).unwrap();
+    }
+    bypass
+        .input(Ok(CliInput::Command(ReplCommand::Dchain)))
+        .unwrap();
+
+    let expected = Sexp::parse_with("(car 1)", policy_base).unwrap();
+    assert_eq!(
+        bypass.output().unwrap().unwrap(),
+        CliInput::Amlang(expected)
+    );
+    assert_eq!(
+        bypass.output().unwrap().unwrap(),
+        CliInput::Command(ReplCommand::Dchain)
+    );
+    assert!(bypass.output().is_none());
+}
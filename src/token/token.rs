@@ -6,17 +6,24 @@ use crate::primitive::Primitive;
 pub enum TokenKind {
     LeftParen,
     RightParen,
+    LeftBracket,
+    RightBracket,
     Quote,
+    Quasiquote,
+    Unquote,
+    UnquoteSplicing,
     Period,
     Primitive(Primitive),
     Comment(String),
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Token {
     pub token: TokenKind,
     pub line: usize,
     pub col: usize,
+    // (line, col) just past the token.
+    pub end: (usize, usize),
 }
 
 impl fmt::Display for Token {
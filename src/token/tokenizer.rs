@@ -1,15 +1,19 @@
 //! Module for breaking Amlang text into tokens.
 
 use std::collections::VecDeque;
+use std::fmt;
 
 use super::token::{Token, TokenKind};
+use crate::agent::Agent;
 use crate::error::{Error, ErrorKind};
+use crate::primitive::character;
 use crate::primitive::symbol::{SymbolError, ToSymbol};
-use crate::primitive::symbol_policies::SymbolPolicy;
+use crate::primitive::symbol_policies::{SymbolGrammar, SymbolPolicy};
 use crate::primitive::LangString;
 use crate::primitive::Number as Num;
 use crate::primitive::Primitive::*;
 use crate::sexp::Sexp;
+use crate::span::{SharedSourceMap, SourceId};
 use crate::stream::Transform;
 
 use self::TokenizerState::*;
@@ -25,6 +29,12 @@ pub struct Tokenizer<SymbolInfo> {
 
     // Non-control state.
     symbol_policy: SymbolPolicy<SymbolInfo>,
+    grammar: SymbolGrammar,
+    // SourceMap to record lines in, if any.
+    source: Option<(SharedSourceMap, SourceId)>,
+    // Whether to collect errors and carry on rather than fail.
+    recover: bool,
+    errors: Vec<TokenizeError>,
 
     line_count: usize,
     tokens: VecDeque<Token>,
@@ -33,23 +43,24 @@ pub struct Tokenizer<SymbolInfo> {
 #[derive(Debug)]
 enum TokenizerState {
     Base,
-    // (String accumulated from prev lines, col of first line).
-    InString(String, usize),
-    // (String accumulated from prev lines, col of first line).
-    InStringEscaped(String, usize),
+    // (String accumulated from prev lines, (line, col) of opening quote).
+    InString(String, (usize, usize)),
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct TokenizeError {
     line: usize,
     col: usize,
     kind: TokenizeErrorKind,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 enum TokenizeErrorKind {
     InvalidSymbol(SymbolError),
+    UnterminatedString,
+    // Escape sequence in a string, or char literal.
+    InvalidEscape(String),
 }
 
 
@@ -61,11 +72,50 @@ impl<SymbolInfo> Tokenizer<SymbolInfo> {
             started_quote: false,
 
             symbol_policy,
+            grammar: SymbolGrammar::standard().clone(),
+            source: None,
+            recover: false,
+            errors: Default::default(),
             line_count: 0,
             tokens: Default::default(),
         }
     }
 
+    /// Check identifiers against grammar rather than the standard one.
+    pub fn with_grammar(mut self, grammar: SymbolGrammar) -> Self {
+        self.grammar = grammar;
+        self
+    }
+
+    /// Record tokenized lines in map, as part of source.
+    pub fn with_source(mut self, map: SharedSourceMap, source: SourceId) -> Self {
+        self.source = Some((map, source));
+        self
+    }
+
+    /// Skip invalid tokens rather than failing, collecting errors to be
+    /// retrieved through take_errors.
+    pub fn recovering(mut self) -> Self {
+        self.recover = true;
+        self
+    }
+
+    pub fn take_errors(&mut self) -> Vec<TokenizeError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// Signal end of input, failing if a string was left open.
+    pub fn finish(&mut self) -> Result<(), TokenizeError> {
+        match std::mem::replace(&mut self.state, Base) {
+            InString(_, (line, col)) => Err(TokenizeError {
+                line,
+                col,
+                kind: TokenizeErrorKind::UnterminatedString,
+            }),
+            Base => Ok(()),
+        }
+    }
+
     pub fn clear(&mut self) {
         self.depth = 0;
         self.started_quote = false;
@@ -89,8 +139,25 @@ impl<SymbolInfo> Tokenizer<SymbolInfo> {
     fn tokenize_line<S: AsRef<str>>(&mut self, line: S) -> Result<(), TokenizeError> {
         let mut start: usize = 0;
         let mut empty = true;
+        // Number of chars already consumed as part of a token (e.g. ,@).
+        let mut skip: usize = 0;
+        // Set when the next char is part of a char literal, whatever it is.
+        let mut literal = false;
+        // Set when a string continues past a trailing \.
+        let mut continued = false;
         let l = line.as_ref();
+        if let Some((map, source)) = &self.source {
+            map.borrow_mut().push_line(*source, l);
+        }
         for (i, c) in l.char_indices() {
+            if skip > 0 {
+                skip -= 1;
+                continue;
+            }
+            if literal {
+                literal = false;
+                continue;
+            }
             match &mut self.state {
                 Base => {
                     if c.is_whitespace() {
@@ -107,6 +174,7 @@ impl<SymbolInfo> Tokenizer<SymbolInfo> {
                             token: TokenKind::Comment(l[i..].to_string()),
                             line: self.line_count,
                             col: i,
+                            end: (self.line_count, l.len()),
                         });
                         break;
                     }
@@ -116,7 +184,7 @@ impl<SymbolInfo> Tokenizer<SymbolInfo> {
                     self.started_quote = false;
 
                     match c {
-                        '(' | ')' | '\'' => {
+                        '(' | ')' | '[' | ']' | '\'' | '`' | ',' => {
                             if !empty {
                                 self.push_token(&l[start..i], start)?;
                                 empty = true;
@@ -131,16 +199,39 @@ impl<SymbolInfo> Tokenizer<SymbolInfo> {
                                     self.depth = self.depth.saturating_sub(1);
                                     TokenKind::RightParen
                                 }
+                                '[' => {
+                                    self.depth += 1;
+                                    TokenKind::LeftBracket
+                                }
+                                ']' => {
+                                    self.depth = self.depth.saturating_sub(1);
+                                    TokenKind::RightBracket
+                                }
                                 '\'' => {
                                     self.started_quote = true;
                                     TokenKind::Quote
                                 }
+                                '`' => {
+                                    self.started_quote = true;
+                                    TokenKind::Quasiquote
+                                }
+                                ',' => {
+                                    self.started_quote = true;
+                                    if l[i + 1..].starts_with('@') {
+                                        skip = 1;
+                                        TokenKind::UnquoteSplicing
+                                    } else {
+                                        TokenKind::Unquote
+                                    }
+                                }
                                 _ => panic!(),
                             };
+                            let len = skip + 1;
                             self.tokens.push_back(Token {
                                 token,
                                 line: self.line_count,
                                 col: i,
+                                end: (self.line_count, i + len),
                             });
                         }
                         '"' => {
@@ -148,33 +239,50 @@ impl<SymbolInfo> Tokenizer<SymbolInfo> {
                                 self.push_token(&l[start..i], start)?;
                             }
                             start = i + 1;
-                            self.state = InString(String::default(), start);
+                            self.state = InString(String::default(), (self.line_count, i));
                         }
                         _ => {
                             if empty {
                                 empty = false;
                                 start = i;
+                            } else if c == '\\' && &l[start..i] == "#" {
+                                literal = true;
                             }
                         }
                     }
                 }
-                InString(s, col) => {
+                InString(s, pos) => {
                     if empty {
                         empty = false;
                         start = i;
                     }
                     match c {
                         '\\' => {
-                            s.push_str(&line.as_ref()[start..i]);
-                            let curr_str = std::mem::replace(s, String::default());
-                            self.state = InStringEscaped(curr_str, *col);
+                            s.push_str(&l[start..i]);
+                            empty = true;
+                            let rest = &l[i + 1..];
+                            if rest.is_empty() {
+                                // \ followed by EOL simply means ignore the newline.
+                                continued = true;
+                            } else if let Some((c, len)) = LangString::unescape(rest) {
+                                s.push(c);
+                                skip = rest[..len].chars().count();
+                            } else {
+                                let escape = rest.chars().take(2).collect::<String>();
+                                self.fail(TokenizeError {
+                                    line: self.line_count,
+                                    col: i,
+                                    kind: TokenizeErrorKind::InvalidEscape(format!("\\{}", escape)),
+                                })?;
+                            }
                         }
                         '"' => {
-                            s.push_str(&line.as_ref()[start..i]);
+                            s.push_str(&l[start..i]);
                             self.tokens.push_back(Token {
                                 token: TokenKind::Primitive(LangString(LangString::new(s))),
-                                line: self.line_count,
-                                col: *col,
+                                line: pos.0,
+                                col: pos.1,
+                                end: (self.line_count, i + 1),
                             });
 
                             self.state = Base;
@@ -183,27 +291,18 @@ impl<SymbolInfo> Tokenizer<SymbolInfo> {
                         _ => {}
                     }
                 }
-                InStringEscaped(s, col) => {
-                    // TODO(func) allow for decoding of unicode.
-                    s.push(LangString::unescape_char(c));
-
-                    empty = true;
-                    let curr_str = std::mem::replace(s, String::default());
-                    self.state = InString(curr_str, *col);
-                }
             }
         }
 
         // EOL handling.
         match &mut self.state {
             InString(s, ..) => {
-                s.push_str(&line.as_ref()[start..]);
-                s.push('\n');
-            }
-            InStringEscaped(s, col) => {
-                // \ followed by EOL simply means ignore the newline.
-                let curr_str = std::mem::replace(s, String::default());
-                self.state = InString(curr_str, *col);
+                if !empty {
+                    s.push_str(&l[start..]);
+                }
+                if !continued {
+                    s.push('\n');
+                }
             }
             _ => {
                 if !empty {
@@ -223,18 +322,33 @@ impl<SymbolInfo> Tokenizer<SymbolInfo> {
                 token: TokenKind::Period,
                 line: self.line_count,
                 col: start,
+                end: (self.line_count, start + 1),
             });
             return Ok(());
         }
 
-        // Try to parse as number before imposing Symbol constraints.
-        let token = if let Ok(num) = ptoken.parse::<Num>() {
+        // Try to parse as bool, char or number before imposing Symbol
+        // constraints.
+        let token = if ptoken == "#t" || ptoken == "#f" {
+            TokenKind::Primitive(Bool(ptoken == "#t"))
+        } else if let Some(literal) = ptoken.strip_prefix("#\\") {
+            match character::from_literal(literal) {
+                Some(c) => TokenKind::Primitive(Char(c)),
+                None => {
+                    return self.fail(TokenizeError {
+                        line: self.line_count,
+                        col: start,
+                        kind: TokenizeErrorKind::InvalidEscape(ptoken.to_string()),
+                    });
+                }
+            }
+        } else if let Ok(num) = ptoken.parse::<Num>() {
             TokenKind::Primitive(Number(num))
         } else {
-            match ptoken.to_symbol(self.symbol_policy) {
+            match ptoken.to_symbol_with(self.symbol_policy, &self.grammar) {
                 Ok(symbol) => TokenKind::Primitive(Symbol(symbol)),
                 Err(err) => {
-                    return Err(TokenizeError {
+                    return self.fail(TokenizeError {
                         line: self.line_count,
                         col: start,
                         kind: TokenizeErrorKind::InvalidSymbol(err),
@@ -247,9 +361,19 @@ impl<SymbolInfo> Tokenizer<SymbolInfo> {
             token,
             line: self.line_count,
             col: start,
+            end: (self.line_count, start + ptoken.len()),
         });
         Ok(())
     }
+
+    // Fail with err, unless recovering.
+    fn fail(&mut self, err: TokenizeError) -> Result<(), TokenizeError> {
+        if self.recover {
+            self.errors.push(err);
+            return Ok(());
+        }
+        Err(err)
+    }
 }
 
 
@@ -266,15 +390,46 @@ impl<S: AsRef<str>, SymbolInfo> Transform<S, Token> for Tokenizer<SymbolInfo> {
     }
 }
 
+impl TokenizeError {
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn col(&self) -> usize {
+        self.col
+    }
+}
+
+impl fmt::Display for TokenizeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            TokenizeErrorKind::InvalidSymbol(err) => write!(f, "invalid symbol: {:?}", err),
+            TokenizeErrorKind::UnterminatedString => write!(f, "string is never closed"),
+            TokenizeErrorKind::InvalidEscape(escape) => write!(f, "invalid escape: {}", escape),
+        }
+    }
+}
+
 impl ErrorKind for TokenizeError {
-    // TODO(func) Model within env rather than fall back on strings.
-    fn reify(&self) -> Sexp {
-        list!(
-            "TokenizeError",
-            format!(
-                "[Tokenize Error]: {:?} @ ({}, {})",
-                self.kind, self.line, self.col
-            ),
+    fn reify(&self, agent: &Agent) -> Sexp {
+        let context = agent.error_context();
+        let (kind, message) = match &self.kind {
+            TokenizeErrorKind::InvalidSymbol(err) => {
+                (context.invalid_symbol(), format!("{:?}", err))
+            }
+            TokenizeErrorKind::UnterminatedString => {
+                (context.unterminated_string(), self.to_string())
+            }
+            TokenizeErrorKind::InvalidEscape(_) => (context.invalid_escape(), self.to_string()),
+        };
+        context.reify_error(
+            *context.tokenize_error(),
+            *kind,
+            vec![
+                (*context.message(), message.into()),
+                (*context.line(), Num::USize(self.line).into()),
+                (*context.col(), Num::USize(self.col).into()),
+            ],
         )
     }
 }
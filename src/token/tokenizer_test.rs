@@ -1,7 +1,7 @@
 use super::*;
 
 use crate::error::Error;
-use crate::primitive::symbol_policies::policy_base;
+use crate::primitive::symbol_policies::{policy_base, SymbolGrammar};
 use crate::primitive::{Number, ToSymbol};
 use crate::stream::input::StringReader;
 use TokenKind::*;
@@ -13,7 +13,14 @@ fn nest(mut v: Vec<TokenKind>) -> Vec<TokenKind> {
 }
 
 fn stream(input: &str) -> Result<impl Iterator<Item = Result<Token, Error>>, Error> {
-    Ok(pull_transform!(StringReader::new(input) =>> Tokenizer::new(policy_base)))
+    stream_with(input, Tokenizer::new(policy_base))
+}
+
+fn stream_with(
+    input: &str,
+    tokenizer: Tokenizer<()>,
+) -> Result<impl Iterator<Item = Result<Token, Error>>, Error> {
+    Ok(pull_transform!(StringReader::new(input) =>> tokenizer))
 }
 
 #[test]
@@ -100,3 +107,155 @@ fn strings() {
         assert_eq!(elem.token, expected[i]);
     }
 }
+
+#[test]
+fn string_escapes() {
+    let input = r#""\t\"\\\0 \x41\u{1F600}\u{e9}" "a\
+b\n""#;
+    let expected = vec!["\t\"\\\0 A\u{1F600}é", "ab\n"];
+
+    let tokens = stream(input).unwrap().map(|r| r.unwrap().token);
+    assert_eq!(
+        tokens.collect::<Vec<_>>(),
+        expected
+            .into_iter()
+            .map(|s| Primitive(LangString(LangString::new(s))))
+            .collect::<Vec<_>>()
+    );
+
+    for invalid in [
+        r#""\x8f""#,
+        r#""\xg0""#,
+        r#""\u{}""#,
+        r#""\u{110000}""#,
+        r#""\u41""#,
+    ] {
+        assert!(stream(invalid).is_err(), "{}", invalid);
+    }
+
+    // Escaping restores the original string.
+    let s = "\t\r\n\0\\\"\u{7}\u{1b}😀 é";
+    let escaped = LangString::new(s).to_escaped();
+    assert_eq!(escaped, "\\t\\r\\n\\0\\\\\\\"\\u{7}\\u{1b}😀 é");
+    let tokens = stream(&format!("\"{}\"", escaped)).unwrap();
+    assert_eq!(
+        tokens.map(|r| r.unwrap().token).collect::<Vec<_>>(),
+        vec![Primitive(LangString(LangString::new(s)))]
+    );
+}
+
+#[test]
+fn chars() {
+    let input = r"(#\a #\( #\; #\space #\u{1F600} #\x41 #\\ #\λ)";
+    let expected = nest(
+        vec!['a', '(', ';', ' ', '😀', 'A', '\\', 'λ']
+            .into_iter()
+            .map(|c| Primitive(Char(c)))
+            .collect(),
+    );
+
+    let tokens = stream(input).unwrap().map(|r| r.unwrap().token);
+    assert_eq!(tokens.collect::<Vec<_>>(), expected);
+
+    for c in ['a', ' ', '\n', '\0', '\u{1b}', '(', '"', '😀'] {
+        let literal = crate::primitive::character::to_literal(c);
+        let tokens = stream(&literal).unwrap().map(|r| r.unwrap().token);
+        assert_eq!(tokens.collect::<Vec<_>>(), vec![Primitive(Char(c))]);
+    }
+    for invalid in [r"#\", r"#\ab", r"#\u{zz}"] {
+        assert!(stream(invalid).is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn quasiquotes() {
+    let input = "`(a ,b ,@c)";
+    let symbol = |s: &str| Primitive(Symbol(s.to_symbol_or_panic(policy_base)));
+    let mut expected = nest(vec![
+        symbol("a"),
+        Unquote,
+        symbol("b"),
+        UnquoteSplicing,
+        symbol("c"),
+    ]);
+    expected.insert(0, Quasiquote);
+
+    let tokens = stream(input).unwrap().map(|r| r.unwrap().token);
+    assert_eq!(tokens.collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn bools() {
+    let input = "#t #f";
+    let expected = vec![Primitive(Bool(true)), Primitive(Bool(false))];
+
+    let tokens = stream(input).unwrap();
+    for (i, elem) in tokens.map(|r| r.unwrap()).enumerate() {
+        assert_eq!(elem.token, expected[i]);
+    }
+    assert!(stream("#true").is_err());
+}
+
+#[test]
+fn brackets() {
+    let input = "[1 (a)]";
+    let expected = vec![
+        LeftBracket,
+        Primitive(Number(Number::generic(1))),
+        LeftParen,
+        Primitive(Symbol("a".to_symbol_or_panic(policy_base))),
+        RightParen,
+        RightBracket,
+    ];
+
+    let tokens = stream(input).unwrap().map(|r| r.unwrap().token);
+    assert_eq!(tokens.collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn identifiers() {
+    let input = "vec2 list? <= a/b -> x.y ≤ λ set-car! -5";
+    let symbol = |s: &str| Primitive(Symbol(s.to_symbol_or_panic(policy_base)));
+    let expected = vec![
+        symbol("vec2"),
+        symbol("list?"),
+        symbol("<="),
+        symbol("a/b"),
+        symbol("->"),
+        symbol("x.y"),
+        symbol("≤"),
+        symbol("λ"),
+        symbol("set-car!"),
+        Primitive(Number(Number::generic(-5))),
+    ];
+
+    let tokens = stream(input).unwrap().map(|r| r.unwrap().token);
+    assert_eq!(tokens.collect::<Vec<_>>(), expected);
+
+    for invalid in ["2a", "^a", "a#b", "inf"] {
+        assert!(invalid.to_symbol(policy_base).is_err(), "{}", invalid);
+    }
+}
+
+#[test]
+fn configured_grammar() {
+    let grammar = SymbolGrammar {
+        special_initial: "#".to_string(),
+        special_subsequent: String::new(),
+        allow_unicode: false,
+    };
+    let tokens = stream_with(
+        "a#b #x",
+        Tokenizer::new(policy_base).with_grammar(grammar.clone()),
+    )
+    .unwrap()
+    .map(|r| r.unwrap().token)
+    .collect::<Vec<_>>();
+    let symbol = |s: &str| Primitive(Symbol(s.to_symbol_with(policy_base, &grammar).unwrap()));
+    assert_eq!(tokens, vec![symbol("a#b"), symbol("#x")]);
+
+    // Other tokenizers keep the standard grammar.
+    assert!(Tokenizer::new(policy_base).tokenize("a#b").is_err());
+    assert!(Tokenizer::new(policy_base).tokenize("≤").is_ok());
+    assert!("≤".to_symbol_with(policy_base, &grammar).is_err());
+}
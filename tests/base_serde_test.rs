@@ -20,11 +20,7 @@ fn test_struct() {
         b: true,
     };
 
-    let t = Sexp::from(
-        agent
-            .resolve_name(&"true".to_symbol_or_panic(policy_base))
-            .unwrap(),
-    );
+    let t = Sexp::from(true);
 
     // TODO(func) Have list! support (a . b)
     let expected = list!(
@@ -54,6 +50,26 @@ fn test_struct() {
 
     let deserialized = agent.reflect::<Test>(*serialized).unwrap();
     assert_eq!(original, deserialized);
+
+    // Structures predating Bool reference the true Node instead.
+    let legacy = list!(
+        "Test".to_symbol_or_panic(policy_base),
+        Cons::new("int".to_symbol_or_panic(policy_base), Some(1u32.into())),
+        Cons::new(
+            "seq".to_symbol_or_panic(policy_base),
+            list!("a".to_string(), "b".to_string())
+        ),
+        Cons::new(
+            "b".to_symbol_or_panic(policy_base),
+            Sexp::from(
+                agent
+                    .resolve_name(&"true".to_symbol_or_panic(policy_base))
+                    .unwrap()
+            )
+        )
+    );
+    let deserialized = agent.reflect::<Test>(legacy).unwrap();
+    assert_eq!(original, deserialized);
 }
 
 #[test]
@@ -0,0 +1,283 @@
+mod common;
+
+use proptest::prelude::*;
+use proptest::test_runner::{Config, TestRunner};
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use amlang::builtins::generate_builtin_map;
+use amlang::env::LocalNode;
+use amlang::prelude::*;
+
+
+// Nodes without structure defined at the start of each generated env, which
+// generated values may reference.
+const NODE_COUNT: usize = 4;
+
+/// Description of a Primitive (or nesting of them) independent of the env
+/// it ends up in.
+#[derive(Clone, Debug)]
+enum Value {
+    Bool(bool),
+    Char(char),
+    Number(Number),
+    Symbol(String),
+    Str(String),
+    Node(usize),
+    BuiltIn(usize),
+    Path(String),
+    Procedure(Vec<usize>, usize),
+    SymNodeTable(Vec<(String, usize)>),
+    SymSexpTable(Vec<(String, Value)>),
+    LocalNodeTable(Vec<(usize, usize)>),
+    Vector(Vec<Value>),
+    List(Vec<Value>, Option<Box<Value>>),
+}
+
+struct Realizer {
+    // Env of referenced Nodes.
+    node_env: LocalNode,
+    // Env LocalNodeTables are in; they refer to their original env even
+    // after reloading.
+    table_env: LocalNode,
+    builtins: Vec<BuiltIn>,
+}
+
+impl Realizer {
+    fn node(&self, i: usize) -> Node {
+        Node::new(self.node_env, LocalNode::new(i as u64 + 1))
+    }
+
+    fn realize(&self, value: &Value) -> Sexp {
+        match value {
+            Value::Bool(b) => (*b).into(),
+            Value::Char(c) => (*c).into(),
+            Value::Number(num) => num.clone().into(),
+            Value::Symbol(s) => s.to_symbol_or_panic(policy_admin).into(),
+            Value::Str(s) => s.clone().into(),
+            Value::Node(i) => self.node(*i).into(),
+            Value::BuiltIn(i) => self.builtins[*i].clone().into(),
+            Value::Path(s) => LangPath::new(s.into()).into(),
+            Value::Procedure(params, body) => Procedure::UserAbstraction(
+                params.iter().map(|i| self.node(*i)).collect(),
+                self.node(*body),
+            )
+            .into(),
+            Value::SymNodeTable(entries) => {
+                let mut table = SymNodeTable::default();
+                for (k, v) in entries {
+                    table.insert(k.to_symbol_or_panic(policy_admin), self.node(*v));
+                }
+                table.into()
+            }
+            Value::SymSexpTable(entries) => {
+                let mut table = SymSexpTable::default();
+                for (k, v) in entries {
+                    table.insert(k.to_symbol_or_panic(policy_admin), self.realize(v));
+                }
+                table.into()
+            }
+            Value::LocalNodeTable(entries) => {
+                let mut table = LocalNodeTable::in_env(self.table_env);
+                for (k, v) in entries {
+                    table.insert(self.node(*k).local(), self.node(*v).local());
+                }
+                table.into()
+            }
+            Value::Vector(elems) => {
+                Vector::from(elems.iter().map(|e| self.realize(e)).collect::<Vec<_>>()).into()
+            }
+            Value::List(elems, tail) => {
+                let mut list = tail.as_ref().map(|t| Box::new(self.realize(t)));
+                if elems.is_empty() && list.is_none() {
+                    return Sexp::default();
+                }
+                for elem in elems.iter().rev() {
+                    list = Some(Box::new(
+                        Cons::new(Box::new(self.realize(elem)), list).into(),
+                    ));
+                }
+                *list.unwrap()
+            }
+        }
+    }
+}
+
+fn arb_number() -> impl Strategy<Value = Number> {
+    prop_oneof![
+        any::<i8>().prop_map(Number::I8),
+        any::<i16>().prop_map(Number::I16),
+        any::<i32>().prop_map(Number::I32),
+        any::<i64>().prop_map(Number::I64),
+        any::<isize>().prop_map(Number::ISize),
+        any::<u8>().prop_map(Number::U8),
+        any::<u16>().prop_map(Number::U16),
+        any::<u32>().prop_map(Number::U32),
+        any::<u64>().prop_map(Number::U64),
+        any::<usize>().prop_map(Number::USize),
+        any::<f32>().prop_map(Number::F32),
+        any::<f64>().prop_map(Number::F64),
+        any::<i128>().prop_map(Number::GenericInt),
+        "-?[1-9][0-9]{39,60}".prop_map(|s| s.parse::<Number>().unwrap()),
+        (any::<i64>(), 1..1000u32).prop_map(|(n, d)| format!("{}/{}", n, d).parse().unwrap()),
+    ]
+}
+
+fn arb_symbol() -> impl Strategy<Value = String> {
+    prop_oneof![
+        "[a-zA-Zλ!$%&*/:<=>?_~+-][a-zA-Z0-9λ≤!$%&*/:<=>?_~+.@^-]{0,6}",
+        // Symbols with meaning to the reader or serializer.
+        Just("quote".to_string()),
+        Just("unquote".to_string()),
+        Just("__embed".to_string()),
+        Just("__builtin".to_string()),
+        Just("lambda".to_string()),
+        Just("table-sym-node".to_string()),
+    ]
+    .prop_filter("symbol must not read as a number", |s| {
+        s.parse::<Number>().is_err()
+    })
+}
+
+fn arb_string() -> impl Strategy<Value = String> {
+    prop::collection::vec(
+        prop_oneof![
+            prop::char::range('a', 'z'),
+            prop::sample::select(vec![
+                ' ', '"', '\\', '\n', '\t', '\r', '(', ';', 'é', 'λ', '中'
+            ]),
+            // Control chars, which are escaped.
+            prop::char::range('\u{0}', '\u{1f}'),
+            Just('\u{7f}'),
+            // Astral plane chars, e.g. emoji.
+            prop::char::range('\u{10000}', '\u{10ffff}'),
+        ],
+        0..8,
+    )
+    .prop_map(|chars| chars.into_iter().collect())
+}
+
+fn arb_node() -> impl Strategy<Value = usize> {
+    0..NODE_COUNT
+}
+
+fn arb_value(builtin_count: usize) -> impl Strategy<Value = Value> {
+    let leaf = prop_oneof![
+        any::<bool>().prop_map(Value::Bool),
+        any::<char>().prop_map(Value::Char),
+        arb_number().prop_map(Value::Number),
+        arb_symbol().prop_map(Value::Symbol),
+        arb_string().prop_map(Value::Str),
+        arb_node().prop_map(Value::Node),
+        (0..builtin_count).prop_map(Value::BuiltIn),
+        arb_string().prop_map(Value::Path),
+        (prop::collection::vec(arb_node(), 0..3), arb_node())
+            .prop_map(|(params, body)| Value::Procedure(params, body)),
+        prop::collection::vec((arb_symbol(), arb_node()), 0..3).prop_map(Value::SymNodeTable),
+        prop::collection::vec((arb_node(), arb_node()), 0..3).prop_map(Value::LocalNodeTable),
+    ];
+    leaf.clone().prop_recursive(3, 32, 4, move |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..4).prop_map(Value::Vector),
+            // Tails are limited to leaves, since (a . (b)) is (a b).
+            (
+                prop::collection::vec(inner.clone(), 0..4),
+                prop::option::of(leaf.clone().prop_map(Box::new))
+            )
+                .prop_map(|(elems, tail)| Value::List(elems, tail)),
+            prop::collection::vec((arb_symbol(), inner), 0..3).prop_map(Value::SymSexpTable),
+        ]
+    })
+}
+
+#[test]
+fn env_round_trip() {
+    static CASE: AtomicUsize = AtomicUsize::new(0);
+
+    let (_lang_agent, manager) = common::setup().unwrap();
+    // TestRunner takes an Fn.
+    let manager = RefCell::new(manager);
+    let mut names = generate_builtin_map().into_iter().collect::<Vec<_>>();
+    names.sort_by_key(|(name, _)| *name);
+    let builtins = names.into_iter().map(|(_, b)| b).collect::<Vec<_>>();
+
+    let mut runner = TestRunner::new(Config::with_cases(64));
+    let strategy = prop::collection::vec(arb_value(builtins.len()), 1..6);
+    runner
+        .run(&strategy, |values| {
+            let mut manager = manager.borrow_mut();
+            let case = CASE.fetch_add(1, Ordering::SeqCst);
+            let path = std::env::temp_dir().join(format!(
+                "amlang_env_serde_{}_{}.env",
+                std::process::id(),
+                case
+            ));
+
+            let out_env = manager.insert_new_env(&path);
+            manager.agent_mut().jump_env(out_env);
+            for _ in 0..NODE_COUNT {
+                manager.agent_mut().define(None).unwrap();
+            }
+            let original = Realizer {
+                node_env: out_env,
+                table_env: out_env,
+                builtins: builtins.clone(),
+            };
+            let mut nodes = vec![];
+            for value in &values {
+                let structure = original.realize(value);
+                nodes.push(manager.agent_mut().define(Some(structure)).unwrap());
+            }
+            manager.serialize_curr_env(&path).unwrap();
+
+            let in_env = manager.insert_new_env(&path);
+            manager.agent_mut().jump_env(in_env);
+            let res = manager.deserialize_curr_env(&path);
+            std::fs::remove_file(&path).unwrap();
+            prop_assert!(res.is_ok(), "{:?}", res);
+
+            let reloaded = Realizer {
+                node_env: in_env,
+                table_env: out_env,
+                builtins: builtins.clone(),
+            };
+            for (value, node) in values.iter().zip(nodes) {
+                let loaded = manager
+                    .agent()
+                    .access_env(in_env)
+                    .unwrap()
+                    .entry(node.local())
+                    .owned();
+                // Debug distinguishes Number variants, unlike PartialEq.
+                prop_assert_eq!(
+                    format!("{:?}", loaded),
+                    format!("{:?}", Some(reloaded.realize(value)))
+                );
+            }
+            Ok(())
+        })
+        .unwrap();
+}
+
+#[test]
+fn env_header_version() {
+    let (_lang_agent, mut manager) = common::setup().unwrap();
+    let path = std::env::temp_dir().join(format!("amlang_env_header_{}.env", std::process::id()));
+
+    let env = manager.insert_new_env(&path);
+    manager.agent_mut().jump_env(env);
+    manager.agent_mut().define(None).unwrap();
+    manager.serialize_curr_env(&path).unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    assert!(contents.starts_with(
+        "(header (version . \"0.0.6\") (node-count . 2) (triple-count . 0))\n"
+    ));
+
+    // Files from newer versions are rejected rather than misread.
+    std::fs::write(&path, contents.replacen("0.0.6", "0.0.7", 1)).unwrap();
+    let in_env = manager.insert_new_env(&path);
+    manager.agent_mut().jump_env(in_env);
+    let res = manager.deserialize_curr_env(&path);
+    std::fs::remove_file(&path).unwrap();
+    assert!(res.is_err());
+}
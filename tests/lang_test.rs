@@ -1,11 +1,16 @@
 mod common;
 
+use std::cell::RefCell;
 use std::convert::TryFrom;
+use std::io::Cursor;
+use std::rc::Rc;
 
-use amlang::agent::TransformExecutor;
+use amlang::agent::{AgentIo, AmlangInterpreter, TransformExecutor};
 use amlang::env::LocalNode;
 use amlang::parser::Parser;
 use amlang::prelude::*;
+use amlang::primitive::symbol_policies::policy_env_serde;
+use amlang::span::SourceMap;
 use amlang::stream::input::StringReader;
 use amlang::token::Tokenizer;
 
@@ -52,6 +57,149 @@ fn basic_arithmetic() {
     assert_eq!(results, vec![0.into(), 1.into()]);
 }
 
+#[test]
+fn interpret_values() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+    let history_env = lang_agent.find_env("history.env").unwrap();
+    let history_size = |agent: &Agent| agent.access_env(history_env).unwrap().all_nodes().len();
+
+    // Atoms & lists are defined into the history env and executed, like
+    // any other structure.
+    let before = history_size(&lang_agent);
+    let results = eval(&mut lang_agent, "4 \"s\" '(1 2) '()");
+    assert_eq!(
+        results,
+        vec![
+            4.into(),
+            LangString::new("s").into(),
+            list!(1, 2),
+            Sexp::default(),
+        ]
+    );
+    assert_eq!(history_size(&lang_agent), before + 4);
+}
+
+#[test]
+fn numeric_tower() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    let results = eval(
+        &mut lang_agent,
+        "(+ 1 2.5)
+         (/ 1 3)
+         (* (/ 1 3) 3)
+         (mod -7 3)
+         (abs -4)
+         (max 1 5/2 2)
+         (min 3 -1.5)
+         (* 18446744073709551616 18446744073709551616)",
+    );
+    assert_eq!(results[0], 3.5.into());
+    assert_eq!(results[1].to_string(), "1/3");
+    assert_eq!(results[2], 1.into());
+    assert_eq!(results[3], 2.into());
+    assert_eq!(results[4], 4.into());
+    assert_eq!(results[5].to_string(), "5/2");
+    assert_eq!(results[6], (-1.5).into());
+    assert_eq!(
+        results[7].to_string(),
+        "340282366920938463463374607431768211456"
+    );
+}
+
+#[test]
+fn integer_division_is_exact() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    let results = eval(
+        &mut lang_agent,
+        "(/ 7 2)
+         (/ 6 3)
+         (/ -1 3)
+         (/ 7. 2)",
+    );
+    assert_eq!(results[0].to_string(), "7/2");
+    assert_eq!(results[1], 2.into());
+    assert_eq!(results[2].to_string(), "-1/3");
+    assert_eq!(results[3], 3.5.into());
+}
+
+#[test]
+fn numeric_comparison() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    let results = eval(
+        &mut lang_agent,
+        "(< 1 2 3)
+         (< 1 3 2)
+         (<= 1 1 2.5)
+         (> 1/2 1/3)
+         (>= 1 2)
+         (= 1 1.0 2/2)",
+    );
+    let expected: Vec<Sexp> = vec![true, false, true, true, false, true]
+        .into_iter()
+        .map(|b| b.into())
+        .collect();
+    assert_eq!(results, expected);
+}
+
+#[test]
+fn numeric_errors() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    let results = eval_with_errors(&mut lang_agent, "(/ 1 0) (mod 1 0)");
+    for result in &results {
+        let err = result.as_ref().unwrap_err().kind().reify(&lang_agent);
+        let (_, kind, _given) = break_sexp!(err => (Node, Node, Sexp)).unwrap();
+        assert_eq!(
+            kind,
+            context_node!(division_by_zero, lang_agent.error_context())
+        );
+    }
+
+    // The Number parser doesn't produce fixed-width ints, so reach them
+    // through a builtin returning usize.
+    let results = eval_with_errors(
+        &mut lang_agent,
+        "(- (list-len '()) 1)
+         (+ (list-len '(1)) 18446744073709551615)",
+    );
+    for result in &results {
+        let err = result.as_ref().unwrap_err().kind().reify(&lang_agent);
+        let (_, kind, _given) = break_sexp!(err => (Node, Node, Sexp)).unwrap();
+        assert_eq!(kind, context_node!(overflow, lang_agent.error_context()));
+    }
+}
+
+#[test]
+fn error_spans() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+    let map = SourceMap::shared();
+    let source = map.borrow_mut().add_source("spans");
+    lang_agent.set_source_map(map.clone());
+
+    let results = pull_transform!(?unwrap
+                                  StringReader::new("(+ 1\n   (/ 2 0))")
+                                  =>> Tokenizer::new(policy_base).with_source(map.clone(), source)
+                                  =>. Parser::new().with_source(map.clone(), source)
+                                  =>. TransformExecutor::interpret(&mut lang_agent))
+    .collect::<Vec<_>>();
+
+    // Frames of the error point back at each enclosing application.
+    let err = results[0].as_ref().unwrap_err();
+    let map = map.borrow();
+    let excerpts = err
+        .cont()
+        .unwrap()
+        .iter()
+        .filter_map(|frame| map.node_span(frame.context()))
+        .map(|span| map.render(&span).unwrap())
+        .collect::<Vec<_>>();
+    assert!(excerpts.contains(&" --> spans:2:4\n  |\n2 |    (/ 2 0))\n  |    ^^^^^^^".to_string()));
+    assert!(excerpts.contains(&" --> spans:1:1\n  |\n1 | (+ 1\n  | ^^^^ ...".to_string()));
+}
+
 #[test]
 fn lambda_param_node_body() {
     let (mut lang_agent, _manager) = common::setup().unwrap();
@@ -77,12 +225,10 @@ fn lambda_seq_body() {
     let results = eval(&mut lang_agent, "((lambda (a) (jump a) (curr)) lambda)");
     assert_eq!(
         results,
-        vec![
-            lang_agent
-                .resolve_name(&"lambda".to_symbol_or_panic(policy_base))
-                .unwrap()
-                .into()
-        ]
+        vec![lang_agent
+            .resolve_name(&"lambda".to_symbol_or_panic(policy_base))
+            .unwrap()
+            .into()]
     );
 }
 
@@ -122,9 +268,12 @@ fn lambda_duplicate_argname() {
 
     let results = eval_with_errors(&mut lang_agent, "(lambda (a a) (+ a a))");
 
-    let err = results[0].as_ref().unwrap_err().kind().reify();
-    let (_, kind, _) = break_sexp!(err => (LangString, LangString; remainder)).unwrap();
-    assert_eq!(kind.as_str(), "InvalidArgument");
+    let err = results[0].as_ref().unwrap_err().kind().reify(&lang_agent);
+    let (_, kind, _) = break_sexp!(err => (Node, Node; remainder)).unwrap();
+    assert_eq!(
+        kind,
+        context_node!(invalid_argument, lang_agent.error_context())
+    );
 }
 
 #[test]
@@ -172,20 +321,8 @@ fn let_rec_lambdas() {
     );
 
     let cons = Cons::try_from(results[0].clone()).unwrap();
-    assert_eq!(
-        *cons.car().unwrap(),
-        lang_agent
-            .resolve_name(&"false".to_symbol_or_panic(policy_base))
-            .unwrap()
-            .into()
-    );
-    assert_eq!(
-        *cons.cdr().unwrap(),
-        lang_agent
-            .resolve_name(&"true".to_symbol_or_panic(policy_base))
-            .unwrap()
-            .into()
-    );
+    assert_eq!(*cons.car().unwrap(), false.into());
+    assert_eq!(*cons.cdr().unwrap(), true.into());
 }
 
 #[test]
@@ -367,13 +504,7 @@ fn improper_list() {
     let (mut lang_agent, _manager) = common::setup().unwrap();
 
     let results = eval(&mut lang_agent, "(eq '(1 2 . 3) (cons 1 (cons 2 3)))");
-    assert_eq!(
-        results[0],
-        lang_agent
-            .resolve_name(&"true".to_symbol_or_panic(policy_base))
-            .unwrap()
-            .into()
-    );
+    assert_eq!(results[0], true.into());
 }
 
 #[test]
@@ -417,14 +548,8 @@ fn import() {
          (eq lambda (import lambda))",
     );
 
-    let t = lang_agent
-        .resolve_name(&"true".to_symbol_or_panic(policy_base))
-        .unwrap()
-        .into();
-    let f = lang_agent
-        .resolve_name(&"false".to_symbol_or_panic(policy_base))
-        .unwrap()
-        .into();
+    let t: Sexp = true.into();
+    let f: Sexp = false.into();
     assert_eq!(results[1], t);
     assert_eq!(results[2], t);
     assert_eq!(results[3], f);
@@ -450,13 +575,37 @@ fn tell_dupe() {
          (tell a is a)",
     );
 
-    let err = results[3].as_ref().unwrap_err().kind().reify();
-    let (_, kind, _triple) = break_sexp!(err => (LangString, LangString, Sexp)).unwrap();
-    assert_eq!(kind.as_str(), "DuplicateTriple");
+    let err = results[3].as_ref().unwrap_err().kind().reify(&lang_agent);
+    let (_, kind, _triple) = break_sexp!(err => (Node, Node, Sexp)).unwrap();
+    assert_eq!(
+        kind,
+        context_node!(duplicate_triple, lang_agent.error_context())
+    );
+
+    let err = results[7].as_ref().unwrap_err().kind().reify(&lang_agent);
+    let (_, kind, _triple) = break_sexp!(err => (Node, Node, Sexp)).unwrap();
+    assert_eq!(
+        kind,
+        context_node!(duplicate_triple, lang_agent.error_context())
+    );
+}
 
-    let err = results[7].as_ref().unwrap_err().kind().reify();
-    let (_, kind, _triple) = break_sexp!(err => (LangString, LangString, Sexp)).unwrap();
-    assert_eq!(kind.as_str(), "DuplicateTriple");
+#[test]
+fn tell_to() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+    let pos = lang_agent.pos();
+    let impl_env = lang_agent.find_env("impl.env").unwrap();
+
+    let a = lang_agent.define_to(impl_env, None).unwrap();
+    let related_to = lang_agent.define_to(impl_env, None).unwrap();
+    let triple = lang_agent.tell_to(impl_env, a, related_to, a).unwrap();
+    assert_eq!(triple.env(), impl_env);
+    assert_eq!(lang_agent.pos(), pos);
+    // Inserted into the target env rather than the current one.
+    let found = lang_agent
+        .ask_from(impl_env, Some(a), Some(related_to), Some(a))
+        .unwrap();
+    assert_eq!(found.triples().count(), 1);
 }
 
 /*
@@ -516,3 +665,657 @@ fn env_find() {
     let lang_env = lang_agent.find_env("lang.env").unwrap();
     assert_eq!(results[0], Node::new(LocalNode::default(), lang_env).into());
 }
+
+#[test]
+fn quasiquote() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    let results = eval(
+        &mut lang_agent,
+        "(def b 2)
+         `(a ,b ,@(cons 3 (cons 4 '())) ,(+ b b))
+         `(a `(b ,(c ,b)))
+         (def xs '(3))
+         (let ((cons 5) (quote 6) (append 7)) `(a ,(+ 1 1) ,@xs))",
+    );
+    let expected = Sexp::parse_with("(a 2 3 4 4)", policy_base).unwrap();
+    assert_eq!(results[1], expected);
+    let expected = Sexp::parse_with("(a `(b ,(c 2)))", policy_base).unwrap();
+    assert_eq!(results[2], expected);
+    // Expansion is unaffected by local bindings.
+    let expected = Sexp::parse_with("(a 2 3)", policy_base).unwrap();
+    assert_eq!(results[4], expected);
+}
+
+#[test]
+fn defmacro() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    let results = eval(
+        &mut lang_agent,
+        "(defmacro unless (c a b) `(if ,c ,b ,a))
+         (unless (eq 1 2) 3 4)
+         (defmacro swap-args (form) `(,(car form) ,@(cdr (cdr form)) ,(car (cdr form))))
+         (swap-args (- 1 5))",
+    );
+    assert_eq!(results[1], 3.into());
+    assert_eq!(results[3], 4.into());
+}
+
+#[test]
+fn pattern_match() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    let results = eval(
+        &mut lang_agent,
+        "(match 2 (1 'one) (2 'two) (_ 'other))
+         (match (cons 1 (cons 2 '())) ((a b) (+ a b)))
+         (match '(1 (2 3) 4) ((1 (x y) . rest) (cons (+ x y) rest)))
+         (match '() ((a . b) 'cons) (() 'empty))
+         (match '(a b) (('a x) x) (_ 'none))
+         (match (eq 1 1) (,false 1) (,true 2))
+         (match 5 (n n (+ n n)))
+         ((lambda (car) (match '(1 2) ((x y) y))) 7)
+         ((lambda (let) (match 3 (x x))) 7)",
+    );
+    assert_eq!(results[0], "two".to_symbol_or_panic(policy_base).into());
+    assert_eq!(results[1], 3.into());
+    let expected = Sexp::parse_with("(5 4)", policy_base).unwrap();
+    assert_eq!(results[2], expected);
+    assert_eq!(results[3], "empty".to_symbol_or_panic(policy_base).into());
+    assert_eq!(results[4], "b".to_symbol_or_panic(policy_base).into());
+    assert_eq!(results[5], 2.into());
+    assert_eq!(results[6], 10.into());
+    // Expansion is unaffected by local bindings.
+    assert_eq!(results[7], 2.into());
+    assert_eq!(results[8], 3.into());
+}
+
+#[test]
+fn pattern_match_non_exhaustive() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    let results = eval_with_errors(&mut lang_agent, "(match '(1 2) ((a) a) (() 0))");
+    let err = results[0].as_ref().unwrap_err().kind().reify(&lang_agent);
+    let (_, kind, given) = break_sexp!(err => (Node, Node, Sexp)).unwrap();
+    assert_eq!(
+        kind,
+        context_node!(non_exhaustive_match, lang_agent.error_context())
+    );
+    let (field, value) = break_sexp!(given => (Node, Sexp)).unwrap();
+    assert_eq!(field, context_node!(given, lang_agent.error_context()));
+    assert_eq!(value, Sexp::parse_with("(1 2)", policy_base).unwrap());
+}
+
+#[test]
+fn booleans() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    let results = eval(
+        &mut lang_agent,
+        "#t
+         (if #f 1 2)
+         (if '() 1 2)
+         (if false 1 2)
+         (not 0)
+         (not #f)",
+    );
+    let expected: Vec<Sexp> = vec![
+        true.into(),
+        2.into(),
+        1.into(),
+        2.into(),
+        false.into(),
+        true.into(),
+    ];
+    assert_eq!(results, expected);
+}
+
+#[test]
+fn connectives() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    let results = eval(
+        &mut lang_agent,
+        "(and)
+         (and 1 2)
+         (and 1 #f (car 1))
+         (or)
+         (or #f 3)
+         (or (eq 1 2) #f)
+         (or 1 (car 1))
+         ((lambda (if) (and 1 2)) 7)
+         ((lambda (if let) (or #f 3)) 7 8)",
+    );
+    let expected: Vec<Sexp> = vec![
+        true.into(),
+        2.into(),
+        false.into(),
+        false.into(),
+        3.into(),
+        false.into(),
+        1.into(),
+        // Unaffected by local bindings.
+        2.into(),
+        3.into(),
+    ];
+    assert_eq!(results, expected);
+}
+
+#[test]
+fn vectors() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    let results = eval(
+        &mut lang_agent,
+        "[1 a (b)]
+         '[]
+         (vector (+ 1 1) 'a)
+         (vector-ref [1 2 3] 1)
+         (vector-len [1 2 3])
+         (vector->list [1 [2]])
+         (list->vector '(1 2))
+         (vector-slice [1 2 3 4] 1 3)
+         (def v [1 2 3])
+         (vector-set! v 0 (+ 4 5))
+         v
+         (vector-ref v 0)
+         ((lambda (w) (vector-ref w 2)) v)
+         (vector-ref (vector 'c 'd) 1)",
+    );
+    let a = Sexp::from("a".to_symbol_or_panic(policy_base));
+    let b = Sexp::from("b".to_symbol_or_panic(policy_base));
+    let vector = |elems: Vec<Sexp>| Sexp::from(Vector::from(elems));
+    assert_eq!(results[0], vector(vec![1.into(), a.clone(), list!(b)]));
+    assert_eq!(results[1], vector(vec![]));
+    assert_eq!(results[2], vector(vec![2.into(), a]));
+    assert_eq!(results[3], 2.into());
+    assert_eq!(results[4], Number::USize(3).into());
+    assert_eq!(results[5], list!(1, vector(vec![2.into()])));
+    assert_eq!(results[6], vector(vec![1.into(), 2.into()]));
+    assert_eq!(results[7], vector(vec![2.into(), 3.into()]));
+    assert_eq!(results[10], vector(vec![9.into(), 2.into(), 3.into()]));
+    assert_eq!(results[11], 9.into());
+    assert_eq!(results[12], 3.into());
+    assert_eq!(results[13], "d".to_symbol_or_panic(policy_base).into());
+}
+
+#[test]
+fn vector_errors() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    let results = eval_with_errors(
+        &mut lang_agent,
+        "(vector-ref [1 2] 2)
+         (vector-ref 1 0)
+         (vector-slice [1 2] 2 1)
+         (vector-set! 1 0 0)",
+    );
+    assert_eq!(results.len(), 4);
+    assert!(results.iter().all(|res| res.is_err()));
+    assert_eq!(
+        results[2].as_ref().unwrap_err().kind().to_string(),
+        "invalid argument 1; expected end index in [2, 2]"
+    );
+
+    let results = eval_with_errors(&mut lang_agent, "[1 2)");
+    assert!(results[0].is_err());
+}
+
+#[test]
+fn vector_env_round_trip() {
+    let (_lang_agent, mut manager) = common::setup().unwrap();
+
+    let path = std::env::temp_dir().join(format!(
+        "amlang_vector_round_trip_{}.env",
+        std::process::id()
+    ));
+    let vector: Sexp = Vector::from(vec![
+        1.into(),
+        "s".into(),
+        "a".to_symbol_or_panic(policy_base).into(),
+        Vector::from(vec![true.into()]).into(),
+        list!(2, 3),
+    ])
+    .into();
+
+    let out_env = manager.insert_new_env(&path);
+    manager.agent_mut().jump_env(out_env);
+    let node = manager.agent_mut().define(Some(vector.clone())).unwrap();
+    manager.serialize_curr_env(&path).unwrap();
+
+    let in_env = manager.insert_new_env(&path);
+    manager.agent_mut().jump_env(in_env);
+    manager.deserialize_curr_env(&path).unwrap();
+    let loaded = manager
+        .agent()
+        .designate(Node::new(in_env, node.local()).into())
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, vector);
+}
+
+#[test]
+fn strings() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    let results = eval(
+        &mut lang_agent,
+        r#"(string-append "ab" "c" "")
+           (string-len "héllo")
+           (string-find "héllo" "llo")
+           (string-find "hello" "z")
+           (string-split "a,b,,c" ",")
+           (string-join '("a" "b") "-")
+           (substring "héllo" 1 3)
+           (string-upcase "abc")
+           (string-downcase "ABC")
+           (string-trim "  x ")
+           (number->string (/ 1 2))
+           (string->number "42")
+           (string->number "x")
+           (symbol->string 'abc)
+           (string->symbol "abc")"#,
+    );
+    let expected: Vec<Sexp> = vec![
+        "abc".into(),
+        Number::USize(5).into(),
+        Number::USize(2).into(),
+        false.into(),
+        list!("a", "b", "", "c"),
+        "a-b".into(),
+        "él".into(),
+        "ABC".into(),
+        "abc".into(),
+        "x".into(),
+        "1/2".into(),
+        42.into(),
+        false.into(),
+        "abc".into(),
+        "abc".to_symbol_or_panic(policy_base).into(),
+    ];
+    assert_eq!(results, expected);
+
+    let results = eval_with_errors(
+        &mut lang_agent,
+        r#"(string-append "a" 1)
+           (substring "abc" 2 1)
+           (string->symbol "1a")"#,
+    );
+    assert!(results.iter().all(|res| res.is_err()));
+}
+
+#[test]
+fn chars() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    let results = eval(
+        &mut lang_agent,
+        r#"(string-ref "aλ😀" 2)
+           (= (char->integer #\a) 97)
+           (integer->char 955)
+           (eq #\space (string-ref " " 0))
+           '(#\a "\u{1F600}")"#,
+    );
+    assert_eq!(
+        results,
+        vec![
+            '😀'.into(),
+            true.into(),
+            'λ'.into(),
+            true.into(),
+            list!('a', "😀"),
+        ]
+    );
+
+    let results = eval_with_errors(&mut lang_agent, "(integer->char 55296) (string-ref \"\" 0)");
+    assert!(results.iter().all(|res| res.is_err()));
+}
+
+#[test]
+fn regexes() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    let results = eval(
+        &mut lang_agent,
+        r#"(regex-match "(\\w+)@(\\w+)?" "mail: me@")
+           (regex-match "\\d" "abc")
+           (regex-replace "(\\d+)" "a1b22" "<$1>")"#,
+    );
+    let expected: Vec<Sexp> = vec![
+        list!("me@", "me", Sexp::default()),
+        false.into(),
+        "a<1>b<22>".into(),
+    ];
+    assert_eq!(results, expected);
+
+    let results = eval_with_errors(&mut lang_agent, r#"(regex-match "(" "")"#);
+    assert!(results[0].is_err());
+}
+
+#[test]
+fn tables() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    let results = eval(
+        &mut lang_agent,
+        "(def t (table-sym-sexp (a (+ 1 2)) (b '(x))))
+         (table-lookup t 'a)
+         (table-lookup t 'c)
+         (table-insert! t 'c \"c\")
+         (table-remove! t 'a)
+         (table-contains t 'a)
+         (table-keys t)
+         (table-len t)
+         (table->list t)
+         (def n (table-sym-node (self car)))
+         (table-lookup n 'self)",
+    );
+    let symbol = |s: &str| Sexp::from(s.to_symbol_or_panic(policy_base));
+    assert_eq!(results[1], 3.into());
+    assert_eq!(results[2], Sexp::default());
+    assert_eq!(results[5], false.into());
+    assert_eq!(results[6], list!(symbol("b"), symbol("c")));
+    assert_eq!(results[7], Number::USize(2).into());
+    assert_eq!(
+        results[8],
+        list!(
+            list!(symbol("b"), list!(symbol("x"))),
+            list!(symbol("c"), "c")
+        )
+    );
+    let car = lang_agent
+        .resolve_name(&"car".to_symbol_or_panic(policy_base))
+        .unwrap();
+    assert_eq!(results[10], car.into());
+
+    let results = eval(
+        &mut lang_agent,
+        "(def l (table-lnode (curr) ((curr) (curr))))
+         (eq (table-lookup l (curr)) (curr))
+         (table-remove! l (curr))
+         (table-len l)",
+    );
+    assert_eq!(results[1], true.into());
+    assert_eq!(results[3], Number::USize(0).into());
+
+    let results = eval_with_errors(
+        &mut lang_agent,
+        "(table-lookup 1 'a)
+         (table-lookup (table-sym-sexp) 1)
+         (table-insert! car 'a 1)
+         (table-sym-sexp (1 2))
+         (table-lnode (curr) (car car))
+         (table-lnode (curr) ((curr) car))
+         (table-insert! l car (curr))
+         (table-insert! l (curr) car)
+         (table-lookup l car)
+         (table-contains l car)
+         (table-remove! l car)",
+    );
+    assert_eq!(results.len(), 11);
+    assert!(results.iter().all(|res| res.is_err()));
+}
+
+#[test]
+fn table_env_round_trip() {
+    let (_lang_agent, mut manager) = common::setup().unwrap();
+
+    let path = std::env::temp_dir().join("amlang_table_round_trip.env");
+    let out_env = manager.insert_new_env(&path);
+    manager.agent_mut().jump_env(out_env);
+
+    let target = manager.agent_mut().define(Some("target".into())).unwrap();
+    let mut sym_node = SymNodeTable::default();
+    sym_node.insert("a".to_symbol_or_panic(policy_base), target);
+    let mut sym_sexp = SymSexpTable::default();
+    sym_sexp.insert("a".to_symbol_or_panic(policy_base), 1.into());
+    sym_sexp.insert("b".to_symbol_or_panic(policy_base), list!("x", 2));
+    sym_sexp.insert("c".to_symbol_or_panic(policy_base), "s".into());
+    sym_sexp.insert("d".to_symbol_or_panic(policy_base), target.into());
+    let mut local_node = LocalNodeTable::in_env(out_env);
+    local_node.insert(target.local(), target.local());
+
+    let tables: Vec<Sexp> = vec![sym_node.into(), sym_sexp.into(), local_node.into()];
+    let mut nodes = vec![];
+    for table in &tables {
+        nodes.push(manager.agent_mut().define(Some(table.clone())).unwrap());
+    }
+    manager.serialize_curr_env(&path).unwrap();
+
+    let in_env = manager.insert_new_env(&path);
+    manager.agent_mut().jump_env(in_env);
+    manager.deserialize_curr_env(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // Nodes within the tables now point into in_env.
+    let relocate = |node: Node| Node::new(in_env, node.local());
+    let mut sym_node = SymNodeTable::default();
+    sym_node.insert("a".to_symbol_or_panic(policy_base), relocate(target));
+    let mut sym_sexp = SymSexpTable::default();
+    sym_sexp.insert("a".to_symbol_or_panic(policy_base), 1.into());
+    sym_sexp.insert("b".to_symbol_or_panic(policy_base), list!("x", 2));
+    sym_sexp.insert("c".to_symbol_or_panic(policy_base), "s".into());
+    sym_sexp.insert("d".to_symbol_or_panic(policy_base), relocate(target).into());
+    let mut local_node = LocalNodeTable::in_env(out_env);
+    local_node.insert(target.local(), target.local());
+    let expected: Vec<Sexp> = vec![sym_node.into(), sym_sexp.into(), local_node.into()];
+
+    for (node, expected) in nodes.into_iter().zip(expected) {
+        let loaded = manager
+            .agent()
+            .designate(Node::new(in_env, node.local()).into())
+            .unwrap();
+        assert_eq!(loaded, expected);
+    }
+}
+
+#[test]
+fn print_settings() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    let results = eval(
+        &mut lang_agent,
+        "(set-print-setting! 'color false)
+         (set-print-setting! 'max-width 40)
+         (table-lookup (print-settings) 'max-width)
+         '(def fact (lambda (n) (if (< n 2) 1 (* n (fact (- n 1))))))
+         '(alpha beta gamma delta epsilon zeta eta theta iota kappa)
+         [(a 1) (b 2) (c 3) (d 4) (e 5) (f 6) (g 7) (h 8)]",
+    );
+    assert_eq!(results[2], Number::USize(40).into());
+
+    let write = |agent: &Agent, sexp: &Sexp| {
+        let mut buf = vec![];
+        agent.write_sexp(&mut buf, sexp).unwrap();
+        String::from_utf8(buf).unwrap()
+    };
+    assert_eq!(
+        write(&lang_agent, &results[3]),
+        "(def fact
+  (lambda (n)
+    (if (< n 2) 1 (* n (fact (- n 1))))))"
+    );
+    assert_eq!(
+        write(&lang_agent, &results[4]),
+        "(alpha beta gamma delta epsilon zeta eta
+  theta iota kappa)"
+    );
+    assert_eq!(
+        write(&lang_agent, &results[5]),
+        "[(a 1) (b 2) (c 3) (d 4) (e 5) (f 6)
+ (g 7) (h 8)]"
+    );
+
+    eval(
+        &mut lang_agent,
+        "(set-print-setting! 'max-width '())
+         (set-print-setting! 'max-length 3)",
+    );
+    assert_eq!(write(&lang_agent, &results[4]), "(alpha beta gamma ...)");
+
+    let results = eval_with_errors(
+        &mut lang_agent,
+        "(set-print-setting! 'colour false)
+         (set-print-setting! 'indent -1)",
+    );
+    for result in &results {
+        let err = result.as_ref().unwrap_err().kind().reify(&lang_agent);
+        let (_, kind, _) = break_sexp!(err => (Node, Node; remainder)).unwrap();
+        assert_eq!(
+            kind,
+            context_node!(invalid_argument, lang_agent.error_context())
+        );
+    }
+}
+
+#[test]
+fn io() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+    let output = Rc::new(RefCell::new(Vec::<u8>::new()));
+    let input = Rc::new(RefCell::new(Cursor::new(
+        "first line\n(a\n  b) (c)\n".to_string(),
+    )));
+    lang_agent.set_io(
+        AgentIo::default()
+            .with_output(output.clone())
+            .with_input(input.clone()),
+    );
+    let take_output = || String::from_utf8(output.replace(vec![])).unwrap();
+    let symbol = |s: &str| Sexp::from(s.to_symbol_or_panic(policy_base));
+
+    eval(
+        &mut lang_agent,
+        "(set-print-setting! 'color false)
+         (print 1)
+         (print '(a b))
+         (println \"s\")",
+    );
+    assert_eq!(take_output(), "1(a b)\"s\"\n");
+
+    let results = eval(&mut lang_agent, "(read-line) (read) (read) (read-line)");
+    assert_eq!(results[0], "first line".into());
+    assert_eq!(results[1], list!(symbol("a"), symbol("b")));
+    // Forms following the one read on its last line are kept for later.
+    assert_eq!(results[2], list!(symbol("c")));
+    assert_eq!(results[3], Sexp::default());
+
+    *input.borrow_mut() = Cursor::new("(a b) (c)".to_string());
+    let results = eval(&mut lang_agent, "(read) (read) (read)");
+    assert_eq!(results[0], list!(symbol("a"), symbol("b")));
+    assert_eq!(results[1], list!(symbol("c")));
+    assert_eq!(results[2], Sexp::default());
+
+    *input.borrow_mut() = Cursor::new("(unclosed\n".to_string());
+    let results = eval_with_errors(&mut lang_agent, "(read)");
+    assert!(results[0].is_err());
+
+    // Write errors are reported by print & println alike.
+    struct Closed;
+    impl std::io::Write for Closed {
+        fn write(&mut self, _buf: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::ErrorKind::BrokenPipe.into())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    lang_agent.set_io(AgentIo::default().with_output(Rc::new(RefCell::new(Closed))));
+    let results = eval_with_errors(&mut lang_agent, "(print 1) (println 1)");
+    assert!(results.iter().all(Result::is_err));
+
+    // Error traces go to the output as well.
+    lang_agent.set_io(AgentIo::default().with_output(output.clone()));
+    let results = eval_with_errors(&mut lang_agent, "((lambda (x) (car x)) 1)");
+    lang_agent.trace_error(results[0].as_ref().unwrap_err());
+    assert!(take_output().contains("--TRACE--"));
+}
+
+#[test]
+fn param_names() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+
+    eval(
+        &mut lang_agent,
+        "(def f (lambda (first second) second))
+         (defmacro m (a rest) a)",
+    );
+    // As the REPL's CliHelper finds them.
+    let names = |name: &str| {
+        let node = lang_agent
+            .resolve_name(&name.to_symbol_or_panic(policy_base))
+            .unwrap();
+        let proc = Procedure::try_from(lang_agent.concretize(node).unwrap()).unwrap();
+        AmlangInterpreter::param_names(&lang_agent, &proc)
+            .unwrap()
+            .into_iter()
+            .map(|name| name.as_str().to_string())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(names("f"), vec!["first", "second"]);
+    assert_eq!(names("m"), vec!["a", "rest"]);
+}
+
+#[test]
+fn node_references() {
+    let (mut lang_agent, _manager) = common::setup().unwrap();
+    // As in the REPL's admin mode.
+    let admin_eval = |agent: &mut Agent, s: &str| {
+        pull_transform!(?unwrap
+                        StringReader::new(s)
+                        =>> Tokenizer::new(policy_env_serde)
+                        =>. Parser::new()
+                        =>. TransformExecutor::interpret(agent))
+        .collect::<Vec<_>>()
+    };
+
+    let results = eval(&mut lang_agent, "(def a 4) (def r) (tell a r a)");
+    let reference = format!("^{}", Node::try_from(results[0].clone()).unwrap().local().id());
+    // Resolved by name unless enabled, whatever policy made the symbol.
+    let symbol = reference.to_symbol_or_panic(policy_env_serde);
+    assert!(lang_agent.designate(symbol.clone().into()).is_err());
+    lang_agent.set_node_references(true);
+    assert_eq!(lang_agent.designate(symbol.into()).unwrap(), results[0]);
+
+    let a = Node::try_from(results[0].clone()).unwrap();
+    let r = Node::try_from(results[1].clone()).unwrap();
+    let env = lang_agent.env();
+    let triple = env.match_triple(a.local(), r.local(), a.local());
+    let triple_index = env.triple_index(triple.triples().next().unwrap());
+
+    let car = lang_agent
+        .resolve_name(&"car".to_symbol_or_panic(policy_base))
+        .unwrap();
+    let input = format!(
+        "^{} (+ ^{} 1) ^{}^{} (car ^t{}) (car ^{}^t{})",
+        a.local().id(),
+        a.local().id(),
+        car.env().id(),
+        car.local().id(),
+        triple_index,
+        a.env().id(),
+        triple_index,
+    );
+    let results = admin_eval(&mut lang_agent, &input);
+    assert_eq!(*results[0].as_ref().unwrap(), 4.into());
+    assert_eq!(*results[1].as_ref().unwrap(), 5.into());
+    assert_eq!(
+        *results[2].as_ref().unwrap(),
+        lang_agent.concretize(car).unwrap()
+    );
+    assert_eq!(*results[3].as_ref().unwrap(), a.into());
+    assert_eq!(*results[4].as_ref().unwrap(), a.into());
+
+    let results = admin_eval(&mut lang_agent, "^99999 ^99999^1 ^99999^t1");
+    for result in results {
+        let err = result.as_ref().unwrap_err().kind().reify(&lang_agent);
+        let (_, kind, _) = break_sexp!(err => (Node, Node; remainder)).unwrap();
+        assert_eq!(
+            kind,
+            context_node!(unbound_symbol, lang_agent.error_context())
+        );
+    }
+
+    // Only admin policies accept references.
+    assert!(Sexp::parse_with("^1", policy_base).is_err());
+}
@@ -0,0 +1,212 @@
+mod common;
+
+use std::convert::TryFrom;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use amlang::agent::script;
+use amlang::env::LocalNode;
+use amlang::prelude::*;
+use amlang::stream::input::StringReader;
+
+
+// Fresh directory of Amlang files, removed on drop.
+struct Files {
+    dir: PathBuf,
+}
+
+impl Files {
+    fn new(name: &str, files: &[(&str, &str)]) -> Self {
+        let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for (file, contents) in files {
+            fs::write(dir.join(file), contents).unwrap();
+        }
+        Self {
+            dir: dir.canonicalize().unwrap(),
+        }
+    }
+
+    fn path(&self, file: &str) -> PathBuf {
+        self.dir.join(file)
+    }
+}
+
+impl Drop for Files {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn run(agent: &mut Agent, source: &str) -> Result<Sexp, Error> {
+    script::run(agent, "<test>", StringReader::new(source))
+}
+
+fn quoted(path: &Path) -> String {
+    format!("\"{}\"", path.display())
+}
+
+fn resolves(agent: &Agent, name: &str) -> Option<Node> {
+    agent
+        .resolve_name(&name.to_symbol_or_panic(policy_base))
+        .ok()
+}
+
+
+#[test]
+fn load() {
+    let (mut agent, _manager) = common::setup().unwrap();
+    let files = Files::new(
+        "module_test_load",
+        &[
+            (
+                "main.aml",
+                "(def load-a 1)\n(load \"dep.aml\")\n(+ load-a load-b)\n",
+            ),
+            ("dep.aml", "(def load-b 2)\n"),
+            ("cycle.aml", "(load \"cycle.aml\")\n"),
+        ],
+    );
+
+    let result = run(
+        &mut agent,
+        &format!("(load {})", quoted(&files.path("main.aml"))),
+    );
+    assert_eq!(result.unwrap(), 3.into());
+    // Loaded into the current env.
+    let pos = agent.pos().env();
+    assert_eq!(resolves(&agent, "load-b").unwrap().env(), pos);
+
+    let result = run(
+        &mut agent,
+        &format!("(load {})", quoted(&files.path("cycle.aml"))),
+    );
+    let err = result.as_ref().unwrap_err().kind().reify(&agent);
+    let (_, kind, _) = break_sexp!(err => (Node, Node; remainder)).unwrap();
+    assert_eq!(kind, context_node!(invalid_state, agent.error_context()));
+
+    assert!(run(&mut agent, "(load \"/no/such/file.aml\")").is_err());
+    assert!(run(&mut agent, "(load 1)").is_err());
+}
+
+#[test]
+fn module() {
+    let (mut agent, manager) = common::setup().unwrap();
+    let files = Files::new(
+        "module_test_module",
+        &[(
+            "geometry.aml",
+            "(def pi 3)\n(def area (lambda (r) (* pi (* r r))))\n",
+        )],
+    );
+    let path = files.path("geometry.aml");
+
+    let env = run(&mut agent, &format!("(module {})", quoted(&path))).unwrap();
+    let env = Node::try_from(env).unwrap();
+    assert_eq!(env.env(), LocalNode::default());
+    let env = env.local();
+    assert_eq!(agent.module_env(&path), Some(env));
+    assert!(agent
+        .env_paths()
+        .iter()
+        .any(|(node, path)| *node == env && path.as_std_path().ends_with("geometry.env")));
+
+    // Exports are designated in the module env, and usable from the importer.
+    assert_eq!(resolves(&agent, "area").unwrap().env(), env);
+    assert_eq!(run(&mut agent, "(area 2)").unwrap(), 12.into());
+    let working_env = agent.pos().env();
+    let area = resolves(&agent, "area").unwrap();
+    assert_eq!(
+        agent.get_imported(area, working_env).unwrap().env(),
+        working_env
+    );
+
+    // Repeated loads are cached until the source changes.
+    let again = run(&mut agent, &format!("(module {})", quoted(&path))).unwrap();
+    assert_eq!(again, Node::new(LocalNode::default(), env).into());
+    fs::write(&path, "(def pi 4)\n").unwrap();
+    let later = SystemTime::now() + Duration::from_secs(10);
+    File::options()
+        .write(true)
+        .open(&path)
+        .unwrap()
+        .set_modified(later)
+        .unwrap();
+    let reloaded = run(&mut agent, &format!("(module {})", quoted(&path))).unwrap();
+    let reloaded = Node::try_from(reloaded).unwrap().local();
+    assert_ne!(reloaded, env);
+    assert_eq!(agent.module_env(&path), Some(reloaded));
+    assert_eq!(agent.module_envs(&path), vec![env, reloaded]);
+    assert_eq!(run(&mut agent, "pi").unwrap(), 4.into());
+
+    // Envs created by forks are shared with their EnvManager.
+    assert!(manager.agent().access_env(env).is_some());
+    assert!(manager.agent().access_env(reloaded).is_some());
+}
+
+#[test]
+fn module_isolation() {
+    let (mut agent, _manager) = common::setup().unwrap();
+    let files = Files::new(
+        "module_test_isolation",
+        &[("uses_importer.aml", "(def iso-b iso-a)\n")],
+    );
+
+    run(&mut agent, "(def iso-a 1)").unwrap();
+    let dchain = agent.designation_chain().clone();
+    let pos = agent.pos();
+    let result = run(
+        &mut agent,
+        &format!("(module {})", quoted(&files.path("uses_importer.aml"))),
+    );
+    assert!(result.is_err());
+    assert_eq!(agent.pos(), pos);
+    assert_eq!(*agent.designation_chain(), dchain);
+    assert!(resolves(&agent, "iso-b").is_none());
+}
+
+#[test]
+fn module_failure() {
+    let (mut agent, _manager) = common::setup().unwrap();
+    let files = Files::new(
+        "module_test_failure",
+        &[("broken.aml", "(def broken-a 1)\n(car 1)\n")],
+    );
+    let path = files.path("broken.aml");
+
+    let env_paths = agent.env_paths();
+    assert!(run(&mut agent, &format!("(module {})", quoted(&path))).is_err());
+    assert_eq!(agent.env_paths(), env_paths);
+    assert_eq!(agent.module_env(&path), None);
+    assert!(agent.module_envs(&path).is_empty());
+
+    // Once fixed, the module gets the env name the failed load never claimed.
+    fs::write(&path, "(def broken-a 1)\n").unwrap();
+    let env = run(&mut agent, &format!("(module {})", quoted(&path))).unwrap();
+    let env = Node::try_from(env).unwrap().local();
+    assert_eq!(agent.module_env(&path), Some(env));
+    assert!(agent
+        .env_paths()
+        .iter()
+        .any(|(node, path)| *node == env && path.as_std_path().ends_with("broken.env")));
+}
+
+#[test]
+fn module_failure_trace() {
+    let (mut agent, _manager) = common::setup().unwrap();
+    let files = Files::new(
+        "module_test_failure_trace",
+        &[("helper.aml", "(def helper (lambda (x) (car x)))\n(helper 1)\n")],
+    );
+    let path = files.path("helper.aml");
+
+    // The error's continuation points into the failed module's env.
+    let err = run(&mut agent, &format!("(module {})", quoted(&path))).unwrap_err();
+    let mut trace = Vec::new();
+    agent.write_trace(&mut trace, &err).unwrap();
+    let trace = String::from_utf8(trace).unwrap();
+    assert!(trace.contains("--TRACE--"), "{}", trace);
+    assert!(trace.contains("helper"), "{}", trace);
+    assert_eq!(agent.module_env(&path), None);
+}
@@ -0,0 +1,207 @@
+#![cfg(feature = "cli")]
+
+mod common;
+
+use std::fs;
+
+use amlang::agent::history::now;
+use amlang::agent::{HistoryEntry, HistorySession};
+use amlang::env::LocalNode;
+use amlang::prelude::*;
+use amlang::token::cli_stream::{ReplCommand, ReplCommands};
+
+
+fn command(line: &str) -> ReplCommand {
+    ReplCommand::parse(line).unwrap().unwrap()
+}
+
+#[test]
+fn inspection() {
+    let (mut agent, mut manager) = common::setup().unwrap();
+    let mut commands = ReplCommands::new(&mut manager, ".", Default::default());
+
+    let envs = commands.run(&mut agent, command(":envs")).unwrap();
+    let lang_env = agent.find_env("lang.env").unwrap();
+    assert!(envs.iter().any(|(entry, _)| {
+        let (env, path) = break_sexp!(entry.clone() => (Node, LangPath)).unwrap();
+        env == Node::new(LocalNode::default(), lang_env) && path.as_std_path().ends_with("lang.env")
+    }));
+
+    let dchain = commands.run(&mut agent, command(":dchain")).unwrap();
+    let expected = agent
+        .designation_chain()
+        .iter()
+        .map(|node| Sexp::from(*node))
+        .collect::<Vec<_>>();
+    assert_eq!(dchain, expected.into());
+
+    let car = agent
+        .resolve_name(&"car".to_symbol_or_panic(policy_base))
+        .unwrap();
+    let description = commands.run(&mut agent, command(":describe car")).unwrap();
+    let (node, structure, _triples) =
+        break_sexp!(description => (Node, HeapSexp, HeapSexp)).unwrap();
+    assert_eq!(node, car);
+    assert_eq!(*structure, agent.concretize(car).unwrap());
+
+    let result = commands.run(&mut agent, command(":describe not-defined"));
+    assert!(result.is_err());
+    // Raw references to nodes which don't exist fail rather than panic.
+    let result = commands.run(&mut agent, command(":describe ^4^99999"));
+    assert!(result.is_err());
+}
+
+#[test]
+fn env_jump() {
+    let (mut agent, mut manager) = common::setup().unwrap();
+    let mut commands = ReplCommands::new(&mut manager, ".", Default::default());
+
+    let lang_env = agent.find_env("lang.env").unwrap();
+    let pos = commands.run(&mut agent, command(":env lang.env")).unwrap();
+    assert_eq!(pos, Node::new(lang_env, LocalNode::default()).into());
+    assert_eq!(agent.pos().env(), lang_env);
+
+    let result = commands.run(&mut agent, command(":env missing.env"));
+    let err = result.as_ref().unwrap_err().kind().reify(&agent);
+    let (_, kind, _) = break_sexp!(err => (Node, Node; remainder)).unwrap();
+    assert_eq!(kind, context_node!(invalid_argument, agent.error_context()));
+    assert_eq!(agent.pos().env(), lang_env);
+}
+
+#[test]
+fn trace_and_load() {
+    let (mut agent, mut manager) = common::setup().unwrap();
+    let mut commands = ReplCommands::new(&mut manager, ".", Default::default());
+
+    assert!(commands.trace());
+    let result = commands.run(&mut agent, command(":trace off")).unwrap();
+    assert_eq!(result, false.into());
+    assert!(!commands.trace());
+
+    let path = std::env::temp_dir().join(format!("repl_test_{}.am", std::process::id()));
+    fs::write(&path, "(def loaded-val 4)\n(+ loaded-val\n   1)\n").unwrap();
+    let line = format!(":load {}", path.to_string_lossy());
+    let result = commands.run(&mut agent, command(&line));
+    fs::remove_file(&path).unwrap();
+    assert_eq!(result.unwrap(), 5.into());
+
+    let result = commands.run(&mut agent, command(":load /no/such/file.am"));
+    assert!(result.is_err());
+}
+
+#[test]
+fn save() {
+    let (mut agent, mut manager) = common::setup().unwrap();
+    let dir = std::env::temp_dir().join(format!("repl_test_save_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let mut commands = ReplCommands::new(&mut manager, &dir, ["lang.env"].into());
+
+    let result = commands.run(&mut agent, command(":save"));
+    let saved = |path: &str| dir.join(path).exists();
+    let (meta, working, lang) = (saved("meta.env"), saved("working.env"), saved("lang.env"));
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(result.unwrap(), LangPath::from(dir.as_path()).into());
+    assert!(meta);
+    assert!(working);
+    // Blacklisted.
+    assert!(!lang);
+}
+
+#[test]
+fn history() {
+    let (mut agent, mut manager) = common::setup().unwrap();
+    let history_env = agent.find_env("history.env").unwrap();
+
+    let start = now();
+    let session = HistorySession::start(&mut agent).unwrap();
+    let session_node = session.node();
+    let mut commands =
+        ReplCommands::new(&mut manager, ".", Default::default()).with_history(session);
+
+    let inputs = ["(def hist-val 4)", "(+ hist-val 1)", "(car 1)"];
+    for input in inputs {
+        let sexp = Sexp::parse_with(input, policy_base).unwrap();
+        let _ = commands.eval(&mut agent, sexp);
+    }
+    // Commands aren't recorded.
+    commands.run(&mut agent, command(":trace off")).unwrap();
+    let recalled = commands.run(&mut agent, command(":history")).unwrap();
+    let expected = inputs
+        .iter()
+        .map(|input| Sexp::parse_with(input, policy_base).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(recalled, expected.into());
+
+    let entries = HistoryEntry::since(&agent, start).unwrap();
+    assert_eq!(entries.len(), 3);
+    for (i, (entry, input)) in entries.iter().zip(inputs).enumerate() {
+        assert_eq!(entry.node.env(), history_env);
+        assert_eq!(entry.input, Sexp::parse_with(input, policy_base).unwrap());
+        assert_eq!(entry.session, session_node);
+        assert_eq!(entry.seq, i);
+    }
+    assert_eq!(entries[1].outcome, Ok(5.into()));
+    let (_, kind, _) =
+        break_sexp!(entries[2].outcome.clone().unwrap_err() => (Node, Node; remainder)).unwrap();
+    assert_eq!(kind, context_node!(invalid_argument, agent.error_context()));
+
+    let hist_val = agent
+        .resolve_name(&"hist-val".to_symbol_or_panic(policy_base))
+        .unwrap();
+    let touching = entries
+        .iter()
+        .filter(|entry| entry.touches(&agent, hist_val))
+        .count();
+    assert_eq!(touching, 2);
+
+    // Also queryable from Amlang.
+    let query = format!("(history-entry (car (history-entries {})))", start);
+    let table = agent
+        .interpret(Sexp::parse_with(&query, policy_base).unwrap())
+        .unwrap();
+    assert_eq!(table, entries[0].to_table().into());
+    let query = format!(
+        "(history-touches (car (history-entries {})) 'hist-val)",
+        start
+    );
+    let touches = agent
+        .interpret(Sexp::parse_with(&query, policy_base).unwrap())
+        .unwrap();
+    assert_eq!(touches, true.into());
+}
+
+#[test]
+fn history_seeding() {
+    let (mut agent, mut manager) = common::setup().unwrap();
+    let history_env = agent.find_env("history.env").unwrap();
+
+    let session = HistorySession::start(&mut agent).unwrap();
+    let mut commands =
+        ReplCommands::new(&mut manager, ".", Default::default()).with_history(session);
+    let inputs = [r#"'"two\nlines""#, r#"'"a \"quote\"""#, "(car '(1))"];
+    for input in inputs {
+        let sexp = Sexp::parse_with(input, policy_base).unwrap();
+        commands.eval(&mut agent, sexp).unwrap();
+    }
+
+    let entries = HistoryEntry::last(&agent, 2).unwrap();
+    assert_eq!(entries.len(), 2);
+    // Entries link to the inputs as interpreted rather than copies.
+    let env = agent.access_env(history_env).unwrap();
+    for entry in &entries {
+        let copies = env
+            .all_nodes()
+            .into_iter()
+            .filter(|node| env.entry(*node).owned().as_ref() == Some(&entry.input))
+            .count();
+        assert_eq!(copies, 1);
+    }
+
+    // As the REPL seeds its line history.
+    let lines = entries
+        .iter()
+        .map(|entry| manager.serialize_structure(&entry.input).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(lines, inputs[1..]);
+}
@@ -0,0 +1,215 @@
+use std::convert::TryFrom;
+use std::fs;
+
+use amlang::agent::env_sources::embedded_env;
+use amlang::agent::EnvSources;
+use amlang::prelude::*;
+use amlang::runtime::BaseInterpreter;
+
+
+fn symbol(s: &str) -> Symbol {
+    s.to_symbol_or_panic(policy_base)
+}
+
+fn interpret(runtime: &mut Runtime, s: &str) -> Result<Sexp, Error> {
+    let sexp = Sexp::parse_with(s, policy_base).unwrap();
+    runtime.agent_mut().interpret(sexp)
+}
+
+fn temp_dir(name: &str) -> std::path::PathBuf {
+    let dir = std::env::temp_dir().join(format!("runtime_test_{}_{}", name, std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+
+#[test]
+fn isolated_env_dir() {
+    let dir = temp_dir("isolated");
+
+    // Saved on drop by default.
+    {
+        let mut runtime = Runtime::builder().env_dir(&dir).build().unwrap();
+        assert_eq!(runtime.root(), Some(dir.as_path()));
+        assert!(interpret(&mut runtime, "(def kept 4)").is_ok());
+        let working_env = runtime.agent().find_env("working.env").unwrap();
+        assert_eq!(runtime.agent().pos().env(), working_env);
+    }
+    assert!(dir.join("meta.env").exists());
+    assert!(dir.join("working.env").exists());
+
+    // Saved state is restored, unless reset.
+    let mut runtime = Runtime::builder()
+        .env_dir(&dir)
+        .save_on_drop(false)
+        .build()
+        .unwrap();
+    assert_eq!(interpret(&mut runtime, "kept").unwrap(), 4.into());
+    assert!(interpret(&mut runtime, "(def dropped 5)").is_ok());
+    drop(runtime);
+    let runtime = Runtime::builder().env_dir(&dir).build().unwrap();
+    assert!(runtime.agent().resolve_name(&symbol("dropped")).is_err());
+    drop(runtime);
+
+    let runtime = Runtime::builder()
+        .env_dir(&dir)
+        .reset(true)
+        .interpreter(BaseInterpreter::Null)
+        .build()
+        .unwrap();
+    assert!(runtime.agent().resolve_name(&symbol("kept")).is_err());
+    // Amlang nodes are designated regardless.
+    assert!(runtime.agent().resolve_name(&symbol("car")).is_ok());
+    let (mut agent, _manager) = runtime.into_parts();
+    let sexp = Sexp::parse_with("(+ 1 2)", policy_base).unwrap();
+    let compiled = agent.interpret(sexp).unwrap();
+    assert!(Procedure::try_from(compiled).is_ok());
+    assert!(!dir.join("working.env").exists());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn in_memory() {
+    let dir = temp_dir("in_memory");
+    {
+        let mut runtime = Runtime::builder().env_dir(&dir).build().unwrap();
+        assert!(interpret(&mut runtime, "(def kept 4)").is_ok());
+        runtime.save().unwrap();
+    }
+    let working = fs::read_to_string(dir.join("working.env")).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let mut seeded = Runtime::builder()
+        .sources(EnvSources::in_memory().with_env("working.env", working))
+        .build()
+        .unwrap();
+    let mut empty = Runtime::builder()
+        .sources(EnvSources::in_memory())
+        .build()
+        .unwrap();
+    assert_eq!(interpret(&mut seeded, "kept").unwrap(), 4.into());
+    assert!(empty.agent().resolve_name(&symbol("kept")).is_err());
+
+    // Runtimes are independent of one another.
+    assert!(interpret(&mut empty, "(def added 5)").is_ok());
+    assert!(seeded.agent().resolve_name(&symbol("added")).is_err());
+
+    assert_eq!(empty.root(), None);
+    assert!(empty.save().is_err());
+}
+
+#[test]
+fn default_sources() {
+    // Neither reads nor writes the source tree's envs/.
+    let mut runtime = Runtime::builder().build().unwrap();
+    assert_eq!(runtime.root(), None);
+    assert_eq!(interpret(&mut runtime, "(+ 1 2)").unwrap(), 3.into());
+    assert!(interpret(&mut runtime, "(def kept 4)").is_ok());
+    assert!(runtime.save().is_err());
+}
+
+#[test]
+fn root_run() {
+    let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("envs");
+    let runtime = Runtime::builder()
+        .root_run()
+        .save_on_drop(false)
+        .build()
+        .unwrap();
+    assert_eq!(runtime.root(), Some(root.as_path()));
+    drop(runtime);
+
+    // Upstream envs are never reset, however the root is given.
+    let link = temp_dir("root_run").join("envs");
+    std::os::unix::fs::symlink(&root, &link).unwrap();
+    assert!(Runtime::builder().root_run().reset(true).build().is_err());
+    for dir in [
+        root.clone(),
+        "envs".into(),
+        root.join("..").join("envs"),
+        link.clone(),
+    ] {
+        assert!(Runtime::builder()
+            .env_dir(&dir)
+            .reset(true)
+            .build()
+            .is_err());
+    }
+    // Nor are roots which can't be told apart.
+    let missing = link.with_file_name("missing");
+    assert!(Runtime::builder()
+        .env_dir(&missing)
+        .reset(true)
+        .build()
+        .is_err());
+    fs::remove_dir_all(link.parent().unwrap()).unwrap();
+    assert!(root.join("meta.env").exists());
+    assert!(root.join("lang.env").exists());
+}
+
+#[test]
+fn env_readers() {
+    let meta = fs::File::open(concat!(env!("CARGO_MANIFEST_DIR"), "/envs/meta.env")).unwrap();
+    let sources = EnvSources::in_memory()
+        .with_env_reader("meta.env", meta)
+        .unwrap();
+    let mut runtime = Runtime::builder().sources(sources).build().unwrap();
+    assert_eq!(interpret(&mut runtime, "(+ 1 2)").unwrap(), 3.into());
+
+    let sources = EnvSources::in_memory().with_env("lang.env", "");
+    assert!(Runtime::builder().sources(sources).build().is_err());
+}
+
+#[test]
+fn upstream_envs() {
+    let lang = fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/envs/lang.env")).unwrap();
+    assert_eq!(embedded_env("lang.env"), Some(lang.as_str()));
+    assert_eq!(embedded_env("working.env"), None);
+
+    // Development mode reads upstream envs from disk instead, falling back
+    // to the embedded copies.
+    let dir = temp_dir("upstream");
+    let sources = EnvSources::in_memory().with_upstream_dir(&dir);
+    assert!(Runtime::builder().sources(sources).build().is_ok());
+    fs::write(dir.join("lang.env"), "").unwrap();
+    let sources = EnvSources::in_memory().with_upstream_dir(&dir);
+    assert!(Runtime::builder().sources(sources).build().is_err());
+    // Nor are upstream dirs reset when used as the root.
+    let name = dir.file_name().unwrap();
+    for upstream in [dir.clone(), dir.join("..").join(name)] {
+        let sources = EnvSources::at_root(&dir).with_upstream_dir(upstream);
+        assert!(Runtime::builder()
+            .sources(sources)
+            .reset(true)
+            .build()
+            .is_err());
+        assert!(dir.join("lang.env").exists());
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn configured_grammar() {
+    let grammar = SymbolGrammar {
+        special_initial: format!("{}#", SymbolGrammar::default().special_initial),
+        ..Default::default()
+    };
+    let mut runtime = Runtime::builder()
+        .sources(EnvSources::in_memory().with_grammar(grammar.clone()))
+        .build()
+        .unwrap();
+    let sexp = Sexp::parse_with_grammar("(def a#b 3)", policy_base, &grammar).unwrap();
+    assert!(runtime.agent_mut().interpret(sexp).is_ok());
+    assert_eq!(
+        runtime.agent().symbol_grammar().special_initial,
+        grammar.special_initial
+    );
+
+    // Grammars are per Runtime rather than per process.
+    let other = Runtime::builder()
+        .sources(EnvSources::in_memory())
+        .build()
+        .unwrap();
+    assert!(!other.agent().symbol_grammar().is_identifier("a#b"));
+}
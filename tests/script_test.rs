@@ -0,0 +1,83 @@
+mod common;
+
+use amlang::agent::script::{self, ARGS_NAME};
+use amlang::prelude::*;
+use amlang::stream::input::StringReader;
+
+
+fn run(agent: &mut Agent, source: &str) -> Result<Sexp, Error> {
+    script::run(agent, "<test>", StringReader::new(source))
+}
+
+#[test]
+fn last_result() {
+    let (mut agent, _manager) = common::setup().unwrap();
+
+    let result = run(&mut agent, "(def script-a 2)\n(+ script-a\n   3)\n").unwrap();
+    assert_eq!(result, 5.into());
+    assert_eq!(run(&mut agent, "").unwrap(), Sexp::default());
+}
+
+#[test]
+fn stops_at_error() {
+    let (mut agent, _manager) = common::setup().unwrap();
+
+    let result = run(&mut agent, "(def script-b 1)\n(car 1)\n(def script-c 1)\n");
+    let err = result.as_ref().unwrap_err().kind().reify(&agent);
+    let (_, kind, _) = break_sexp!(err => (Node, Node; remainder)).unwrap();
+    assert_eq!(kind, context_node!(invalid_argument, agent.error_context()));
+
+    let defined = |agent: &Agent, name: &str| {
+        agent
+            .resolve_name(&name.to_symbol_or_panic(policy_base))
+            .is_ok()
+    };
+    assert!(defined(&agent, "script-b"));
+    assert!(!defined(&agent, "script-c"));
+
+    assert!(run(&mut agent, "(car '(1)").is_err());
+}
+
+#[test]
+fn trace() {
+    let (mut agent, _manager) = common::setup().unwrap();
+
+    let err = run(&mut agent, "(def script-d (lambda () (car 1)))
+(script-d)
+").unwrap_err();
+    let pos = agent.pos();
+    let mut trace = Vec::new();
+    agent.write_trace(&mut trace, &err).unwrap();
+    let trace = String::from_utf8(trace).unwrap();
+    assert!(trace.contains("--TRACE--"), "{}", trace);
+    assert!(trace.contains("0)"), "{}", trace);
+    // The Agent's own state is left as it was.
+    assert_eq!(agent.pos(), pos);
+}
+
+#[test]
+fn args() {
+    let (mut agent, _manager) = common::setup().unwrap();
+
+    let node = script::bind_args(&mut agent, ["a", "b c"]).unwrap();
+    assert_eq!(
+        agent
+            .resolve_name(&ARGS_NAME.to_symbol_or_panic(policy_base))
+            .unwrap(),
+        node
+    );
+    let result = run(&mut agent, "(car (cdr script-args))").unwrap();
+    assert_eq!(result, LangString::new("b c").into());
+
+    // Rebinding replaces the previous args.
+    let rebound = script::bind_args(&mut agent, Vec::<String>::new()).unwrap();
+    assert_eq!(rebound, node);
+    assert_eq!(run(&mut agent, "script-args").unwrap(), Sexp::default());
+
+    // Unbound before saving, so that args aren't persisted.
+    script::bind_args(&mut agent, ["secret"]).unwrap();
+    script::unbind_args(&mut agent).unwrap();
+    let env = agent.access_env(node.env()).unwrap();
+    assert_eq!(env.entry(node.local()).owned(), None);
+    assert_eq!(script::bind_args(&mut agent, ["a"]).unwrap(), node);
+}